@@ -0,0 +1,15 @@
+#![no_main]
+
+use ai_cup_22::codegame::ServerMessage;
+use ai_cup_22::trans::Trans;
+use libfuzzer_sys::fuzz_target;
+
+// whatever the server sends, decoding it gives a message or an error and never panics or runs out of memory
+fuzz_target!(|data: &[u8]| {
+    let mut reader = data;
+    if let Ok(message) = ServerMessage::read_from(&mut reader) {
+        let mut bytes = vec![];
+        message.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, data[..data.len() - reader.len()]);
+    }
+});
@@ -0,0 +1,128 @@
+use std::f64::consts::FRAC_PI_2;
+use crate::model::*;
+use crate::{segment_circle_contact, MyStrategy};
+
+// samples over the target's possible positions when integrating the hit probability
+const HIT_PROBABILITY_SAMPLES: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct AimingConfig {
+    /// Shots less likely than this to hit aren't fired
+    pub min_hit_probability: f64,
+    /// Part of the reachable set an enemy is expected to actually use to dodge, nobody dodges perfectly
+    pub dodge_factor: f64,
+}
+
+impl Default for AimingConfig {
+    fn default() -> Self {
+        Self {
+            min_hit_probability: 0.1,
+            dodge_factor: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShotSolution {
+    /// Where the enemy is expected to be when the projectile gets there
+    pub aim_point: Vec2,
+    /// Seconds from now until the projectile reaches the aim point, aiming included
+    pub time_to_hit: f64,
+    pub hit_probability: f64,
+}
+
+/// Seconds after firing at which a projectile fired from `shooter` meets a target moving with constant velocity,
+/// the target keeps moving for `delay` seconds before the shot.
+pub fn intercept_time(shooter: Vec2, projectile_speed: f64, target: Vec2, target_velocity: Vec2, delay: f64) -> Option<f64> {
+    // |p + v t| = s t
+    let p = target + target_velocity * delay - shooter;
+    let a = target_velocity.dot_product(&target_velocity) - projectile_speed * projectile_speed;
+    let b = 2.0 * p.dot_product(&target_velocity);
+    let c = p.dot_product(&p);
+
+    if a.abs() < 1e-9 {
+        return if b < 0.0 { Some(-c / b) } else { None };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    [(-b - discriminant.sqrt()) / (2.0 * a), (-b + discriminant.sqrt()) / (2.0 * a)]
+        .into_iter()
+        .filter(|t| *t >= 0.0)
+        .min_by(f64::total_cmp)
+}
+
+/// How far off its predicted straight path a unit can get in `seconds` by accelerating away, turning around at most.
+pub fn reachable_deviation(seconds: f64, constants: &Constants) -> f64 {
+    let max_velocity_change = 2.0 * constants.max_unit_forward_speed;
+    let acceleration_time = max_velocity_change / constants.unit_acceleration;
+    if seconds <= acceleration_time {
+        constants.unit_acceleration * seconds * seconds / 2.0
+    } else {
+        constants.unit_acceleration * acceleration_time * acceleration_time / 2.0 + max_velocity_change * (seconds - acceleration_time)
+    }
+}
+
+/// Chance that a projectile spread uniformly over `offset ± spread_half_width` across the line of fire hits
+/// a target of `radius` spread uniformly over `± target_half_width`.
+pub fn lateral_hit_probability(offset: f64, spread_half_width: f64, target_half_width: f64, radius: f64) -> f64 {
+    let covered = |center: f64| {
+        if spread_half_width <= 0.0 {
+            return if (offset - center).abs() < radius { 1.0 } else { 0.0 };
+        }
+        let low = (center - radius).max(offset - spread_half_width);
+        let high = (center + radius).min(offset + spread_half_width);
+        (high - low).max(0.0) / (2.0 * spread_half_width)
+    };
+    if target_half_width <= 0.0 {
+        return covered(0.0);
+    }
+    (0..HIT_PROBABILITY_SAMPLES)
+        .map(|i| -target_half_width + (i as f64 + 0.5) / HIT_PROBABILITY_SAMPLES as f64 * 2.0 * target_half_width)
+        .map(covered)
+        .sum::<f64>() / HIT_PROBABILITY_SAMPLES as f64
+}
+
+impl MyStrategy {
+    /// Where the unit gets to in `seconds` keeping its velocity, stopping at the first obstacle in the way.
+    pub fn predicted_position(&self, unit: &Unit, seconds: f64) -> Vec2 {
+        let end = unit.position + unit.velocity * seconds;
+        let radius = self.constants.unit_radius;
+        self.obstacle_index.along_segment(unit.position, end, radius).iter()
+            .filter_map(|o| segment_circle_contact(unit.position, end, o.position, o.radius + radius))
+            .min_by(f64::total_cmp)
+            .map_or(end, |fraction| unit.position + (end - unit.position) * fraction)
+    }
+
+    /// Where to aim at the enemy and how likely a shot fired in the unit's current direction is to hit.
+    pub fn aim_at(&self, enemy: &Unit, unit: &Unit) -> Option<ShotSolution> {
+        let weapon = unit.get_weapon(&self.constants)?;
+        let remaining_aim_time = weapon.aim_time * (1.0 - unit.aim);
+        let flight_time = intercept_time(unit.position, weapon.projectile_speed, enemy.position, enemy.velocity, remaining_aim_time)
+            .filter(|t| *t <= weapon.projectile_life_time)?;
+        let time_to_hit = remaining_aim_time + flight_time;
+        let aim_point = self.predicted_position(enemy, time_to_hit);
+
+        let to_target = aim_point - unit.position;
+        let distance = to_target.length();
+        // acos rounding gives NaN for parallel vectors
+        let direction_error = Some(unit.direction.angle_with(&to_target))
+            .filter(|angle| !angle.is_nan())
+            .map_or(0.0, |angle| angle.min(FRAC_PI_2));
+        let offset = distance * direction_error.sin();
+        let spread_half_width = distance * (weapon.spread.to_radians() / 2.0).tan();
+        let target_half_width = reachable_deviation(time_to_hit, &self.constants) * self.params.aiming.dodge_factor;
+
+        Some(ShotSolution {
+            aim_point,
+            time_to_hit,
+            hit_probability: lateral_hit_probability(offset, spread_half_width, target_half_width, self.constants.unit_radius),
+        })
+    }
+
+    /// Point to turn to for hitting the enemy, its current position if it can't be caught up with.
+    pub fn fire_target(&self, enemy: &Unit, unit: &Unit) -> Vec2 {
+        self.aim_at(enemy, unit).map_or(enemy.position, |shot| shot.aim_point)
+    }
+}
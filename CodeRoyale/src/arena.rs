@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use crate::codegame::{ClientMessage, ServerMessage};
+use crate::model::*;
+use crate::{idle_debug_state, BehaviourConfig, BehaviourTree, DebugInterface, DebugLayers, MlpEvaluator, MyStrategy, ObstacleGrid, ReplayWriter, SimGame, StrategyParams, TimeBudgetConfig};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Preset {
+    Round1,
+    Round2,
+    Finals,
+}
+
+impl Preset {
+    fn file_stem(&self) -> &'static str {
+        match self {
+            Preset::Round1 => "round1",
+            Preset::Round2 => "round2",
+            Preset::Finals => "finals",
+        }
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round1" => Ok(Preset::Round1),
+            "round2" => Ok(Preset::Round2),
+            "finals" => Ok(Preset::Finals),
+            _ => Err(format!("unknown preset {:?}, expected round1, round2 or finals", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArenaConfig {
+    pub preset: Preset,
+    /// Where `common.json` and the preset files are read from
+    pub presets_dir: PathBuf,
+    pub players: usize,
+    pub seed: u64,
+    pub max_ticks: i32,
+    pub initial_zone_radius: f64,
+    /// Behaviours for player 0, everybody else plays the default ones
+    pub challenger_behaviours: Option<BehaviourConfig>,
+    /// Directory with `MlpEvaluator` weights for player 0, everybody else uses the heuristic evaluator
+    pub challenger_model: Option<PathBuf>,
+    /// Parameters for player 0, everybody else plays the default ones
+    pub challenger_params: Option<StrategyParams>,
+    /// Print player 0's timings at the end of every game
+    pub profile: bool,
+    /// Layers player 0 draws into the recorded replay, as if the app was open
+    pub debug_layers: Option<DebugLayers>,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            preset: Preset::Round2,
+            presets_dir: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/localrunner/presets")),
+            players: 4,
+            seed: 0,
+            max_ticks: 10_000,
+            initial_zone_radius: 100.0,
+            challenger_behaviours: None,
+            challenger_model: None,
+            challenger_params: None,
+            profile: false,
+            debug_layers: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TeamResult {
+    pub player_id: i32,
+    pub place: i32,
+    pub kills: i32,
+    pub damage: f64,
+    pub score: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchResult {
+    pub seed: u64,
+    pub ticks: i32,
+    pub teams: Vec<TeamResult>,
+}
+
+/// In-process stand-in for the network client: answers server messages the same way `Runner::run` does.
+pub struct LocalClient {
+    strategy: Option<MyStrategy>,
+    behaviours: BehaviourConfig,
+    time_budget: TimeBudgetConfig,
+    model: Option<PathBuf>,
+    params: StrategyParams,
+    debug_layers: Option<DebugLayers>,
+}
+
+impl LocalClient {
+    pub fn new() -> Self {
+        Self::with_behaviours(BehaviourConfig::default())
+    }
+
+    pub fn with_behaviours(behaviours: BehaviourConfig) -> Self {
+        Self {
+            strategy: None,
+            behaviours,
+            time_budget: TimeBudgetConfig::reproducible(),
+            model: None,
+            params: StrategyParams::default(),
+            debug_layers: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: Option<PathBuf>) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_params(mut self, params: StrategyParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_time_budget(mut self, time_budget: TimeBudgetConfig) -> Self {
+        self.time_budget = time_budget;
+        self
+    }
+
+    pub fn with_debug_layers(mut self, layers: Option<DebugLayers>) -> Self {
+        self.debug_layers = layers;
+        self
+    }
+
+    /// Answers the message the way `Runner` would, `debug` stands in for the app when there is one.
+    pub fn handle(&mut self, message: ServerMessage, debug: Option<&mut DebugInterface>) -> Option<ClientMessage> {
+        match message {
+            ServerMessage::UpdateConstants { constants } => {
+                let behaviours = BehaviourTree::from_config(&self.behaviours).expect("Invalid behaviours");
+                let evaluator = self.model.as_ref()
+                    .map(|dir| MlpEvaluator::load(dir, &constants).expect("Can't load model"));
+                let mut strategy = MyStrategy::with_behaviours(constants, behaviours)
+                    .with_time_budget(self.time_budget.clone())
+                    .with_params(self.params.clone());
+                if let Some(evaluator) = evaluator {
+                    strategy = strategy.with_evaluator(Rc::new(evaluator));
+                }
+                if let Some(layers) = self.debug_layers.as_ref() {
+                    strategy.debug_console.layers = layers.clone();
+                }
+                self.strategy = Some(strategy);
+                None
+            }
+            ServerMessage::GetOrder { player_view, .. } => {
+                let order = self.strategy.as_mut().expect("GetOrder before UpdateConstants").get_order(&player_view, debug);
+                Some(ClientMessage::OrderMessage { order })
+            }
+            ServerMessage::Finish {} => {
+                if let Some(strategy) = self.strategy.as_mut() {
+                    strategy.finish();
+                }
+                None
+            }
+            ServerMessage::DebugUpdate { displayed_tick } => {
+                if let (Some(strategy), Some(debug)) = (self.strategy.as_mut(), debug) {
+                    strategy.debug_update(displayed_tick, debug);
+                }
+                Some(ClientMessage::DebugUpdateDone {})
+            }
+        }
+    }
+}
+
+impl Default for LocalClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Arena {
+    pub constants: Constants,
+    pub game: SimGame,
+    obstacles: ObstacleGrid,
+    pub max_ticks: i32,
+    seed: u64,
+    clients: Vec<LocalClient>,
+    /// Records what player 0 gets and answers, in the same format as `Runner` replays
+    recorder: Option<ReplayWriter>,
+    /// Whether player 0 draws into the recording
+    debug: bool,
+}
+
+impl Arena {
+    pub fn new(config: &ArenaConfig) -> Self {
+        let rules = ArenaRules::load(&config.presets_dir, config.preset).expect("Can't read presets");
+        let constants = arena_constants(config, &rules);
+        let obstacles = ObstacleGrid::new(&constants);
+        let mut game = SimGame::new_match(&constants, &obstacles, config.players, config.seed);
+        scatter_loot(&mut game, &constants, &obstacles, &rules);
+        Self {
+            constants,
+            game,
+            obstacles,
+            max_ticks: config.max_ticks,
+            seed: config.seed,
+            clients: (0..config.players).map(|player_id| match (player_id, &config.challenger_behaviours) {
+                (0, Some(behaviours)) => LocalClient::with_behaviours(behaviours.clone()),
+                _ => LocalClient::new(),
+            }
+                .with_model(config.challenger_model.clone().filter(|_| player_id == 0))
+                .with_params(config.challenger_params.clone().filter(|_| player_id == 0).unwrap_or_default())
+                .with_time_budget(TimeBudgetConfig {
+                    report_on_finish: player_id == 0 && config.profile,
+                    ..TimeBudgetConfig::reproducible()
+                })
+                .with_debug_layers(config.debug_layers.clone().filter(|_| player_id == 0))
+            ).collect(),
+            recorder: None,
+            debug: config.debug_layers.is_some(),
+        }
+    }
+
+    pub fn with_recorder(mut self, recorder: ReplayWriter) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    fn send(&mut self, player_id: usize, message: ServerMessage) -> Option<ClientMessage> {
+        if player_id != 0 {
+            return self.clients[player_id].handle(message, None);
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_server(&message).expect("Can't record replay");
+        }
+        let response = match self.recorder.as_mut() {
+            Some(recorder) if self.debug => {
+                let mut frame = vec![];
+                let state = idle_debug_state(None, self.game.zone.current_center);
+                let mut debug = DebugInterface::headless(&mut frame, state).recorded_by(recorder);
+                self.clients[player_id].handle(message, Some(&mut debug))
+            }
+            _ => self.clients[player_id].handle(message, None),
+        };
+        if let (Some(recorder), Some(response)) = (self.recorder.as_mut(), response.as_ref()) {
+            recorder.record_client(response).expect("Can't record replay");
+        }
+        response
+    }
+
+    fn debug_available(&self, player_id: usize) -> bool {
+        player_id == 0 && self.debug && self.recorder.is_some()
+    }
+
+    pub fn run(mut self) -> MatchResult {
+        for player_id in 0..self.clients.len() {
+            self.send(player_id, ServerMessage::UpdateConstants { constants: self.constants.clone() });
+        }
+
+        while !self.game.is_finished() && self.game.current_tick < self.max_ticks {
+            self.step();
+        }
+
+        for player_id in 0..self.clients.len() {
+            self.send(player_id, ServerMessage::Finish {});
+        }
+
+        self.result()
+    }
+
+    pub fn step(&mut self) {
+        let mut orders = HashMap::new();
+        for player_id in self.game.alive_players() {
+            let player_view = self.game.player_view(player_id, &self.constants);
+            let debug_available = self.debug_available(player_id as usize);
+            let message = ServerMessage::GetOrder { player_view, debug_available };
+            if let Some(ClientMessage::OrderMessage { order }) = self.send(player_id as usize, message) {
+                orders.insert(player_id, order);
+            }
+            if debug_available {
+                self.send(player_id as usize, ServerMessage::DebugUpdate { displayed_tick: self.game.current_tick });
+            }
+        }
+        self.game.tick(&self.constants, &self.obstacles, &orders);
+    }
+
+    pub fn result(&self) -> MatchResult {
+        MatchResult {
+            seed: self.seed,
+            ticks: self.game.current_tick,
+            teams: self.game.players.iter().map(|p| TeamResult {
+                player_id: p.id,
+                place: p.place,
+                kills: p.kills,
+                damage: p.damage,
+                score: p.score,
+            }).collect(),
+        }
+    }
+}
+
+/// `localrunner/presets/common.json`, what every preset shares.
+#[derive(Deserialize)]
+struct CommonRules {
+    ticks_per_second: f64,
+    zone_speed: f64,
+    zone_damage_per_second: f64,
+    spawn_collision_damage_per_second: f64,
+    obstacles: Vec<ObstacleKind>,
+    looting_time: f64,
+    unit_radius: f64,
+    unit_health: f64,
+    max_shield: f64,
+    health_regeneration_per_second: f64,
+    health_regeneration_delay: f64,
+    spawn_shield: f64,
+    field_of_view: f64,
+    view_distance: f64,
+    rotation_speed: f64,
+    spawn_movement_speed: f64,
+    max_unit_forward_speed: f64,
+    max_unit_backward_speed: f64,
+    unit_acceleration: f64,
+    weapons: Vec<WeaponRules>,
+    max_shield_potions_in_inventory: i32,
+    shield_potion_loot_base_density: f64,
+    shield_potions_per_loot: i32,
+    shield_per_potion: f64,
+    shield_potion_use_time: f64,
+    sounds: Vec<SoundRules>,
+    steps_sound_type_index: Option<i32>,
+    steps_sound_travel_distance: f64,
+}
+
+#[derive(Deserialize)]
+struct ObstacleKind {
+    radius: f64,
+    can_see_through: bool,
+    can_shoot_through: bool,
+    spawn_weight: f64,
+}
+
+#[derive(Deserialize)]
+struct WeaponRules {
+    name: String,
+    rounds_per_second: f64,
+    spread: f64,
+    ammo_per_loot: i32,
+    aim_time: f64,
+    aim_field_of_view: f64,
+    aim_rotation_speed: f64,
+    aim_movement_speed_modifier: f64,
+    projectile_speed: f64,
+    projectile_damage: f64,
+    projectile_life_time: f64,
+    weapon_loot_base_density: f64,
+    ammo_loot_base_density: f64,
+    shot_sound_type_index: Option<i32>,
+    projectile_hit_sound_type_index: Option<i32>,
+    max_inventory_ammo: i32,
+}
+
+#[derive(Deserialize)]
+struct SoundRules {
+    name: String,
+    distance: f64,
+    offset: f64,
+}
+
+/// `localrunner/presets/<preset>.json`, the zone radius and bot settings in it are left to `ArenaConfig`.
+#[derive(Deserialize)]
+struct PresetRules {
+    team_size: i32,
+    spawn_time: f64,
+    obstacle_density: f64,
+    loot_multiplier: f64,
+    min_distance_between_obstacles: f64,
+    extra_lives: i32,
+    last_respawn_zone_radius: f64,
+    view_blocking: bool,
+    friendly_fire: bool,
+    kill_score: f64,
+    damage_score_multiplier: f64,
+    score_per_place: f64,
+    starting_weapon: Option<i32>,
+    starting_weapon_ammo: i32,
+}
+
+struct ArenaRules {
+    common: CommonRules,
+    preset: PresetRules,
+}
+
+impl ArenaRules {
+    fn load(dir: &Path, preset: Preset) -> std::io::Result<Self> {
+        fn read<T: DeserializeOwned>(path: PathBuf) -> std::io::Result<T> {
+            let text = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&text).map_err(|e| std::io::Error::other(format!("{}: {}", path.display(), e)))
+        }
+        Ok(Self {
+            common: read(dir.join("common.json"))?,
+            preset: read(dir.join(format!("{}.json", preset.file_stem())))?,
+        })
+    }
+}
+
+// loot densities in the presets are given per this much area
+const LOOT_DENSITY_AREA: f64 = 100_000.0;
+
+fn arena_constants(config: &ArenaConfig, rules: &ArenaRules) -> Constants {
+    let (common, preset) = (&rules.common, &rules.preset);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    Constants {
+        ticks_per_second: common.ticks_per_second,
+        team_size: preset.team_size,
+        initial_zone_radius: config.initial_zone_radius,
+        zone_speed: common.zone_speed,
+        zone_damage_per_second: common.zone_damage_per_second,
+        spawn_time: preset.spawn_time,
+        spawn_collision_damage_per_second: common.spawn_collision_damage_per_second,
+        looting_time: common.looting_time,
+        bot_players: 0,
+        unit_radius: common.unit_radius,
+        unit_health: common.unit_health,
+        health_regeneration_per_second: common.health_regeneration_per_second,
+        health_regeneration_delay: common.health_regeneration_delay,
+        max_shield: common.max_shield,
+        spawn_shield: common.spawn_shield,
+        extra_lives: preset.extra_lives,
+        last_respawn_zone_radius: preset.last_respawn_zone_radius,
+        field_of_view: common.field_of_view,
+        view_distance: common.view_distance,
+        view_blocking: preset.view_blocking,
+        rotation_speed: common.rotation_speed,
+        spawn_movement_speed: common.spawn_movement_speed,
+        max_unit_forward_speed: common.max_unit_forward_speed,
+        max_unit_backward_speed: common.max_unit_backward_speed,
+        unit_acceleration: common.unit_acceleration,
+        friendly_fire: preset.friendly_fire,
+        kill_score: preset.kill_score,
+        damage_score_multiplier: preset.damage_score_multiplier,
+        score_per_place: preset.score_per_place,
+        weapons: common.weapons.iter().map(|w| WeaponProperties {
+            name: w.name.clone(),
+            rounds_per_second: w.rounds_per_second,
+            spread: w.spread,
+            aim_time: w.aim_time,
+            aim_field_of_view: w.aim_field_of_view,
+            aim_rotation_speed: w.aim_rotation_speed,
+            aim_movement_speed_modifier: w.aim_movement_speed_modifier,
+            projectile_speed: w.projectile_speed,
+            projectile_damage: w.projectile_damage,
+            projectile_life_time: w.projectile_life_time,
+            shot_sound_type_index: w.shot_sound_type_index,
+            projectile_hit_sound_type_index: w.projectile_hit_sound_type_index,
+            max_inventory_ammo: w.max_inventory_ammo,
+        }).collect(),
+        starting_weapon: preset.starting_weapon,
+        starting_weapon_ammo: preset.starting_weapon_ammo,
+        max_shield_potions_in_inventory: common.max_shield_potions_in_inventory,
+        shield_per_potion: common.shield_per_potion,
+        shield_potion_use_time: common.shield_potion_use_time,
+        sounds: common.sounds.iter().map(|s| SoundProperties {
+            name: s.name.clone(),
+            distance: s.distance,
+            offset: s.offset,
+        }).collect(),
+        steps_sound_type_index: common.steps_sound_type_index,
+        steps_sound_travel_distance: common.steps_sound_travel_distance,
+        obstacles: generate_obstacles(&mut rng, &common.obstacles, config.initial_zone_radius, preset.obstacle_density, preset.min_distance_between_obstacles),
+    }
+}
+
+fn generate_obstacles(rng: &mut StdRng, kinds: &[ObstacleKind], zone_radius: f64, density: f64, min_distance: f64) -> Vec<Obstacle> {
+    let total_weight: f64 = kinds.iter().map(|k| k.spawn_weight).sum();
+    let avg_area: f64 = kinds.iter().map(|k| PI * k.radius * k.radius * k.spawn_weight).sum::<f64>() / total_weight;
+    let wanted = (density * PI * zone_radius * zone_radius / avg_area) as usize;
+
+    let mut obstacles: Vec<Obstacle> = vec![];
+    for _ in 0..wanted * 10 {
+        if obstacles.len() >= wanted {
+            break;
+        }
+        let mut pick = rng.gen_range(0.0..total_weight);
+        let kind = kinds.iter().find(|k| {
+            pick -= k.spawn_weight;
+            pick < 0.0
+        }).unwrap_or(&kinds[0]);
+        let (radius, can_see_through, can_shoot_through) = (kind.radius, kind.can_see_through, kind.can_shoot_through);
+
+        let position = Vec2::from_length_and_angle(zone_radius * rng.gen::<f64>().sqrt(), rng.gen_range(0.0..2.0 * PI));
+        let fits = obstacles.iter().all(|o| o.position.distance_to(&position) >= o.radius + radius + min_distance);
+        if fits {
+            obstacles.push(Obstacle {
+                id: obstacles.len() as i32,
+                position,
+                radius,
+                can_see_through,
+                can_shoot_through,
+            });
+        }
+    }
+    obstacles
+}
+
+fn scatter_loot(game: &mut SimGame, constants: &Constants, obstacles: &ObstacleGrid, rules: &ArenaRules) {
+    let area = PI * constants.initial_zone_radius * constants.initial_zone_radius;
+    let count = |base_density: f64| (base_density * rules.preset.loot_multiplier * area / LOOT_DENSITY_AREA).ceil() as usize;
+
+    for (type_index, weapon) in rules.common.weapons.iter().enumerate() {
+        let type_index = type_index as i32;
+        for _ in 0..count(weapon.weapon_loot_base_density) {
+            let position = game.random_point_in_zone(constants, obstacles);
+            game.spawn_loot(position, Item::Weapon { type_index });
+        }
+        for _ in 0..count(weapon.ammo_loot_base_density) {
+            let position = game.random_point_in_zone(constants, obstacles);
+            game.spawn_loot(position, Item::Ammo { weapon_type_index: type_index, amount: weapon.ammo_per_loot });
+        }
+    }
+    for _ in 0..count(rules.common.shield_potion_loot_base_density) {
+        let position = game.random_point_in_zone(constants, obstacles);
+        game.spawn_loot(position, Item::ShieldPotions { amount: rules.common.shield_potions_per_loot });
+    }
+}
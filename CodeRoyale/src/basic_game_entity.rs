@@ -1,4 +1,4 @@
-use ai_cup_22::model::*;
+use crate::model::*;
 
 pub trait BasicGameEntity {
     fn id(&self) -> i32;
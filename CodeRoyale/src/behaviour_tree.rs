@@ -0,0 +1,266 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use crate::model::*;
+use crate::{DebugInterface, MyStrategy, TraceEntry, TraceOutcome};
+use crate::logic::{ActionOrderOrder, Vec2Order};
+
+/// Order of behaviours used when no config file is given, same syntax as the config files.
+pub const DEFAULT_BEHAVIOURS: &str = "
+[velocity]
+selector = priority
+land_safely = 1
+avoid_projectiles = 1
+peek_and_shoot = 1
+move_out_of_fire_range = 1
+rotate_to_next_zone = 1
+retreat_to_recover = 1
+go_to_weapon = 1
+go_to_shield = 1
+go_to_ammo = 1
+close_in_on_an_enemy = 1
+keep_formation = 1
+continue_to_waypoint = 1
+go_closer_to_allies = 1
+go_to_somewhere_in_the_zone = 1
+
+[direction]
+selector = priority
+peek_target = 1
+hittable_enemy = 1
+
+[action]
+selector = priority
+shoot_at_target = 1
+pick_up_weapon = 1
+pick_up_shield = 1
+drink_shield = 1
+pick_up_ammo = 1
+share_supplies = 1
+";
+
+pub trait Scored {
+    fn score(&self) -> f64;
+}
+
+pub trait Behaviour<T> {
+    fn name(&self) -> &'static str;
+    fn run(&self, strategy: &mut MyStrategy, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<T>;
+}
+
+pub type BehaviourFn<T> = fn(&mut MyStrategy, &Unit, &Game, &mut Option<&mut DebugInterface>) -> Option<T>;
+
+/// Behaviour backed by one of the `velocity_*`/`direction_*`/`action_*` methods of `MyStrategy`.
+pub struct FnBehaviour<T> {
+    name: &'static str,
+    f: BehaviourFn<T>,
+}
+
+impl<T> FnBehaviour<T> {
+    pub fn new(name: &str, f: BehaviourFn<T>) -> Self {
+        Self { name: intern(name), f }
+    }
+}
+
+impl<T> Behaviour<T> for FnBehaviour<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn run(&self, strategy: &mut MyStrategy, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<T> {
+        (self.f)(strategy, unit, game, debug_interface)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selector {
+    /// First behaviour (in config order) that returns something wins
+    Priority,
+    /// Every behaviour runs, the highest weighted score wins.
+    /// Scores come from the situation, only behaviours carrying out a commitment (landing, finishing
+    /// a pickup, peeking from cover) score a flat value, and only their weights rank them.
+    Utility,
+}
+
+pub struct WeightedBehaviour<T> {
+    pub behaviour: Box<dyn Behaviour<T>>,
+    pub weight: f64,
+}
+
+pub struct BehaviourList<T> {
+    pub selector: Selector,
+    pub behaviours: Vec<WeightedBehaviour<T>>,
+}
+
+impl<T: Scored> BehaviourList<T> {
+    pub fn select(&self, strategy: &mut MyStrategy, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<T> {
+        let mut enabled = self.behaviours.iter().filter(|b| b.weight > 0.0);
+        let mut run = |b: &WeightedBehaviour<T>, strategy: &mut MyStrategy| {
+            strategy.take_rejection();
+            let started_at = Instant::now();
+            let order = b.behaviour.run(strategy, unit, game, debug_interface);
+            strategy.time_budget.record(b.behaviour.name(), started_at.elapsed());
+            if strategy.debug_console.tracing {
+                let outcome = match order.as_ref() {
+                    Some(order) => TraceOutcome::Proposed(order.score()),
+                    None => TraceOutcome::Rejected(strategy.take_rejection()),
+                };
+                strategy.debug_console.entries.push(TraceEntry { behaviour: b.behaviour.name().to_string(), outcome });
+            }
+            order
+        };
+        match self.selector {
+            Selector::Priority => {
+                let order = enabled.by_ref().find_map(|b| run(b, strategy));
+                if strategy.debug_console.tracing {
+                    strategy.debug_console.entries.extend(enabled.map(|b| TraceEntry {
+                        behaviour: b.behaviour.name().to_string(),
+                        outcome: TraceOutcome::NotReached,
+                    }));
+                }
+                order
+            }
+            Selector::Utility => {
+                let mut best: Option<(f64, T)> = None;
+                for b in enabled {
+                    if let Some(order) = run(b, strategy) {
+                        let utility = order.score() * b.weight;
+                        if best.as_ref().is_none_or(|(best_utility, _)| utility > *best_utility) {
+                            best = Some((utility, order));
+                        }
+                    }
+                }
+                best.map(|(_, order)| order)
+            }
+        }
+    }
+}
+
+pub struct BehaviourTree {
+    pub velocity: BehaviourList<Vec2Order>,
+    pub direction: BehaviourList<Vec2Order>,
+    pub action: BehaviourList<ActionOrderOrder>,
+}
+
+impl BehaviourTree {
+    pub fn from_config(config: &BehaviourConfig) -> Result<Self, String> {
+        Ok(Self {
+            velocity: build_list(&config.velocity, MyStrategy::velocity_behaviour)?,
+            direction: build_list(&config.direction, MyStrategy::direction_behaviour)?,
+            action: build_list(&config.action, MyStrategy::action_behaviour)?,
+        })
+    }
+}
+
+impl Default for BehaviourTree {
+    fn default() -> Self {
+        Self::from_config(&BehaviourConfig::default()).expect("default behaviours must be valid")
+    }
+}
+
+/// The same `&'static str` for every equal name, so timings can be keyed by it.
+/// Only names of existing behaviours get here, so what's leaked is bounded.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+    match names.get(name) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+fn build_list<T: 'static>(section: &SectionConfig, lookup: fn(&str) -> Option<BehaviourFn<T>>) -> Result<BehaviourList<T>, String> {
+    let behaviours = section.behaviours.iter()
+        .map(|(name, weight)| {
+            let f = lookup(name).ok_or_else(|| format!("unknown behaviour {:?}", name))?;
+            Ok(WeightedBehaviour { behaviour: Box::new(FnBehaviour::new(name, f)) as Box<dyn Behaviour<T>>, weight: *weight })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(BehaviourList { selector: section.selector, behaviours })
+}
+
+#[derive(Clone, Debug)]
+pub struct SectionConfig {
+    pub selector: Selector,
+    /// Behaviour names with their weights, in priority order; weight 0 disables a behaviour
+    pub behaviours: Vec<(String, f64)>,
+}
+
+impl SectionConfig {
+    fn new() -> Self {
+        Self { selector: Selector::Priority, behaviours: vec![] }
+    }
+}
+
+/// Behaviour layout read from an ini-like file with `[velocity]`, `[direction]` and `[action]` sections.
+#[derive(Clone, Debug)]
+pub struct BehaviourConfig {
+    pub velocity: SectionConfig,
+    pub direction: SectionConfig,
+    pub action: SectionConfig,
+}
+
+impl BehaviourConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        text.parse().map_err(std::io::Error::other)
+    }
+}
+
+impl Default for BehaviourConfig {
+    fn default() -> Self {
+        DEFAULT_BEHAVIOURS.parse().expect("default behaviours must parse")
+    }
+}
+
+impl std::str::FromStr for BehaviourConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Self {
+            velocity: SectionConfig::new(),
+            direction: SectionConfig::new(),
+            action: SectionConfig::new(),
+        };
+        let mut section: Option<&mut SectionConfig> = None;
+
+        for (line_idx, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", line_idx + 1, message);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(match name.trim() {
+                    "velocity" => &mut config.velocity,
+                    "direction" => &mut config.direction,
+                    "action" => &mut config.action,
+                    other => return Err(error(format!("unknown section {:?}", other))),
+                });
+                continue;
+            }
+
+            let current = section.as_mut().ok_or_else(|| error("expected a section header".to_string()))?;
+            let (key, value) = line.split_once('=').ok_or_else(|| error(format!("expected `name = value`, got {:?}", line)))?;
+            let (key, value) = (key.trim(), value.trim());
+            if key == "selector" {
+                current.selector = match value {
+                    "priority" => Selector::Priority,
+                    "utility" => Selector::Utility,
+                    other => return Err(error(format!("unknown selector {:?}", other))),
+                };
+            } else {
+                let weight = value.parse().map_err(|_| error(format!("can't parse weight {:?}", value)))?;
+                current.behaviours.push((key.to_string(), weight));
+            }
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,74 @@
+use ai_cup_22::*;
+
+struct Args {
+    config: ArenaConfig,
+    games: u64,
+    /// Where to record player 0's games, suffixed with the seed when playing more than one
+    record_path: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut config = ArenaConfig::default();
+        let mut games = 1;
+        let mut record_path = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            let value = args.next().unwrap_or_else(|| panic!("Missing value for {}", flag));
+            match flag.as_str() {
+                "--preset" => config.preset = value.parse().unwrap_or_else(|e| panic!("{}", e)),
+                "--presets-dir" => config.presets_dir = value.into(),
+                "--players" => config.players = value.parse().expect("Can't parse players"),
+                "--seed" => config.seed = value.parse().expect("Can't parse seed"),
+                "--max-ticks" => config.max_ticks = value.parse().expect("Can't parse max ticks"),
+                "--zone-radius" => config.initial_zone_radius = value.parse().expect("Can't parse zone radius"),
+                "--games" => games = value.parse().expect("Can't parse games"),
+                "--record" => record_path = Some(value),
+                "--debug-layers" => config.debug_layers = Some(DebugLayers::only(&value)),
+                "--profile" => config.profile = value.parse().expect("Can't parse profile, expected true or false"),
+                "--params" => config.challenger_params = Some(StrategyParams::from_file(&value).expect("Can't read params")),
+                "--model" => config.challenger_model = Some(value.into()),
+                "--behaviours" => config.challenger_behaviours = Some(BehaviourConfig::from_file(&value).expect("Can't read behaviours")),
+                _ => panic!("Unknown argument {}", flag),
+            }
+        }
+        Self { config, games, record_path }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut totals = vec![(0.0, 0, 0.0, 0.0); args.config.players];
+
+    for game_idx in 0..args.games {
+        let mut config = args.config.clone();
+        config.seed = args.config.seed + game_idx;
+        let mut arena = Arena::new(&config);
+        if let Some(path) = args.record_path.as_ref() {
+            let path = if args.games > 1 { format!("{}.{}", path, config.seed) } else { path.clone() };
+            arena = arena.with_recorder(ReplayWriter::create(path).expect("Can't create replay file"));
+        }
+        let result = arena.run();
+
+        println!("game {} (seed {}, {} ticks)", game_idx + 1, result.seed, result.ticks);
+        println!("{:>6} {:>6} {:>6} {:>10} {:>10}", "team", "place", "kills", "damage", "score");
+        for team in result.teams.iter() {
+            println!("{:>6} {:>6} {:>6} {:>10.1} {:>10.1}", team.player_id, team.place, team.kills, team.damage, team.score);
+            let total = &mut totals[team.player_id as usize];
+            total.0 += team.place as f64;
+            total.1 += team.kills;
+            total.2 += team.damage;
+            total.3 += team.score;
+        }
+        println!();
+    }
+
+    if args.games > 1 {
+        let n = args.games as f64;
+        println!("average over {} games", args.games);
+        println!("{:>6} {:>6} {:>6} {:>10} {:>10}", "team", "place", "kills", "damage", "score");
+        for (player_id, (place, kills, damage, score)) in totals.iter().enumerate() {
+            println!("{:>6} {:>6.2} {:>6.2} {:>10.1} {:>10.1}", player_id, place / n, *kills as f64 / n, damage / n, score / n);
+        }
+    }
+}
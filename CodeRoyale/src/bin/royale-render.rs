@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+use ai_cup_22::*;
+
+struct Args {
+    path: String,
+    config: RenderConfig,
+    /// Page to scrub through the frames in
+    html: Option<PathBuf>,
+    /// Directory to write every frame to as `<tick>.svg`
+    svg_dir: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut path = None;
+        let mut config = RenderConfig::default();
+        let mut html = None;
+        let mut svg_dir = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().unwrap_or_else(|| panic!("Missing value for {}", arg));
+            match arg.as_str() {
+                "--from" => config.from_tick = value().parse().expect("Can't parse tick"),
+                "--to" => config.to_tick = value().parse().expect("Can't parse tick"),
+                "--unit" => config.unit = Some(value().parse().expect("Can't parse unit id")),
+                "--view-radius" => config.view_radius = value().parse().expect("Can't parse view radius"),
+                "--html" => html = Some(value().into()),
+                "--svg-dir" => svg_dir = Some(value().into()),
+                _ => path = Some(arg),
+            }
+        }
+        let usage = "Usage: royale-render <replay file> [--html page.html] [--svg-dir dir] [--from N] [--to N] [--unit id] [--view-radius R]";
+        if html.is_none() && svg_dir.is_none() {
+            panic!("{}", usage);
+        }
+        Self { path: path.expect(usage), config, html, svg_dir }
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let frames = render_replay(&args.path, &args.config)?;
+    if let Some(dir) = args.svg_dir.as_ref() {
+        std::fs::create_dir_all(dir)?;
+        for (tick, svg) in frames.iter() {
+            std::fs::write(dir.join(format!("{}.svg", tick)), svg)?;
+        }
+        println!("wrote {} frames to {}", frames.len(), dir.display());
+    }
+    if let Some(path) = args.html.as_ref() {
+        std::fs::write(path, html_scrubber(&frames))?;
+        println!("wrote {} frames to {}", frames.len(), path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use ai_cup_22::*;
+
+struct Args {
+    path: String,
+    until_tick: Option<i32>,
+    /// Horizons in ticks to check `Simulator` predictions for
+    validate: Option<Vec<usize>>,
+    /// Directory to write per-tick unit features, orders and the outcome to, as `.npy` files, instead of playing
+    export_features: Option<String>,
+    /// What the strategy was playing with, read from the same variables as when the game was recorded
+    behaviours: BehaviourConfig,
+    params: StrategyParams,
+    model: Option<std::path::PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut path = None;
+        let mut until_tick = None;
+        let mut validate = None;
+        let mut export_features = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--until-tick" => {
+                    let value = args.next().expect("Missing value for --until-tick");
+                    until_tick = Some(value.parse().expect("Can't parse tick"));
+                }
+                "--validate" => {
+                    let value = args.next().expect("Missing value for --validate");
+                    validate = Some(value.split(',').map(|h| h.trim().parse().expect("Can't parse horizon")).collect());
+                }
+                "--export-features" => {
+                    export_features = Some(args.next().expect("Missing value for --export-features"));
+                }
+                _ => path = Some(arg),
+            }
+        }
+        let behaviours = std::env::var("ROYALE_BEHAVIOURS")
+            .map_or_else(|_| BehaviourConfig::default(), |path| BehaviourConfig::from_file(path).expect("Can't read behaviours"));
+        let params = std::env::var("ROYALE_PARAMS")
+            .map_or_else(|_| StrategyParams::default(), |path| StrategyParams::from_file(path).expect("Can't read params"));
+        let model = std::env::var_os("ROYALE_MODEL").map(std::path::PathBuf::from);
+        Self {
+            path: path.expect("Usage: royale-replay <replay file> [--until-tick N] [--validate 1,5,10] [--export-features dir]"),
+            until_tick,
+            validate,
+            export_features,
+            behaviours,
+            params,
+            model,
+        }
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    if let Some(dir) = args.export_features.as_ref() {
+        let exporter = FeatureExporter::from_replay(&args.path)?;
+        exporter.write(dir)?;
+        println!("exported features of {} unit ticks to {}", exporter.rows(), dir);
+        return Ok(());
+    }
+    let mut playback = Playback::open(&args.path)?
+        .with_behaviours(args.behaviours.clone())
+        .with_params(args.params.clone())
+        .with_model(args.model.clone());
+    if let Some(horizons) = args.validate.clone() {
+        playback = playback.with_validation(horizons);
+    }
+    let last_tick = playback.run(args.until_tick)?;
+
+    println!("played {} ticks, last tick {}", playback.stats.ticks, last_tick);
+    if playback.stats.mismatched_ticks.is_empty() {
+        println!("all orders match the recording");
+    } else {
+        println!("{} ticks with different orders: {:?}", playback.stats.mismatched_ticks.len(), playback.stats.mismatched_ticks);
+    }
+    if let Some(validator) = playback.validator() {
+        println!();
+        print!("{}", validator.report());
+    }
+    Ok(())
+}
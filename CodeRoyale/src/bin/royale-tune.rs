@@ -0,0 +1,50 @@
+use ai_cup_22::*;
+
+struct Args {
+    config: TunerConfig,
+    /// Where to write the tuned parameters
+    out: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut config = TunerConfig::default();
+        let mut out = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            let value = args.next().unwrap_or_else(|| panic!("Missing value for {}", flag));
+            match flag.as_str() {
+                "--preset" => config.arena.preset = value.parse().unwrap_or_else(|e| panic!("{}", e)),
+                "--players" => config.arena.players = value.parse().expect("Can't parse players"),
+                "--seed" => config.arena.seed = value.parse().expect("Can't parse seed"),
+                "--max-ticks" => config.arena.max_ticks = value.parse().expect("Can't parse max ticks"),
+                "--iterations" => config.iterations = value.parse().expect("Can't parse iterations"),
+                "--games" => config.games_per_evaluation = value.parse().expect("Can't parse games"),
+                "--final-games" => config.final_games = value.parse().expect("Can't parse final games"),
+                "--step" => config.step = value.parse().expect("Can't parse step"),
+                "--perturbation" => config.perturbation = value.parse().expect("Can't parse perturbation"),
+                "--threads" => config.threads = value.parse().expect("Can't parse threads"),
+                "--params" => config.params = value.split(',').map(|p| p.trim().to_string()).collect(),
+                "--start" => config.start = StrategyParams::from_file(&value).expect("Can't read params"),
+                "--out" => out = Some(value),
+                _ => panic!("Unknown argument {}", flag),
+            }
+        }
+        Self { config, out }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut tuner = Tuner::new(args.config).unwrap_or_else(|e| panic!("{}", e));
+    let report = tuner.run(|k, result| {
+        println!("iteration {:>3}: {:>8.1} / {:<8.1}", k + 1, result.plus_score, result.minus_score);
+    });
+
+    println!();
+    print!("{}", report);
+    if let Some(path) = args.out {
+        std::fs::write(&path, report.best.to_string()).expect("Can't write params");
+        println!("\nwritten to {}", path);
+    }
+}
@@ -1,7 +1,6 @@
 use std::collections::HashMap;
-use itertools::Itertools;
-use ai_cup_22::model::*;
-use crate::{BasicGameEntity, MyStrategy};
+use crate::model::*;
+use crate::{BasicGameEntity, MyStrategy, VisibilityMask};
 
 impl MyStrategy {
     pub(crate) fn rebuild_indexes(&mut self, game: &Game) {
@@ -15,9 +14,12 @@ impl MyStrategy {
         self.enemy_units = game.units.iter().filter(|u| u.player_id != game.my_id).cloned().collect();
 
         self.current_tick = game.current_tick;
+        self.observed = VisibilityMask::new(&self.my_units, &self.constants);
 
         self.update_loot(game);
         self.update_projectiles(game);
+        self.update_enemy_beliefs(game);
+        self.update_dynamic_index();
     }
 
     fn update_loot(&mut self, game: &Game) {
@@ -37,12 +39,11 @@ impl MyStrategy {
     }
 
     fn update_seen_items<T: BasicGameEntity + Clone>(&self, source: &HashMap<i32, T>, new_items: &[T], current_tick: i32) -> HashMap<i32, T> {
-        let visibility_sectors = self.my_units.iter().map(|unit| self.unit_visibility_sector(unit)).collect_vec();
-        let item_by_id = source.iter().map(|(id, item)| (item.id(), item)).collect::<HashMap<_, _>>();
+        let item_by_id = source.values().map(|item| (item.id(), item)).collect::<HashMap<_, _>>();
         // prune items no longer there
         let mut seen_items: HashMap<i32, T> = source.iter()
-            .filter(|(id, item)| { // only currently visible loot
-                let visible_at_the_moment = visibility_sectors.iter().any(|sec| sec.cover_point(item.position()));
+            .filter(|(_id, item)| { // only currently visible loot
+                let visible_at_the_moment = self.observed.is_observed(item.position());
                 if visible_at_the_moment {
                     item_by_id.contains_key(&item.id())
                 } else {
@@ -66,67 +67,7 @@ impl MyStrategy {
         seen_items
     }
 
-    pub fn process_sounds(&mut self, game: &Game) {
-        let visibility_sectors = self.my_units.iter().map(|unit| self.unit_visibility_sector(unit)).collect_vec();
-
-        for sound in game.sounds.iter() {
-            let source = sound.position;
-            let visible_at_the_moment = visibility_sectors.iter().any(|sec| sec.cover_point(source));
-
-            if visible_at_the_moment {
-                continue
-            }
-
-            let id = self.gen_imaginary_id();
-            let unit_that_heard = self.my_units.iter().find(|u| u.id == sound.unit_id).unwrap();
-            match sound.type_index {
-                1 => {
-                    self.seen_projectiles.entry(id).or_insert(Projectile{
-                        id,
-                        weapon_type_index: 0,
-                        shooter_id: 0,
-                        shooter_player_id: 0,
-                        position: source,
-                        velocity: (unit_that_heard.position - source).clamp(30.0),
-                        life_time: 1.0,
-                        seen_on_tick: game.current_tick,
-                    });
-                },
-                2 => {
-                    self.seen_projectiles.entry(id).or_insert(Projectile{
-                        id,
-                        weapon_type_index: 1,
-                        shooter_id: 0,
-                        shooter_player_id: 0,
-                        position: source,
-                        velocity: (unit_that_heard.position - source).clamp(20.0),
-                        life_time: 1.0,
-                        seen_on_tick: game.current_tick,
-                    });
-                },
-                3 => {
-                    self.seen_projectiles.entry(id).or_insert(Projectile{
-                        id,
-                        weapon_type_index: 2,
-                        shooter_id: 0,
-                        shooter_player_id: 0,
-                        position: source,
-                        velocity: (unit_that_heard.position - source).clamp(40.0),
-                        life_time: 1.0,
-                        seen_on_tick: game.current_tick,
-                    });
-
-                },
-                // 4 => "wand hit",
-                // 5 => "staff hit",
-                // 6 => "bow hit",
-                _ => ()
-            };
-
-        }
-    }
-
-    fn gen_imaginary_id(&mut self) -> i32 {
+    pub(crate) fn gen_imaginary_id(&mut self) -> i32 {
         self.next_imaginary_id -= 1;
         self.next_imaginary_id
     }
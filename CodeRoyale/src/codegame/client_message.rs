@@ -47,7 +47,7 @@ impl trans::Trans for ClientMessage {
         }
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let tag = <i32 as trans::Trans>::read_from(reader)?;
         match tag {
             0 => {
@@ -70,9 +70,7 @@ impl trans::Trans for ClientMessage {
                 Ok(Self::RequestDebugState {
                 })
             }
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Unexpected tag {:?}", tag))),
+            _ => Err(trans::TransError::UnexpectedTag { type_name: "ClientMessage", tag }),
         }
     }
 }
\ No newline at end of file
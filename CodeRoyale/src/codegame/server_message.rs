@@ -55,7 +55,7 @@ impl trans::Trans for ServerMessage {
         }
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let tag = <i32 as trans::Trans>::read_from(reader)?;
         match tag {
             0 => {
@@ -82,9 +82,7 @@ impl trans::Trans for ServerMessage {
                     displayed_tick,
                 })
             }
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Unexpected tag {:?}", tag))),
+            _ => Err(trans::TransError::UnexpectedTag { type_name: "ServerMessage", tag }),
         }
     }
 }
\ No newline at end of file
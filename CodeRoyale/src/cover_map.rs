@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use itertools::Itertools;
+use crate::debugging::Color;
+use crate::model::*;
+use crate::{segment_intersects_circle, ActionOrderOrder, DebugInterface, MyStrategy, ObstacleGrid, PointIndex, SustainDecision, Vec2Order};
+
+// distance between cover points around the same obstacle
+const COVER_SPACING: f64 = 2.0;
+// cover points stand this far off the obstacle, so units don't scrape it
+const COVER_GAP: f64 = 0.2;
+// sideways steps tried when looking for a spot to peek from
+const PEEK_STEP: f64 = 0.25;
+// units only peek from cover this close
+const PEEK_COVER_DISTANCE: f64 = 6.0;
+// no peeking with less time than this to get into the next zone
+const PEEK_ZONE_SLACK: f64 = 5.0;
+
+/// A spot next to an obstacle bullets can't pass, hidden from whoever is on the other side of it.
+#[derive(Clone, Debug)]
+pub struct CoverPoint {
+    pub position: Vec2,
+    /// Index of the obstacle in `Constants::obstacles`
+    pub obstacle: usize,
+    /// Unit vector from the cover point to the obstacle, threats from around this direction are blocked
+    pub facing: Vec2,
+}
+
+/// Cover points around every obstacle that stops projectiles, built once per game.
+pub struct CoverMap {
+    points: Vec<CoverPoint>,
+    index: PointIndex,
+    obstacles: Vec<Obstacle>,
+    unit_radius: f64,
+}
+
+impl CoverMap {
+    pub fn new(constants: &Constants, obstacle_grid: &ObstacleGrid) -> Self {
+        let mut points = vec![];
+        for (idx, obstacle) in constants.obstacles.iter().enumerate().filter(|(_, o)| !o.can_shoot_through) {
+            let distance = obstacle.radius + constants.unit_radius + COVER_GAP;
+            let count = ((2.0 * PI * distance / COVER_SPACING).ceil() as usize).max(4);
+            for k in 0..count {
+                let offset = Vec2::from_length_and_angle(distance, 2.0 * PI * k as f64 / count as f64);
+                let position = obstacle.position + offset;
+                if position.length() > constants.initial_zone_radius
+                    || !obstacle_grid.near(position, constants.unit_radius).is_empty() {
+                    continue;
+                }
+                points.push(CoverPoint { position, obstacle: idx, facing: (offset * -1.0).normalize() });
+            }
+        }
+        let index = PointIndex::new(points.iter().enumerate().map(|(idx, p)| (idx as i32, p.position)));
+        Self { points, index, obstacles: constants.obstacles.clone(), unit_radius: constants.unit_radius }
+    }
+
+    pub fn points(&self) -> &[CoverPoint] {
+        &self.points
+    }
+
+    /// Cover points within `radius` of the point, closest first.
+    pub fn near(&self, point: Vec2, radius: f64) -> Vec<&CoverPoint> {
+        self.index.within(point, radius).into_iter()
+            .map(|idx| &self.points[idx as usize])
+            .sorted_by(|c1, c2| c1.position.distance_to(&point).total_cmp(&c2.position.distance_to(&point)))
+            .collect()
+    }
+
+    /// Whether the cover's obstacle hides the whole unit standing there from a shooter at `threat`.
+    pub fn shelters(&self, cover: &CoverPoint, threat: Vec2) -> bool {
+        // the obstacle is in front of the cover point, nothing behind or beside it gets blocked
+        if cover.facing.dot_product(&(threat - cover.position)) <= 0.0 {
+            return false;
+        }
+        let obstacle = &self.obstacles[cover.obstacle];
+        let side = Vec2::from_length_and_angle(self.unit_radius, (threat - cover.position).angle() + PI / 2.0);
+        [cover.position + side, cover.position - side].iter()
+            .all(|edge| segment_intersects_circle(threat, *edge, obstacle.position, obstacle.radius))
+    }
+
+    /// Closest spot to step out to from the cover for a clear shot at `target`, if one is a few steps to the side.
+    pub fn peek_position(&self, cover: &CoverPoint, target: Vec2, obstacle_grid: &ObstacleGrid) -> Option<Vec2> {
+        let obstacle = &self.obstacles[cover.obstacle];
+        let sideways = Vec2::from_length_and_angle(PEEK_STEP, (target - cover.position).angle() + PI / 2.0);
+        let steps = ((obstacle.radius + 2.0 * self.unit_radius) / PEEK_STEP).ceil() as usize;
+        (1..=steps)
+            .flat_map(|step| [cover.position + sideways * step as f64, cover.position - sideways * step as f64])
+            .find(|p| {
+                obstacle_grid.near(*p, self.unit_radius).is_empty()
+                    && !obstacle_grid.along_segment(*p, target, 0.0).iter().any(|o| !o.can_shoot_through)
+            })
+    }
+}
+
+/// Where a unit hides from an enemy and where it steps out to shoot it, picked by `plan_peeks`.
+#[derive(Clone, Debug)]
+pub struct PeekPlan {
+    pub enemy_id: i32,
+    pub cover: Vec2,
+    pub peek: Vec2,
+}
+
+impl MyStrategy {
+    pub(crate) fn plan_peeks(&mut self) {
+        let mut plans: HashMap<i32, PeekPlan> = HashMap::new();
+        for unit in self.my_units.iter() {
+            let fighting = self.sustain_plan.get(&unit.id).is_some_and(|plan| matches!(plan.decision, SustainDecision::Fight));
+            if !fighting || self.zone_slack(unit) < PEEK_ZONE_SLACK {
+                continue;
+            }
+            let Some(weapon) = unit.weapon.filter(|w| unit.ammo[*w as usize] > 0) else { continue };
+            let range = self.constants.weapons[weapon as usize].range();
+            // peeking at the target the team picked, or at the closest enemy when nobody has a clear shot yet
+            let target = self.targets.get(&unit.id).and_then(|id| self.units_by_id.get(id));
+            let Some(enemy) = target.or_else(|| {
+                self.enemies_within(unit.position, range + PEEK_COVER_DISTANCE).into_iter()
+                    .filter(|enemy| enemy.remaining_spawn_time.is_none())
+                    .min_by(|e1, e2| e1.position.distance_to(&unit.position).total_cmp(&e2.position.distance_to(&unit.position)))
+            }) else { continue };
+
+            let previous = self.peeks.get(&unit.id).filter(|p| p.enemy_id == enemy.id).map(|p| p.cover);
+            let taken = plans.values().map(|p| p.cover).collect_vec();
+            let plan = self.cover_map.near(unit.position, PEEK_COVER_DISTANCE).into_iter()
+                .filter(|cover| !taken.iter().any(|t| t.distance_to(&cover.position) < 2.0 * self.constants.unit_radius))
+                .filter(|cover| self.zone_forecast.time_until_outside(cover.position) > PEEK_ZONE_SLACK)
+                .filter(|cover| self.cover_map.shelters(cover, enemy.position))
+                .filter_map(|cover| {
+                    let peek = self.cover_map.peek_position(cover, enemy.position, &self.obstacle_index)
+                        .filter(|peek| peek.distance_to(&enemy.position) <= range)?;
+                    Some(PeekPlan { enemy_id: enemy.id, cover: cover.position, peek })
+                })
+                // sticking to the same cover, switching every tick gets nowhere
+                .min_by(|p1, p2| {
+                    let cost = |p: &PeekPlan| {
+                        let stay = if previous.is_some_and(|c| c.distance_to(&p.cover) < 1e-9) { 0.0 } else { 1.0 };
+                        p.cover.distance_to(&unit.position) + stay
+                    };
+                    cost(p1).total_cmp(&cost(p2))
+                });
+            if let Some(plan) = plan {
+                plans.insert(unit.id, plan);
+            }
+        }
+
+        for (unit_id, plan) in plans.iter() {
+            self.targets.entry(*unit_id).or_insert(plan.enemy_id);
+        }
+        self.peeks = plans;
+    }
+
+    /// Whether the unit hides behind cover from its target on purpose, aiming without a clear shot then makes sense.
+    pub fn is_peeking_at(&self, unit: &Unit, enemy_id: i32) -> bool {
+        self.peeks.get(&unit.id).is_some_and(|plan| plan.enemy_id == enemy_id)
+    }
+
+    pub(crate) fn velocity_peek_and_shoot(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let plan = self.peeks.get(&unit.id).or_else(|| self.reject("no cover to peek from"))?;
+
+        // stepping out slowed down by aiming takes a while, the weapon should be ready by the time it's done
+        let speed = unit.get_weapon(&self.constants)
+            .map_or(1.0, |w| w.aim_movement_speed_modifier) * self.constants.max_unit_forward_speed;
+        let step_out_ticks = (plan.cover.distance_to(&plan.peek) / speed * self.constants.ticks_per_second).ceil() as i32;
+        let reloaded = unit.next_shot_tick - self.current_tick <= step_out_ticks;
+
+        let (spot, description) = if unit.aim >= 1.0 && reloaded {
+            (plan.peek, format!("peeking at {}", plan.enemy_id))
+        } else if unit.aim >= 1.0 {
+            (plan.cover, format!("reloading in cover from {}", plan.enemy_id))
+        } else {
+            (plan.cover, format!("aiming in cover from {}", plan.enemy_id))
+        };
+        let to_spot = spot - unit.position;
+        Some(Vec2Order {
+            // arriving at the spot rather than running past it
+            vec: (to_spot * self.constants.ticks_per_second).clamp(self.constants.max_unit_forward_speed),
+            description: Some(description),
+            target: None,
+            score: 1.0,
+        })
+    }
+
+    pub(crate) fn direction_peek_target(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let plan = self.peeks.get(&unit.id).or_else(|| self.reject("no cover to peek from"))?;
+        let enemy = self.units_by_id.get(&plan.enemy_id).or_else(|| self.reject("enemy out of sight"))?;
+        // turned already to where the enemy is seen from the peek spot, no turning once out there
+        let from_peek = Unit { position: plan.peek, ..unit.clone() };
+        Some(Vec2Order {
+            vec: self.fire_target(enemy, &from_peek) - plan.peek,
+            description: Some(format!("turning to {} from cover", enemy.id)),
+            target: None,
+            score: 1.0,
+        })
+    }
+
+    /// Aiming from behind cover, the shot goes off once the unit has stepped out.
+    pub(crate) fn aim_from_cover(&self, unit: &Unit, enemy: &Unit) -> Option<ActionOrderOrder> {
+        if !self.is_peeking_at(unit, enemy.id) {
+            return None;
+        }
+        Some(ActionOrderOrder {
+            action_order: ActionOrder::Aim { shoot: false },
+            description: Some(format!("aiming at {} from cover", enemy.id)),
+            score: 0.5,
+        })
+    }
+
+    pub fn visualize_cover(&self, debug_interface: &mut Option<&mut DebugInterface>) {
+        if let Some(debug) = debug_interface.as_mut() {
+            for cover in self.cover_map.points() {
+                debug.add_circle(cover.position, 0.2, Color::blue().a(0.3));
+            }
+            for plan in self.peeks.values() {
+                debug.add_ring(plan.cover, self.constants.unit_radius, 0.1, Color::blue().a(0.8));
+                debug.add_segment(plan.cover, plan.peek, 0.1, Color::red().a(0.8));
+            }
+        }
+    }
+}
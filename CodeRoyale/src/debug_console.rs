@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use crate::debugging::{Color, DebugState};
+use crate::model::*;
+use crate::{DebugInterface, MyStrategy};
+
+// world units between lines of text drawn next to a unit
+const LINE_HEIGHT: f64 = 0.9;
+const TEXT_SIZE: f64 = 0.8;
+// where the list of layers goes relative to the camera center
+const LEGEND_OFFSET: Vec2 = Vec2 { x: -25.0, y: 15.0 };
+
+/// What gets drawn, every layer is switched on and off with its key.
+#[derive(Clone, Debug)]
+pub struct DebugLayers {
+    pub vision: bool,
+    pub weapon_ranges: bool,
+    pub rollouts: bool,
+    pub loot: bool,
+    pub beliefs: bool,
+    pub cover: bool,
+    pub trace: bool,
+}
+
+impl Default for DebugLayers {
+    fn default() -> Self {
+        Self {
+            vision: false,
+            weapon_ranges: true,
+            rollouts: false,
+            loot: false,
+            beliefs: true,
+            cover: false,
+            trace: true,
+        }
+    }
+}
+
+impl DebugLayers {
+    /// Key toggling each layer with the layer's name, in the order they're listed on screen.
+    pub const KEYS: [(char, &'static str); 7] = [
+        ('V', "vision sectors"),
+        ('R', "weapon ranges"),
+        ('S', "simulator rollouts"),
+        ('L', "loot assignments"),
+        ('B', "enemy beliefs"),
+        ('C', "cover and peeks"),
+        ('T', "decision trace"),
+    ];
+
+    /// Just the layers with the given keys on, like `"RBT"`.
+    pub fn only(keys: &str) -> Self {
+        let mut layers = Self { vision: false, weapon_ranges: false, rollouts: false, loot: false, beliefs: false, cover: false, trace: false };
+        for key in keys.chars() {
+            if let Some(layer) = layers.layer_mut(key.to_ascii_uppercase()) {
+                *layer = true;
+            }
+        }
+        layers
+    }
+
+    fn layer_mut(&mut self, key: char) -> Option<&mut bool> {
+        match key {
+            'V' => Some(&mut self.vision),
+            'R' => Some(&mut self.weapon_ranges),
+            'S' => Some(&mut self.rollouts),
+            'L' => Some(&mut self.loot),
+            'B' => Some(&mut self.beliefs),
+            'C' => Some(&mut self.cover),
+            'T' => Some(&mut self.trace),
+            _ => None,
+        }
+    }
+
+    fn layer(&self, key: char) -> bool {
+        match key {
+            'V' => self.vision,
+            'R' => self.weapon_ranges,
+            'S' => self.rollouts,
+            'L' => self.loot,
+            'B' => self.beliefs,
+            'C' => self.cover,
+            'T' => self.trace,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum TraceOutcome {
+    /// Returned an order, with its score
+    Proposed(f64),
+    /// Returned nothing, with the reason if the behaviour gave one
+    Rejected(Option<String>),
+    /// A priority selector had already picked an earlier behaviour
+    NotReached,
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceEntry {
+    pub behaviour: String,
+    pub outcome: TraceOutcome,
+}
+
+/// How the last tick's orders of a unit were picked.
+#[derive(Clone, Debug, Default)]
+pub struct DecisionTrace {
+    pub velocity: Vec<TraceEntry>,
+    pub direction: Vec<TraceEntry>,
+    pub action: Vec<TraceEntry>,
+    /// What the chosen velocity order turned into after routing, if anything was chosen
+    pub route: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct DebugConsole {
+    pub layers: DebugLayers,
+    /// Keys down on the previous debug update, a layer flips only when its key goes down
+    held_keys: HashSet<String>,
+    /// Whether decisions are traced this tick, only worth it when someone can look at them
+    pub(crate) tracing: bool,
+    /// Reason given by the behaviour that ran last, taken by the behaviour list right after
+    pub(crate) rejection: RefCell<Option<String>>,
+    /// Entries of the behaviour list being selected from
+    pub(crate) entries: Vec<TraceEntry>,
+    pub traces: HashMap<i32, DecisionTrace>,
+    /// Positions the movement search expects each unit to pass through
+    pub rollouts: HashMap<i32, Vec<Vec2>>,
+}
+
+/// Key letter of a renderer key name, which may come as "V" as well as "KeyV".
+fn key_letter(name: &str) -> Option<char> {
+    let name = name.strip_prefix("Key").unwrap_or(name);
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+impl MyStrategy {
+    /// Notes why a behaviour returns nothing, for the decision trace.
+    pub(crate) fn reject<T>(&self, reason: &str) -> Option<T> {
+        self.reject_with(|| reason.to_string())
+    }
+
+    /// Like `reject`, the reason is only put together when tracing.
+    pub(crate) fn reject_with<T>(&self, reason: impl FnOnce() -> String) -> Option<T> {
+        if self.debug_console.tracing {
+            *self.debug_console.rejection.borrow_mut() = Some(reason());
+        }
+        None
+    }
+
+    pub(crate) fn take_rejection(&self) -> Option<String> {
+        self.debug_console.rejection.borrow_mut().take()
+    }
+
+    /// Flips the layers whose keys just went down.
+    pub fn handle_debug_keys(&mut self, state: &DebugState) {
+        let pressed = state.pressed_keys.iter().cloned().collect::<HashSet<_>>();
+        for key in pressed.difference(&self.debug_console.held_keys) {
+            if let Some(layer) = key_letter(key).and_then(|letter| self.debug_console.layers.layer_mut(letter)) {
+                *layer = !*layer;
+            }
+        }
+        self.debug_console.held_keys = pressed;
+    }
+
+    pub fn show_debug_legend(&self, state: &DebugState, debug_interface: &mut DebugInterface) {
+        let top_left = state.camera.center + LEGEND_OFFSET;
+        for (line, (key, name)) in DebugLayers::KEYS.iter().enumerate() {
+            let on = self.debug_console.layers.layer(*key);
+            let color = if on { Color::green() } else { Color::red().a(0.6) };
+            let text = format!("[{}] {} {}", key, name, if on { "on" } else { "off" });
+            debug_interface.add_placed_text(top_left - Vec2 { x: 0.0, y: line as f64 * LINE_HEIGHT }, text, Vec2::zero(), TEXT_SIZE, color);
+        }
+    }
+
+    pub fn visualize_loot_plan(&self, debug_interface: &mut Option<&mut DebugInterface>) {
+        if let Some(debug) = debug_interface.as_mut() {
+            for (unit_id, assignment) in self.loot_plan.iter() {
+                let (Some(unit), Some(loot)) = (self.units_by_id.get(unit_id), self.seen_loot.get(&assignment.loot_id)) else { continue };
+                debug.add_segment(unit.position, loot.position, 0.1, Color::green().a(0.5));
+                debug.add_placed_text(loot.position, format!("{:.2} / {:.2}", assignment.value, assignment.utility), Vec2 { x: 0.5, y: -0.5 }, TEXT_SIZE, Color::green());
+            }
+        }
+    }
+
+    pub fn visualize_rollouts(&self, debug_interface: &mut Option<&mut DebugInterface>) {
+        if let Some(debug) = debug_interface.as_mut() {
+            for (unit_id, positions) in self.debug_console.rollouts.iter() {
+                let Some(unit) = self.units_by_id.get(unit_id) else { continue };
+                let vertices = std::iter::once(unit.position).chain(positions.iter().copied()).collect();
+                debug.add_poly_line(vertices, 0.1, Color::green().a(0.5));
+                for p in positions.iter() {
+                    debug.add_ring(*p, self.constants.unit_radius, 0.05, Color::green().a(0.5));
+                }
+            }
+        }
+    }
+
+    /// Every behaviour the unit went through last tick and what came of it.
+    pub fn show_decision_trace(&self, unit: &Unit, debug_interface: &mut DebugInterface) {
+        let Some(trace) = self.debug_console.traces.get(&unit.id) else { return };
+        let mut lines = vec![];
+        for (section, entries) in [("velocity", &trace.velocity), ("direction", &trace.direction), ("action", &trace.action)] {
+            lines.push((format!("[{}]", section), Color::blue()));
+            if entries.is_empty() {
+                lines.push(("  nothing ran".to_string(), Color::red().a(0.6)));
+            }
+            for entry in entries.iter() {
+                let (text, color) = match &entry.outcome {
+                    TraceOutcome::Proposed(score) => (format!("  {}: {:.2}", entry.behaviour, score), Color::green()),
+                    TraceOutcome::Rejected(Some(reason)) => (format!("  {}: {}", entry.behaviour, reason), Color::red()),
+                    TraceOutcome::Rejected(None) => (format!("  {}: nothing", entry.behaviour), Color::red()),
+                    TraceOutcome::NotReached => (format!("  {}: not reached", entry.behaviour), Color::red().a(0.4)),
+                };
+                lines.push((text, color));
+            }
+            if section == "velocity" {
+                if let Some(route) = trace.route.as_ref() {
+                    lines.push((format!("  routed: {}", route), Color::blue()));
+                }
+            }
+        }
+
+        let origin = unit.position + Vec2 { x: 2.0 * self.constants.unit_radius, y: 0.0 };
+        for (line, (text, color)) in lines.into_iter().enumerate() {
+            debug_interface.add_placed_text(origin - Vec2 { x: 0.0, y: line as f64 * LINE_HEIGHT }, text, Vec2::zero(), TEXT_SIZE, color);
+        }
+    }
+}
@@ -1,14 +1,61 @@
-use ai_cup_22::*;
+use std::collections::VecDeque;
+use crate::*;
+use crate::replay::ReplayWriter;
+
+// where the cursor is kept when nobody is at the app, so nothing counts as under it
+const FAR_AWAY: f64 = 1e9;
+
+/// What the app answers with when nobody is at it: no keys pressed and the cursor far from everything.
+pub fn idle_debug_state(locked_unit: Option<i32>, camera_center: model::Vec2) -> debugging::DebugState {
+    debugging::DebugState {
+        pressed_keys: vec![],
+        cursor_world_position: model::Vec2 { x: FAR_AWAY, y: FAR_AWAY },
+        locked_unit,
+        camera: debugging::Camera { center: camera_center, rotation: 0.0, attack: 0.0, fov: 0.0 },
+    }
+}
+
+enum Backend<'a> {
+    Server {
+        reader: &'a mut dyn std::io::Read,
+        writer: &'a mut dyn std::io::Write,
+    },
+    /// Keeps what gets drawn instead of sending it anywhere, state requests are answered in order and the last answer repeats
+    Headless {
+        frame: &'a mut Vec<debugging::DebugData>,
+        states: VecDeque<debugging::DebugState>,
+    },
+}
 
 pub struct DebugInterface<'a> {
-    reader: &'a mut dyn std::io::Read,
-    writer: &'a mut dyn std::io::Write,
+    backend: Backend<'a>,
+    recorder: Option<&'a mut ReplayWriter>,
 }
 
 #[allow(dead_code)]
 impl<'a> DebugInterface<'a> {
     pub fn new(reader: &'a mut dyn std::io::Read, writer: &'a mut dyn std::io::Write) -> Self {
-        Self { reader, writer }
+        Self::with_recorder(reader, writer, None)
+    }
+
+    pub fn with_recorder(reader: &'a mut dyn std::io::Read, writer: &'a mut dyn std::io::Write, recorder: Option<&'a mut ReplayWriter>) -> Self {
+        Self { backend: Backend::Server { reader, writer }, recorder }
+    }
+
+    /// Collects the added debug data into `frame`, `get_state` always answers with `state`.
+    pub fn headless(frame: &'a mut Vec<debugging::DebugData>, state: debugging::DebugState) -> Self {
+        Self::replayed(frame, vec![state])
+    }
+
+    /// Collects the added debug data into `frame`, `get_state` answers with the recorded states one after another.
+    pub fn replayed(frame: &'a mut Vec<debugging::DebugData>, states: Vec<debugging::DebugState>) -> Self {
+        Self { backend: Backend::Headless { frame, states: states.into() }, recorder: None }
+    }
+
+    /// Also records everything sent and every state received, the way `Runner` does.
+    pub fn recorded_by(mut self, recorder: &'a mut ReplayWriter) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 
     pub fn add_placed_text(&mut self, position: model::Vec2, text: String, alignment: model::Vec2, size: f64, color: debugging::Color) {
@@ -81,18 +128,49 @@ impl<'a> DebugInterface<'a> {
 
     pub fn send(&mut self, command: debugging::DebugCommand) {
         use trans::Trans;
-        codegame::ClientMessage::DebugMessage { command }
-            .write_to(self.writer)
-            .expect("Failed to write custom debug data");
-        self.writer.flush().expect("Failed to flush");
+        let message = codegame::ClientMessage::DebugMessage { command };
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_client(&message).expect("Failed to record custom debug data");
+        }
+        match &mut self.backend {
+            Backend::Server { writer, .. } => {
+                message
+                    .write_to(*writer)
+                    .expect("Failed to write custom debug data");
+                writer.flush().expect("Failed to flush");
+            }
+            Backend::Headless { frame, .. } => match message {
+                codegame::ClientMessage::DebugMessage { command: debugging::DebugCommand::Add { debug_data } } => frame.push(debug_data),
+                codegame::ClientMessage::DebugMessage { command: debugging::DebugCommand::Clear {} } => frame.clear(),
+                _ => {}
+            },
+        }
     }
 
     pub fn get_state(&mut self) -> debugging::DebugState {
         use trans::Trans;
-        codegame::ClientMessage::RequestDebugState {}
-            .write_to(self.writer)
-            .expect("Failed to write request debug state message");
-        self.writer.flush().expect("Failed to flush");
-        debugging::DebugState::read_from(self.reader).expect("Failed to read debug state")
+        let message = codegame::ClientMessage::RequestDebugState {};
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_client(&message).expect("Failed to record request debug state message");
+        }
+        let state = match &mut self.backend {
+            Backend::Server { reader, writer } => {
+                message
+                    .write_to(*writer)
+                    .expect("Failed to write request debug state message");
+                writer.flush().expect("Failed to flush");
+                debugging::DebugState::read_from(*reader).expect("Failed to read debug state")
+            }
+            Backend::Headless { states, .. } => match states.len() {
+                // a replay may hold fewer states than it asks for, answer like a client that does nothing
+                0 => idle_debug_state(None, model::Vec2::zero()),
+                1 => states[0].clone(),
+                _ => states.pop_front().unwrap(),
+            },
+        };
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_debug_state(&state).expect("Failed to record debug state");
+        }
+        state
     }
 }
\ No newline at end of file
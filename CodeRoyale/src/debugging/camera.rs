@@ -21,7 +21,7 @@ impl trans::Trans for Camera {
         self.fov.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let center: model::Vec2 = trans::Trans::read_from(reader)?;
         let rotation: f64 = trans::Trans::read_from(reader)?;
         let attack: f64 = trans::Trans::read_from(reader)?;
@@ -21,7 +21,7 @@ impl trans::Trans for Color {
         self.a.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let r: f64 = trans::Trans::read_from(reader)?;
         let g: f64 = trans::Trans::read_from(reader)?;
         let b: f64 = trans::Trans::read_from(reader)?;
@@ -15,7 +15,7 @@ impl trans::Trans for ColoredVertex {
         self.color.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let position: model::Vec2 = trans::Trans::read_from(reader)?;
         let color: debugging::Color = trans::Trans::read_from(reader)?;
         Ok(Self {
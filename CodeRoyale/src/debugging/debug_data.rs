@@ -290,7 +290,7 @@ impl trans::Trans for DebugData {
         }
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let tag = <i32 as trans::Trans>::read_from(reader)?;
         match tag {
             0 => {
@@ -439,9 +439,7 @@ impl trans::Trans for DebugData {
                     width,
                 })
             }
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Unexpected tag {:?}", tag))),
+            _ => Err(trans::TransError::UnexpectedTag { type_name: "DebugData", tag }),
         }
     }
 }
\ No newline at end of file
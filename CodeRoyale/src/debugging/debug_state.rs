@@ -21,7 +21,7 @@ impl trans::Trans for DebugState {
         self.camera.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let pressed_keys: Vec<String> = trans::Trans::read_from(reader)?;
         let cursor_world_position: model::Vec2 = trans::Trans::read_from(reader)?;
         let locked_unit: Option<i32> = trans::Trans::read_from(reader)?;
@@ -0,0 +1,158 @@
+use itertools::Itertools;
+use crate::model::*;
+use crate::MyStrategy;
+
+const MIN_CONFIDENCE: f64 = 0.1;
+const CONFIDENCE_DECAY_PER_TICK: f64 = 0.98;
+// an estimate that ended up in sight without the enemy being there is most likely wrong
+const CONFIDENCE_DECAY_WHEN_NOT_SEEN: f64 = 0.5;
+const HEARD_CONFIDENCE: f64 = 0.7;
+const DEAD_RECKONING_TICKS: i32 = 15;
+
+/// Estimate of where an enemy we can't see right now is.
+#[derive(Clone, Debug)]
+pub struct EnemyBelief {
+    /// Unit id if the enemy was seen, imaginary (negative) id if it was only heard
+    pub id: i32,
+    pub player_id: Option<i32>,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// The enemy is expected to be within this distance from `position`
+    pub radius: f64,
+    pub confidence: f64,
+    pub weapon: Option<i32>,
+    pub updated_on_tick: i32,
+}
+
+impl EnemyBelief {
+    pub fn from_unit(unit: &Unit, tick: i32) -> Self {
+        Self {
+            id: unit.id,
+            player_id: Some(unit.player_id),
+            position: unit.position,
+            velocity: unit.velocity,
+            radius: 0.0,
+            confidence: 1.0,
+            weapon: unit.weapon,
+            updated_on_tick: tick,
+        }
+    }
+
+    pub fn is_seen(&self, tick: i32) -> bool {
+        self.updated_on_tick == tick && self.radius == 0.0
+    }
+
+    /// Range of the enemy's weapon, the longest one if we don't know which it has.
+    pub fn weapon_range(&self, constants: &Constants) -> f64 {
+        match self.weapon {
+            Some(weapon_idx) => constants.weapons[weapon_idx as usize].range(),
+            None => constants.weapons.iter().map(|w| w.range()).fold(0.0, f64::max),
+        }
+    }
+
+    fn age(&mut self, tick: i32, constants: &Constants) {
+        let delta_time = 1.0 / constants.ticks_per_second;
+        if tick - self.updated_on_tick <= DEAD_RECKONING_TICKS {
+            self.position += self.velocity * delta_time;
+        }
+        self.radius += constants.max_unit_forward_speed * delta_time;
+        self.confidence *= CONFIDENCE_DECAY_PER_TICK;
+    }
+
+    fn merge_heard(&mut self, position: Vec2, radius: f64, weapon: Option<i32>, tick: i32) {
+        // inverse variance weighting of the two estimates
+        let w1 = 1.0 / (self.radius * self.radius).max(1e-6);
+        let w2 = 1.0 / (radius * radius).max(1e-6);
+        self.position = (self.position * w1 + position * w2) / (w1 + w2);
+        self.radius = self.radius.min(radius);
+        self.confidence = self.confidence.max(HEARD_CONFIDENCE);
+        self.velocity = Vec2::zero();
+        self.weapon = weapon.or(self.weapon);
+        self.updated_on_tick = tick;
+    }
+}
+
+impl MyStrategy {
+    pub(crate) fn update_enemy_beliefs(&mut self, game: &Game) {
+        let tick = game.current_tick;
+        let unit_radius = self.constants.unit_radius;
+
+        for belief in self.enemy_beliefs.values_mut() {
+            belief.age(tick, &self.constants);
+            if self.observed.is_circle_observed(belief.position, belief.radius.max(unit_radius)) {
+                // we look at the whole area and there is nobody
+                belief.confidence = 0.0;
+            } else if self.observed.is_observed(belief.position) {
+                belief.confidence *= CONFIDENCE_DECAY_WHEN_NOT_SEEN;
+            }
+        }
+
+        for enemy in self.enemy_units.iter() {
+            // whatever we only heard around here was probably this unit
+            self.enemy_beliefs.retain(|id, belief| {
+                *id >= 0 || belief.position.distance_to(&enemy.position) > belief.radius + unit_radius
+            });
+            self.enemy_beliefs.insert(enemy.id, EnemyBelief::from_unit(enemy, tick));
+        }
+
+        for sound in game.sounds.iter() {
+            self.process_sound(sound, tick);
+        }
+
+        let max_radius = self.constants.view_distance;
+        self.enemy_beliefs.retain(|_, belief| belief.confidence >= MIN_CONFIDENCE && belief.radius <= max_radius);
+    }
+
+    fn process_sound(&mut self, sound: &Sound, tick: i32) {
+        let weapon = self.constants.weapons.iter().position(|w| w.shot_sound_type_index == Some(sound.type_index));
+        let is_steps = self.constants.steps_sound_type_index == Some(sound.type_index);
+        if weapon.is_none() && !is_steps {
+            // hit sounds tell where a projectile landed, not where the shooter is
+            return;
+        }
+
+        let Some(listener) = self.units_by_id.get(&sound.unit_id) else { return };
+        let properties = &self.constants.sounds[sound.type_index as usize];
+        let radius = listener.position.distance_to(&sound.position) * properties.offset / (1.0 - properties.offset)
+            + self.constants.unit_radius;
+
+        // our own units and enemies in plain sight make noise too
+        let explained = self.my_units.iter().chain(self.enemy_units.iter())
+            .any(|u| u.position.distance_to(&sound.position) <= radius);
+        if explained {
+            return;
+        }
+
+        let matching = self.enemy_beliefs.values_mut()
+            .filter(|belief| !belief.is_seen(tick))
+            .filter(|belief| belief.position.distance_to(&sound.position) <= belief.radius + radius)
+            .min_by(|b1, b2| {
+                b1.position.distance_to(&sound.position).total_cmp(&b2.position.distance_to(&sound.position))
+            });
+
+        if let Some(belief) = matching {
+            belief.merge_heard(sound.position, radius, weapon.map(|w| w as i32), tick);
+        } else {
+            let id = self.gen_imaginary_id();
+            self.enemy_beliefs.insert(id, EnemyBelief {
+                id,
+                player_id: None,
+                position: sound.position,
+                velocity: Vec2::zero(),
+                radius,
+                confidence: HEARD_CONFIDENCE,
+                weapon: weapon.map(|w| w as i32),
+                updated_on_tick: tick,
+            });
+        }
+    }
+
+    /// Enemies we don't see at the moment but believe to be around, most confident first.
+    pub fn unseen_enemies(&self, min_confidence: f64) -> Vec<&EnemyBelief> {
+        self.enemy_beliefs.values()
+            .filter(|belief| !belief.is_seen(self.current_tick))
+            .filter(|belief| belief.confidence >= min_confidence)
+            .sorted_by(|b1, b2| b2.confidence.total_cmp(&b1.confidence))
+            .collect()
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Debug;
+use std::path::Path;
+use ndarray::{Array1, Array2};
+use ndarray_npy::read_npy;
+use crate::model::*;
+use crate::{enemy_feature_count, unit_feature_count, SimulationWeights, Simulator, UnitFeatures, EXPORTED_ENEMIES, EXPORTED_PROJECTILES, PROJECTILE_FEATURES};
+
+/// Scores where a `Simulator` rollout got to, planners keep the rollouts with the lowest cost.
+pub trait Evaluator: Debug {
+    fn cost(&self, simulator: &Simulator) -> f64;
+}
+
+/// Weighted sum of what the simulator tallied up along the rollout.
+#[derive(Clone, Debug, Default)]
+pub struct HeuristicEvaluator {
+    pub weights: SimulationWeights,
+}
+
+impl Evaluator for HeuristicEvaluator {
+    fn cost(&self, simulator: &Simulator) -> f64 {
+        simulator.result().cost(&self.weights)
+    }
+}
+
+/// Value of the simulated unit's end state from a small fully connected network, ReLU between layers,
+/// one output and the higher the better. The input is `UnitFeatures::flat`, normalizing it is up to the weights.
+#[derive(Clone, Debug)]
+pub struct MlpEvaluator {
+    /// Weights (inputs × outputs) and biases of every layer, first layer first
+    layers: Vec<(Array2<f32>, Array1<f32>)>,
+}
+
+impl MlpEvaluator {
+    /// Reads `w0.npy`, `b0.npy`, `w1.npy`, `b1.npy`... from the directory, a single layer makes a linear model.
+    pub fn load(dir: impl AsRef<Path>, constants: &Constants) -> std::io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut layers = vec![];
+        while dir.join(format!("w{}.npy", layers.len())).exists() {
+            let idx = layers.len();
+            let weights: Array2<f32> = read_npy(dir.join(format!("w{}.npy", idx))).map_err(std::io::Error::other)?;
+            let bias: Array1<f32> = read_npy(dir.join(format!("b{}.npy", idx))).map_err(std::io::Error::other)?;
+            layers.push((weights, bias));
+        }
+        Self::new(layers, constants)
+    }
+
+    pub fn new(layers: Vec<(Array2<f32>, Array1<f32>)>, constants: &Constants) -> std::io::Result<Self> {
+        let inputs = unit_feature_count(constants)
+            + EXPORTED_ENEMIES * enemy_feature_count(constants)
+            + EXPORTED_PROJECTILES * PROJECTILE_FEATURES;
+        if layers.is_empty() {
+            return Err(std::io::Error::other("model has no layers"));
+        }
+        let mut width = inputs;
+        for (idx, (weights, bias)) in layers.iter().enumerate() {
+            if weights.nrows() != width || bias.len() != weights.ncols() {
+                return Err(std::io::Error::other(format!(
+                    "layer {} is {:?} with {} biases, expected {} inputs", idx, weights.dim(), bias.len(), width,
+                )));
+            }
+            width = weights.ncols();
+        }
+        if width != 1 {
+            return Err(std::io::Error::other(format!("model has {} outputs, expected 1", width)));
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn value(&self, features: &UnitFeatures) -> f64 {
+        let mut x = Array1::from(features.flat());
+        for (idx, (weights, bias)) in self.layers.iter().enumerate() {
+            x = x.dot(weights) + bias;
+            if idx + 1 < self.layers.len() {
+                x.mapv_inplace(|v| v.max(0.0));
+            }
+        }
+        x[0] as f64
+    }
+}
+
+impl Evaluator for MlpEvaluator {
+    fn cost(&self, simulator: &Simulator) -> f64 {
+        // a rollout the unit doesn't live through is as bad as it gets
+        let Some(unit) = simulator.unit() else { return f64::INFINITY };
+        -self.value(&UnitFeatures::simulated(unit, simulator.game(), simulator.constants()))
+    }
+}
@@ -0,0 +1,335 @@
+use std::path::Path;
+use itertools::Itertools;
+use ndarray::{Array2, ArrayView2, ArrayView3};
+use ndarray_npy::write_npy;
+use crate::codegame::{ClientMessage, ServerMessage};
+use crate::model::*;
+use crate::{ReplayReader, ReplayRecord, SimGame, SimProjectile, SimUnit};
+
+// closest enemies and projectiles kept for every unit, the rest doesn't fit the tensor
+pub const EXPORTED_ENEMIES: usize = 5;
+pub const EXPORTED_PROJECTILES: usize = 8;
+// present, dx, dy, vx, vy, health, shield, aim, then the weapon one-hot
+const ENEMY_BASE_FEATURES: usize = 8;
+// present, dx, dy, vx, vy, life time, damage, shot by us
+pub const PROJECTILE_FEATURES: usize = 8;
+// health, shield, extra lives, aim, vx, vy, direction x and y, potions, ammo, zone edge, next zone edge,
+// zone center dx and dy, then the weapon one-hot
+const UNIT_BASE_FEATURES: usize = 14;
+// velocity x and y, direction x and y, then the action kind one-hot and the shoot flag
+const ACTION_KINDS: usize = 6;
+pub const ORDER_FEATURES: usize = 4 + ACTION_KINDS + 1;
+// final placement from 0 for the last to 1 for the winner, then the final score
+pub const OUTCOME_FEATURES: usize = 2;
+
+/// What unit features are read from, seen units and simulated ones alike.
+pub trait UnitState {
+    fn player_id(&self) -> i32;
+    fn position(&self) -> Vec2;
+    fn velocity(&self) -> Vec2;
+    fn direction(&self) -> Vec2;
+    fn health(&self) -> f64;
+    fn shield(&self) -> f64;
+    fn extra_lives(&self) -> i32;
+    fn aim(&self) -> f64;
+    fn weapon(&self) -> Option<i32>;
+    fn ammo(&self) -> &[i32];
+    fn shield_potions(&self) -> i32;
+    fn spawned(&self) -> bool;
+}
+
+/// What projectile features are read from, seen projectiles and simulated ones alike.
+pub trait ProjectileState {
+    fn position(&self) -> Vec2;
+    fn velocity(&self) -> Vec2;
+    fn life_time(&self) -> f64;
+    fn weapon_type_index(&self) -> i32;
+    fn shooter_player_id(&self) -> i32;
+}
+
+// units and projectiles share their field names with the simulated ones, the impls only forward them
+macro_rules! impl_unit_state {
+    ($($unit:ty),*) => {$(
+        impl UnitState for $unit {
+            fn player_id(&self) -> i32 { self.player_id }
+            fn position(&self) -> Vec2 { self.position }
+            fn velocity(&self) -> Vec2 { self.velocity }
+            fn direction(&self) -> Vec2 { self.direction }
+            fn health(&self) -> f64 { self.health }
+            fn shield(&self) -> f64 { self.shield }
+            fn extra_lives(&self) -> i32 { self.extra_lives }
+            fn aim(&self) -> f64 { self.aim }
+            fn weapon(&self) -> Option<i32> { self.weapon }
+            fn ammo(&self) -> &[i32] { &self.ammo }
+            fn shield_potions(&self) -> i32 { self.shield_potions }
+            fn spawned(&self) -> bool { self.remaining_spawn_time.is_none() }
+        }
+    )*};
+}
+
+macro_rules! impl_projectile_state {
+    ($($projectile:ty),*) => {$(
+        impl ProjectileState for $projectile {
+            fn position(&self) -> Vec2 { self.position }
+            fn velocity(&self) -> Vec2 { self.velocity }
+            fn life_time(&self) -> f64 { self.life_time }
+            fn weapon_type_index(&self) -> i32 { self.weapon_type_index }
+            fn shooter_player_id(&self) -> i32 { self.shooter_player_id }
+        }
+    )*};
+}
+
+impl_unit_state!(Unit, SimUnit);
+impl_projectile_state!(Projectile, SimProjectile);
+
+/// Features of one of our units on one tick, positions are relative to the unit and in world units.
+#[derive(Clone, Debug)]
+pub struct UnitFeatures {
+    pub unit: Vec<f32>,
+    /// `EXPORTED_ENEMIES` rows, closest first, absent enemies are zeros
+    pub enemies: Vec<f32>,
+    /// `EXPORTED_PROJECTILES` rows, closest first, absent projectiles are zeros
+    pub projectiles: Vec<f32>,
+}
+
+impl UnitFeatures {
+    pub fn new(unit: &Unit, game: &Game, constants: &Constants) -> Self {
+        Self::of(unit, &game.units, &game.projectiles, game.my_id, &game.zone, constants)
+    }
+
+    /// Features of a simulated unit, read from the simulated game in place.
+    pub fn simulated(unit: &SimUnit, game: &SimGame, constants: &Constants) -> Self {
+        Self::of(unit, &game.units, &game.projectiles, game.my_id, &game.zone, constants)
+    }
+
+    fn of<U: UnitState, P: ProjectileState>(unit: &U, units: &[U], projectiles: &[P], my_id: i32, zone: &Zone, constants: &Constants) -> Self {
+        Self {
+            unit: own_features(unit, zone, constants),
+            enemies: enemy_features(unit, units, my_id, constants),
+            projectiles: projectile_features(unit, projectiles, my_id, constants),
+        }
+    }
+
+    /// All features in one row, in the order the tensors are written.
+    pub fn flat(&self) -> Vec<f32> {
+        self.unit.iter().chain(self.enemies.iter()).chain(self.projectiles.iter()).copied().collect()
+    }
+}
+
+pub fn unit_feature_count(constants: &Constants) -> usize {
+    UNIT_BASE_FEATURES + constants.weapons.len()
+}
+
+pub fn enemy_feature_count(constants: &Constants) -> usize {
+    ENEMY_BASE_FEATURES + constants.weapons.len()
+}
+
+fn weapon_one_hot(weapon: Option<i32>, constants: &Constants) -> impl Iterator<Item = f32> {
+    (0..constants.weapons.len()).map(move |idx| if weapon == Some(idx as i32) { 1.0 } else { 0.0 })
+}
+
+fn own_features(unit: &impl UnitState, zone: &Zone, constants: &Constants) -> Vec<f32> {
+    let ammo = unit.weapon().map_or(0, |w| unit.ammo()[w as usize]);
+    let to_center = zone.current_center - unit.position();
+    [
+        unit.health() / constants.unit_health,
+        unit.shield() / constants.max_shield,
+        unit.extra_lives() as f64,
+        unit.aim(),
+        unit.velocity().x,
+        unit.velocity().y,
+        unit.direction().x,
+        unit.direction().y,
+        unit.shield_potions() as f64,
+        ammo as f64,
+        zone.current_radius - to_center.length(),
+        zone.next_radius - unit.position().distance_to(&zone.next_center),
+        to_center.x,
+        to_center.y,
+    ].into_iter()
+        .map(|v| v as f32)
+        .chain(weapon_one_hot(unit.weapon(), constants))
+        .collect()
+}
+
+fn enemy_features<U: UnitState>(unit: &U, units: &[U], my_id: i32, constants: &Constants) -> Vec<f32> {
+    let count = enemy_feature_count(constants);
+    let mut features = vec![0.0; EXPORTED_ENEMIES * count];
+    let position = unit.position();
+    let enemies = units.iter()
+        .filter(|u| u.player_id() != my_id && u.spawned())
+        .sorted_by(|e1, e2| e1.position().distance_to(&position).total_cmp(&e2.position().distance_to(&position)))
+        .take(EXPORTED_ENEMIES);
+    for (row, enemy) in features.chunks_mut(count).zip(enemies) {
+        let offset = enemy.position() - position;
+        let values = [
+            1.0,
+            offset.x,
+            offset.y,
+            enemy.velocity().x,
+            enemy.velocity().y,
+            enemy.health() / constants.unit_health,
+            enemy.shield() / constants.max_shield,
+            enemy.aim(),
+        ].into_iter()
+            .map(|v| v as f32)
+            .chain(weapon_one_hot(enemy.weapon(), constants));
+        row.iter_mut().zip(values).for_each(|(f, v)| *f = v);
+    }
+    features
+}
+
+fn projectile_features(unit: &impl UnitState, projectiles: &[impl ProjectileState], my_id: i32, constants: &Constants) -> Vec<f32> {
+    let mut features = vec![0.0; EXPORTED_PROJECTILES * PROJECTILE_FEATURES];
+    let position = unit.position();
+    let projectiles = projectiles.iter()
+        .sorted_by(|p1, p2| p1.position().distance_to(&position).total_cmp(&p2.position().distance_to(&position)))
+        .take(EXPORTED_PROJECTILES);
+    for (row, projectile) in features.chunks_mut(PROJECTILE_FEATURES).zip(projectiles) {
+        let offset = projectile.position() - position;
+        let velocity = projectile.velocity();
+        let damage = constants.weapons[projectile.weapon_type_index() as usize].projectile_damage;
+        let ours = if projectile.shooter_player_id() == my_id { 1.0 } else { 0.0 };
+        let values = [1.0, offset.x, offset.y, velocity.x, velocity.y, projectile.life_time(), damage, ours];
+        row.iter_mut().zip(values).for_each(|(f, v)| *f = v as f32);
+    }
+    features
+}
+
+pub fn order_features(order: &UnitOrder) -> [f32; ORDER_FEATURES] {
+    let mut features = [0.0; ORDER_FEATURES];
+    features[0] = order.target_velocity.x as f32;
+    features[1] = order.target_velocity.y as f32;
+    features[2] = order.target_direction.x as f32;
+    features[3] = order.target_direction.y as f32;
+    let kind = match order.action {
+        None => None,
+        Some(ActionOrder::Pickup { .. }) => Some(0),
+        Some(ActionOrder::UseShieldPotion {}) => Some(1),
+        Some(ActionOrder::DropShieldPotions { .. }) => Some(2),
+        Some(ActionOrder::DropWeapon {}) => Some(3),
+        Some(ActionOrder::DropAmmo { .. }) => Some(4),
+        Some(ActionOrder::Aim { .. }) => Some(5),
+    };
+    if let Some(kind) = kind {
+        features[4 + kind] = 1.0;
+    }
+    if let Some(ActionOrder::Aim { shoot: true }) = order.action {
+        features[4 + ACTION_KINDS] = 1.0;
+    }
+    features
+}
+
+/// How the game ended for the player whose view it is, what the value of every row of the game is trained on.
+pub fn outcome_features(game: &Game) -> [f32; OUTCOME_FEATURES] {
+    let Some(me) = game.players.iter().find(|p| p.id == game.my_id) else { return [0.0; OUTCOME_FEATURES] };
+    let others = (game.players.len() - 1).max(1) as f64;
+    [(1.0 - (me.place - 1) as f64 / others) as f32, me.score as f32]
+}
+
+/// Collects features of our spawned units with the orders sent for them, one row per unit per tick,
+/// and writes them out as `.npy` tensors.
+pub struct FeatureExporter {
+    constants: Constants,
+    /// Tick and unit id of every row
+    index: Vec<i32>,
+    units: Vec<f32>,
+    enemies: Vec<f32>,
+    projectiles: Vec<f32>,
+    orders: Vec<f32>,
+    /// Known once the game is over, the same for every row
+    outcome: Option<[f32; OUTCOME_FEATURES]>,
+}
+
+impl FeatureExporter {
+    pub fn new(constants: Constants) -> Self {
+        Self {
+            constants,
+            index: vec![],
+            units: vec![],
+            enemies: vec![],
+            projectiles: vec![],
+            orders: vec![],
+            outcome: None,
+        }
+    }
+
+    /// Features of a recorded game with the orders sent in it, read straight from the replay without playing it.
+    pub fn from_replay(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut exporter: Option<Self> = None;
+        let mut pending_view = None;
+        let mut last_view = None;
+        for record in ReplayReader::open(path)? {
+            match record? {
+                ReplayRecord::Server(message) => match *message {
+                    ServerMessage::UpdateConstants { constants } => exporter = Some(Self::new(constants)),
+                    ServerMessage::GetOrder { player_view, .. } => pending_view = Some(player_view),
+                    ServerMessage::Finish {} => break,
+                    ServerMessage::DebugUpdate { .. } => {}
+                },
+                ReplayRecord::Client(ClientMessage::OrderMessage { order }) => {
+                    let Some(view) = pending_view.take() else { continue };
+                    exporter.as_mut()
+                        .ok_or_else(|| std::io::Error::other("GetOrder before UpdateConstants in replay"))?
+                        .record(&view, &order);
+                    last_view = Some(view);
+                }
+                ReplayRecord::Client(_) | ReplayRecord::DebugState(_) => {}
+            }
+        }
+        let mut exporter = exporter.ok_or_else(|| std::io::Error::other("no UpdateConstants in replay"))?;
+        if let Some(view) = last_view {
+            exporter.finish(&view);
+        }
+        Ok(exporter)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.index.len() / 2
+    }
+
+    /// Sets the outcome of the game from the last view of it.
+    pub fn finish(&mut self, game: &Game) {
+        self.outcome = Some(outcome_features(game));
+    }
+
+    pub fn record(&mut self, game: &Game, order: &Order) {
+        let units = game.units.iter()
+            .filter(|u| u.player_id == game.my_id && u.remaining_spawn_time.is_none())
+            .sorted_by_key(|u| u.id);
+        for unit in units {
+            let Some(unit_order) = order.unit_orders.get(&unit.id) else { continue };
+            let features = UnitFeatures::new(unit, game, &self.constants);
+            self.index.extend([game.current_tick, unit.id]);
+            self.units.extend(features.unit);
+            self.enemies.extend(features.enemies);
+            self.projectiles.extend(features.projectiles);
+            self.orders.extend(order_features(unit_order));
+        }
+    }
+
+    /// Writes `index.npy` (rows × 2, tick and unit id), `units.npy`, `enemies.npy`, `projectiles.npy`,
+    /// `orders.npy` and `outcomes.npy` into the directory, creating it if needed. The outcome has to be known.
+    pub fn write(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        let outcome = self.outcome.ok_or_else(|| std::io::Error::other("the game isn't over, no outcome to export"))?;
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let rows = self.rows();
+        let shape_error = |e: ndarray::ShapeError| std::io::Error::other(e.to_string());
+
+        let index = ArrayView2::from_shape((rows, 2), &self.index).map_err(shape_error)?;
+        let units = ArrayView2::from_shape((rows, unit_feature_count(&self.constants)), &self.units).map_err(shape_error)?;
+        let enemies = ArrayView3::from_shape((rows, EXPORTED_ENEMIES, enemy_feature_count(&self.constants)), &self.enemies).map_err(shape_error)?;
+        let projectiles = ArrayView3::from_shape((rows, EXPORTED_PROJECTILES, PROJECTILE_FEATURES), &self.projectiles).map_err(shape_error)?;
+        let orders = ArrayView2::from_shape((rows, ORDER_FEATURES), &self.orders).map_err(shape_error)?;
+        let outcomes = Array2::from_shape_fn((rows, OUTCOME_FEATURES), |(_, column)| outcome[column]);
+
+        write_npy(dir.join("index.npy"), &index).map_err(std::io::Error::other)?;
+        write_npy(dir.join("units.npy"), &units).map_err(std::io::Error::other)?;
+        write_npy(dir.join("enemies.npy"), &enemies).map_err(std::io::Error::other)?;
+        write_npy(dir.join("projectiles.npy"), &projectiles).map_err(std::io::Error::other)?;
+        write_npy(dir.join("orders.npy"), &orders).map_err(std::io::Error::other)?;
+        write_npy(dir.join("outcomes.npy"), &outcomes).map_err(std::io::Error::other)?;
+        Ok(())
+    }
+}
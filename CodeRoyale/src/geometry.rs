@@ -1,42 +1,108 @@
-use ai_cup_22::model::*;
-use crate::MyStrategy;
-
-pub fn calculate_circle_line(center: Vec2, radius: f64, start: Vec2, end: Vec2) -> Option<Vec2> {
-    closest_intersection_point(radius, start - center, end - center)
-        .iter()
-        .map(|p| *p + center)
-        .filter(|p| p.x >= start.x && p.x <= end.x)
-        .filter(|p| p.y >= start.y && p.y <= end.y)
-        .next()
-}
-
-// https://e-maxx.ru/algo/segment_to_line
-// https://e-maxx.ru/algo/circle_line_intersection
-pub fn closest_intersection_point(radius: f64, start: Vec2, end: Vec2) -> Vec<Vec2> {
-    let a = start.y - end.y;
-    let b = end.x - start.x;
-    let c = -a * start.x - b * start.y;
-
-    let r = radius;
-
-    let a2b2 = a * a + b * b;
-    let r2 = r * r;
-    let c2 = c * c;
-
-    let x0 = -a * c / (a2b2);
-    let y0 = -b * c / (a2b2);
-
-    if c2 > r2 * (a2b2) {
-        vec![]
-    } else if (c2 - r2 * (a2b2)).abs() < 0.000000001 {
-        vec![Vec2::from_xy(x0, y0)]
+use crate::model::*;
+
+/// Point of the segment closest to `point`.
+pub fn closest_point_on_segment(start: Vec2, end: Vec2, point: Vec2) -> Vec2 {
+    let d = end - start;
+    let length_squared = d.dot_product(&d);
+    if length_squared == 0.0 {
+        return start;
+    }
+    let t = ((point - start).dot_product(&d) / length_squared).clamp(0.0, 1.0);
+    start + d * t
+}
+
+/// Whether any point of the segment is within `radius` of `center`, the ends included.
+pub fn segment_intersects_circle(start: Vec2, end: Vec2, center: Vec2, radius: f64) -> bool {
+    closest_point_on_segment(start, end, center).distance_to(&center) <= radius
+}
+
+/// Fraction of the way from `start` to `end` at which the segment first touches the circle, if it does.
+pub fn segment_circle_contact(start: Vec2, end: Vec2, center: Vec2, radius: f64) -> Option<f64> {
+    let d = end - start;
+    let f = start - center;
+    let a = d.dot_product(&d);
+    let c = f.dot_product(&f) - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * f.dot_product(&d);
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    if (0.0..=1.0).contains(&t) {
+        Some(t)
     } else {
-        let d = r2 - c2 / (a2b2);
-        let mult = (d / (a2b2)).sqrt();
-        let ax = x0 + b * mult;
-        let bx = x0 - b * mult;
-        let ay = y0 - a * mult;
-        let by = y0 + a * mult;
-        vec![Vec2::from_xy(ax, ay), Vec2::from_xy(bx, by)]
-    }
-}
\ No newline at end of file
+        None
+    }
+}
+
+/// First point of the segment touching the circle, if any.
+pub fn segment_circle_intersection(start: Vec2, end: Vec2, center: Vec2, radius: f64) -> Option<Vec2> {
+    segment_circle_contact(start, end, center, radius).map(|t| start + (end - start) * t)
+}
+
+/// Seconds until two circles moving with constant velocities first touch, 0 if they already do.
+pub fn moving_circles_contact_time(position1: Vec2, velocity1: Vec2, radius1: f64, position2: Vec2, velocity2: Vec2, radius2: f64) -> Option<f64> {
+    // the second circle standing still and the first one moving relative to it
+    let p = position1 - position2;
+    let v = velocity1 - velocity2;
+    let r = radius1 + radius2;
+
+    let a = v.dot_product(&v);
+    let b = 2.0 * p.dot_product(&v);
+    let c = p.dot_product(&p) - r * r;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    if a == 0.0 {
+        return None;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    Some(t).filter(|t| *t >= 0.0)
+}
+
+/// Points where the lines from `point` touching the circle meet it, `None` if the point is inside.
+pub fn circle_tangent_points(point: Vec2, center: Vec2, radius: f64) -> Option<(Vec2, Vec2)> {
+    let to_point = point - center;
+    let distance = to_point.length();
+    if distance <= radius {
+        return None;
+    }
+    // angle at the center between the direction to the point and to a tangent point
+    let angle = (radius / distance).acos();
+    let radial = to_point * (radius / distance);
+    Some((center + radial.rotate(angle), center + radial.rotate(-angle)))
+}
+
+#[derive(Clone, Debug)]
+pub struct RayHit<'a> {
+    pub obstacle: &'a Obstacle,
+    pub distance: f64,
+    pub point: Vec2,
+}
+
+/// First obstacle a ray from `origin` in `direction` hits within `max_distance`.
+pub fn ray_cast<'a>(origin: Vec2, direction: Vec2, max_distance: f64, obstacles: impl IntoIterator<Item = &'a Obstacle>) -> Option<RayHit<'a>> {
+    let length = direction.length();
+    if length == 0.0 {
+        return None;
+    }
+    let end = origin + direction * (max_distance / length);
+    obstacles.into_iter()
+        .filter_map(|o| segment_circle_contact(origin, end, o.position, o.radius).map(|t| (o, t)))
+        .min_by(|(_, t1), (_, t2)| t1.total_cmp(t2))
+        .map(|(obstacle, t)| RayHit {
+            obstacle,
+            distance: t * max_distance,
+            point: origin + (end - origin) * t,
+        })
+}
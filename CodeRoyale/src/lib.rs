@@ -2,4 +2,67 @@ pub mod trans;
 
 pub mod codegame;
 pub mod debugging;
-pub mod model;
\ No newline at end of file
+pub mod model;
+
+mod my_strategy;
+pub use my_strategy::MyStrategy;
+mod debug_interface;
+pub use debug_interface::{DebugInterface, idle_debug_state};
+mod visualization;
+mod logic;
+pub use logic::{ActionOrderOrder, Vec2Order};
+mod behaviour_tree;
+pub use behaviour_tree::*;
+mod team;
+pub use team::*;
+mod zone_forecast;
+pub use zone_forecast::*;
+mod path_planner;
+pub use path_planner::*;
+mod loot_economy;
+pub use loot_economy::*;
+mod aiming;
+pub use aiming::*;
+mod bookkeeping;
+mod enemy_beliefs;
+pub use enemy_beliefs::*;
+mod simulation;
+pub use simulation::*;
+mod movement_search;
+pub use movement_search::*;
+mod simulatable_model;
+pub use simulatable_model::*;
+mod visibility;
+pub use visibility::*;
+mod basic_game_entity;
+pub use basic_game_entity::*;
+mod geometry;
+pub use geometry::*;
+mod spatial_index;
+pub use spatial_index::*;
+mod time_budget;
+pub use time_budget::*;
+mod spawn_phase;
+pub use spawn_phase::*;
+mod cover_map;
+pub use cover_map::*;
+mod sustain;
+pub use sustain::*;
+mod debug_console;
+pub use debug_console::*;
+mod sim_validation;
+pub use sim_validation::*;
+mod arena;
+pub use arena::*;
+mod replay;
+pub use replay::*;
+mod feature_export;
+pub use feature_export::*;
+mod evaluator;
+pub use evaluator::*;
+mod strategy_params;
+pub use strategy_params::*;
+mod tuner;
+pub use tuner::*;
+mod svg_render;
+pub use svg_render::*;
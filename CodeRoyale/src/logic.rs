@@ -1,39 +1,57 @@
 use std::f64::consts::PI;
+use std::time::Instant;
 use itertools::Itertools;
 use rand::Rng;
-use ai_cup_22::debugging::Color;
+use crate::debugging::Color;
 use crate::{DebugInterface, MyStrategy};
-use ai_cup_22::model::*;
-use crate::simulation::Simulator;
+use crate::behaviour_tree::{BehaviourFn, Scored};
+use crate::model::*;
+use crate::movement_search::MovementSearch;
+use crate::team::FORMATION_RADIUS;
 
 #[derive(Debug)]
-struct Vec2Order {
-    vec: Vec2,
-    description: Option<String>,
+pub struct Vec2Order {
+    pub vec: Vec2,
+    pub description: Option<String>,
+    /// Where the unit is heading, if anywhere in particular; such orders get routed around obstacles
+    pub target: Option<Vec2>,
+    pub score: f64,
 }
 
-struct ActionOrderOrder {
-    action_order: ActionOrder,
-    description: Option<String>,
+pub struct ActionOrderOrder {
+    pub action_order: ActionOrder,
+    pub description: Option<String>,
+    pub score: f64,
+}
+
+impl Scored for Vec2Order {
+    fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+impl Scored for ActionOrderOrder {
+    fn score(&self) -> f64 {
+        self.score
+    }
 }
 
-#[allow(dead_code)]
-#[allow(unused_variables)]
 impl MyStrategy {
     pub fn get_velocity(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Vec2 {
-        let order = None
-            .or_else(|| self.velocity_avoid_projectiles(unit, game, debug_interface))
-            .or_else(|| self.velocity_move_out_of_fire_range(unit, game, debug_interface))
-            .or_else(|| self.velocity_go_to_weapon(unit, game, debug_interface))
-            .or_else(|| self.velocity_go_to_shield(unit, game, debug_interface))
-            .or_else(|| self.velocity_go_to_ammo(unit, game, debug_interface))
-            .or_else(|| self.velocity_close_in_on_an_enemy(unit, game, debug_interface))
-            .or_else(|| self.velocity_continue_to_waypoint(unit, game, debug_interface))
-            .or_else(|| self.velocity_go_closer_to_allies(unit, game, debug_interface))
-            .or_else(|| self.velocity_go_to_somewhere_in_the_zone(unit, game, debug_interface))
-            .and_then(|vec_order| {
-                self.velocity_steer_around_obstacles(unit, vec_order, game, debug_interface)
-            });
+        let behaviours = self.behaviours.clone();
+        let order = behaviours.velocity.select(self, unit, game, debug_interface);
+        let entries = std::mem::take(&mut self.debug_console.entries);
+        let order = order.and_then(|vec_order| {
+            let started_at = Instant::now();
+            let order = self.route_order(unit, vec_order, debug_interface);
+            self.time_budget.record("route_order", started_at.elapsed());
+            order
+        });
+        if self.debug_console.tracing {
+            let trace = self.debug_console.traces.entry(unit.id).or_default();
+            trace.velocity = entries;
+            trace.route = order.as_ref().map(|o| format!("{} ({:.1} m/s)", o.vec.to_short_string(), o.vec.length()));
+        }
 
         if let Some(vec_order) = order {
             if let Some(text) = vec_order.description {
@@ -46,9 +64,12 @@ impl MyStrategy {
     }
 
     pub fn get_direction(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Vec2 {
-        let order = None
-            .or_else(|| self.direction_hittable_enemy(unit, game, debug_interface));
-        // .or_else(|| self.direction_look_around(unit, game, debug_interface));
+        let behaviours = self.behaviours.clone();
+        let order = behaviours.direction.select(self, unit, game, debug_interface);
+        let entries = std::mem::take(&mut self.debug_console.entries);
+        if self.debug_console.tracing {
+            self.debug_console.traces.entry(unit.id).or_default().direction = entries;
+        }
 
         let result = if let Some(vec_order) = order {
             if let Some(text) = vec_order.description {
@@ -68,13 +89,12 @@ impl MyStrategy {
         if self.is_action_cooldown(unit) {
             return None;
         }
-        let order = None
-            .or_else(|| self.action_shoot_at_target(unit, game, debug_interface))
-            .or_else(|| self.action_pick_up_weapon(unit, game, debug_interface))
-            .or_else(|| self.action_pick_up_shield(unit, game, debug_interface))
-            .or_else(|| self.action_drink_shield(unit, game, debug_interface))
-            .or_else(|| self.action_pick_up_ammo(unit, game, debug_interface))
-            ;
+        let behaviours = self.behaviours.clone();
+        let order = behaviours.action.select(self, unit, game, debug_interface);
+        let entries = std::mem::take(&mut self.debug_console.entries);
+        if self.debug_console.tracing {
+            self.debug_console.traces.entry(unit.id).or_default().action = entries;
+        }
 
         order.map(|action_order_order| {
             if let Some(text) = action_order_order.description {
@@ -84,77 +104,115 @@ impl MyStrategy {
         })
     }
 
-    fn direction_hittable_enemy(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if unit.weapon.is_none() {
-            return None;
-        }
-
-        self
-            .enemy_units
-            .iter()
-            .filter(|enemy| enemy.is_within_fire_range_of(unit, &self.constants))
-            .min_by(|e1, e2| {
-                let a1 = unit.direction.angle_with(&(self.simple_projected_position(e1, unit) - unit.position));
-                let a2 = unit.direction.angle_with(&(self.simple_projected_position(e2, unit) - unit.position));
-                a1.total_cmp(&a2)
-            })
-            .and_then(|enemy| {
-                self.targets.entry(unit.id).or_insert(enemy.id);
-                Some(enemy)
-            })
-            .map(|enemy| {
-                let fire_target = self.simple_projected_position(enemy, unit);
+    pub(crate) fn velocity_behaviour(name: &str) -> Option<BehaviourFn<Vec2Order>> {
+        let f: BehaviourFn<Vec2Order> = match name {
+            "land_safely" => |s, u, g, d| s.velocity_land_safely(u, g, d),
+            "avoid_projectiles" => |s, u, g, d| s.velocity_avoid_projectiles(u, g, d),
+            "move_out_of_fire_range" => |s, u, g, d| s.velocity_move_out_of_fire_range(u, g, d),
+            "go_to_weapon" => |s, u, g, d| s.velocity_go_to_weapon(u, g, d),
+            "go_to_shield" => |s, u, g, d| s.velocity_go_to_shield(u, g, d),
+            "go_to_ammo" => |s, u, g, d| s.velocity_go_to_ammo(u, g, d),
+            "close_in_on_an_enemy" => |s, u, g, d| s.velocity_close_in_on_an_enemy(u, g, d),
+            "continue_to_waypoint" => |s, u, g, d| s.velocity_continue_to_waypoint(u, g, d),
+            "go_closer_to_allies" => |s, u, g, d| s.velocity_go_closer_to_allies(u, g, d),
+            "keep_formation" => |s, u, g, d| s.velocity_keep_formation(u, g, d),
+            "rotate_to_next_zone" => |s, u, g, d| s.velocity_rotate_to_next_zone(u, g, d),
+            "retreat_to_recover" => |s, u, g, d| s.velocity_retreat_to_recover(u, g, d),
+            "peek_and_shoot" => |s, u, g, d| s.velocity_peek_and_shoot(u, g, d),
+            "go_to_somewhere_in_the_zone" => |s, u, g, d| s.velocity_go_to_somewhere_in_the_zone(u, g, d),
+            _ => return None,
+        };
+        Some(f)
+    }
 
-                Vec2Order {
-                    vec: fire_target - unit.position,
-                    description: Some("turning to enemy".to_string()),
-                }
-            })
+    pub(crate) fn direction_behaviour(name: &str) -> Option<BehaviourFn<Vec2Order>> {
+        let f: BehaviourFn<Vec2Order> = match name {
+            "hittable_enemy" => |s, u, g, d| s.direction_hittable_enemy(u, g, d),
+            "peek_target" => |s, u, g, d| s.direction_peek_target(u, g, d),
+            "look_around" => |s, u, g, d| s.direction_look_around(u, g, d),
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    pub(crate) fn action_behaviour(name: &str) -> Option<BehaviourFn<ActionOrderOrder>> {
+        let f: BehaviourFn<ActionOrderOrder> = match name {
+            "shoot_at_target" => |s, u, g, d| s.action_shoot_at_target(u, g, d),
+            "pick_up_weapon" => |s, u, g, d| s.action_pick_up_weapon(u, g, d),
+            "pick_up_shield" => |s, u, g, d| s.action_pick_up_shield(u, g, d),
+            "drink_shield" => |s, u, g, d| s.action_drink_shield(u, g, d),
+            "pick_up_ammo" => |s, u, g, d| s.action_pick_up_ammo(u, g, d),
+            "share_supplies" => |s, u, g, d| s.action_share_supplies(u, g, d),
+            _ => return None,
+        };
+        Some(f)
+    }
+
+    fn direction_hittable_enemy(&mut self, unit: &Unit, _game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        unit.weapon.or_else(|| self.reject("no weapon"))?;
+
+        // the team plan picks targets for focus fire, otherwise turn to whoever is the quickest to aim at
+        let assigned = self.targets.get(&unit.id).and_then(|id| self.units_by_id.get(id)).cloned();
+        let enemy = assigned.or_else(|| {
+            self
+                .enemy_units
+                .iter()
+                .filter(|enemy| enemy.is_within_fire_range_of(unit, &self.constants))
+                .min_by(|e1, e2| {
+                    let a1 = unit.direction.angle_with(&(self.fire_target(e1, unit) - unit.position));
+                    let a2 = unit.direction.angle_with(&(self.fire_target(e2, unit) - unit.position));
+                    a1.total_cmp(&a2)
+                })
+                .cloned()
+        }).or_else(|| self.reject("no enemy in fire range"))?;
+        self.targets.entry(unit.id).or_insert(enemy.id);
+
+        let fire_target = self.fire_target(&enemy, unit);
+        Some(Vec2Order {
+            vec: fire_target - unit.position,
+            description: Some(format!("turning to enemy {}", enemy.id)),
+            target: None,
+            // an enemy almost in the sights is worth more than one behind the unit
+            score: 1.0 - unit.direction.angle_with(&(fire_target - unit.position)) / PI,
+        })
             .filter(|vec_order| {
-                self.position_is_hittable_by(&(unit.position + vec_order.vec).into(), unit, &self.constants, debug_interface)
+                self.position_is_hittable_by(&(unit.position + vec_order.vec).into(), unit, debug_interface)
             })
+            .or_else(|| self.reject_with(|| format!("enemy {} is behind cover", enemy.id)))
     }
 
-    fn direction_look_around(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+    fn direction_look_around(&self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
         Some(Vec2Order {
             vec: Vec2 { x: -unit.direction.y, y: unit.direction.x },
             description: Some("looking around".to_string()),
+            target: None,
+            // worth less the more enemies are in sight already
+            score: 1.0 / (1.0 + self.enemy_units.len() as f64),
         })
     }
 
-    fn velocity_avoid_projectiles(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        let threatening_projectiles = self.projectiles_aimed_at_target(game, HittableEntity::from(unit));
+    fn velocity_avoid_projectiles(&mut self, unit: &Unit, game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let threatening_projectiles = self.projectiles_aimed_at_target(HittableEntity::from(unit));
 
         if threatening_projectiles.is_empty() {
-            return None;
+            return self.reject("no projectiles coming at the unit");
         }
 
-        let original_direction = if unit.velocity.length() > 0.0 { unit.velocity } else { unit.direction };
-
-        // TODO: simulate complex movements (N velocities, M directions, K ticks), instead of traveling in a straight line
-        let rotation_angle = (0..360)
-            .step_by(30)
-            .map(|angle_degree| (angle_degree as f64).to_radians())
-            .min_by_key(|angle| {
-                let unit_order = UnitOrder {
-                    target_velocity: Vec2::from_length_and_angle(self.constants.max_unit_forward_speed, original_direction.angle()).rotate(*angle),
-                    target_direction: unit.direction,
-                    action: None,
-                };
-                let mut simulator = Simulator::new(game, &self.constants, unit.id, unit_order);
-                // TODO: prefer positions behind an obstacle
-                // let n_ticks = self.constants.ticks_per_second as usize;
-                let n_ticks = 15;
-                let result = simulator.simulate_n_ticks(n_ticks, debug_interface);
-                result
-            });
+        let deadline = self.time_budget.deadline(self.params.movement_search.time_budget);
+        let plan = MovementSearch::new(&self.params.movement_search, self.evaluator.as_ref(), &self.constants, &self.obstacle_index).run(game, unit, deadline)
+            .or_else(|| self.reject("movement search has no orders to try"))?;
+        let velocity = plan.orders.first().or_else(|| self.reject("movement search found no plan"))?.target_velocity;
+        let threats = threatening_projectiles.len();
 
-        rotation_angle.map(|angle| {
-            let velocity = Vec2::from_length_and_angle(self.constants.max_unit_forward_speed, original_direction.angle()).rotate(angle);
-            Vec2Order {
-                vec: velocity,
-                description: Some(format!("avoiding damage, going to {}", (unit.position + velocity).to_short_string())),
-            }
+        if self.debug_console.tracing {
+            self.debug_console.rollouts.insert(unit.id, plan.positions);
+        }
+
+        Some(Vec2Order {
+            vec: velocity,
+            description: Some(format!("avoiding damage, going to {}", (unit.position + velocity).to_short_string())),
+            target: None,
+            score: 1.0 + threats as f64,
         })
     }
 
@@ -163,9 +221,20 @@ impl MyStrategy {
             .enemy_units
             .iter()
             .filter(|enemy| enemy.position.distance_to(&unit.position) <= enemy.weapon_range(&self.constants));
-        let velocities_to_move_away = units_that_can_hit_me.map(|enemy| unit.position - enemy.position).collect_vec();
+        // enemies we only heard or lost sight of can shoot from the dark too
+        let believed_to_hit_me = self
+            .unseen_enemies(self.params.belief_threat_confidence)
+            .into_iter()
+            .filter(|belief| belief.position.distance_to(&unit.position) <= belief.weapon_range(&self.constants));
+        let velocities_to_move_away = units_that_can_hit_me.map(|enemy| enemy.position)
+            .chain(believed_to_hit_me.map(|belief| belief.position))
+            .map(|position| unit.position - position)
+            .collect_vec();
         if velocities_to_move_away.is_empty() {
-            return None;
+            return self.reject("no enemy can shoot the unit");
+        }
+        if self.risk_tolerance(unit, game) >= self.params.stand_ground_risk_tolerance && unit.health + unit.shield >= self.constants.unit_health {
+            return self.reject_with(|| format!("{} lives to spare, standing ground", unit.extra_lives));
         }
 
         // println!("velocities to move away: {:?}", velocities_to_move_away);
@@ -177,7 +246,7 @@ impl MyStrategy {
         let position_after_1_tick = unit.position + resulting_velocity / self.constants.ticks_per_second;
         if position_after_1_tick.distance_to(&game.zone.current_center) > game.zone.current_radius {
             // println!("would go in the zone, not moving out of range");
-            return None;
+            return self.reject("moving away would leave the zone");
         }
 
         // println!("resulting velocity: {:?}", resulting_velocity);
@@ -187,21 +256,23 @@ impl MyStrategy {
         Some(Vec2Order{
             vec: resulting_velocity,
             description: Some("moving out of fire range".to_string()),
+            target: None,
+            score: velocities_to_move_away.len() as f64,
         })
     }
 
-    fn velocity_steer_around_obstacles(&self, unit: &Unit, vec_order: Vec2Order, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+    pub(crate) fn velocity_steer_around_obstacles(&self, unit: &Unit, vec_order: Vec2Order, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
         assert!(vec_order.vec.x.is_finite(), "vec_order.vec.x was infinite ({:?})", vec_order);
         let delta_time = 1.0 / self.constants.ticks_per_second;
         let mut t = 0;
-        let mut max_t = 10;
+        let max_t = self.params.steer_look_ahead_ticks;
         let obstacle_in_the_way = loop {
             t += 1;
             if t >= max_t {
                 break None;
             }
             let future_position = unit.position + unit.velocity * delta_time * t as f64;
-            let obstacle_in_the_way = self.constants.obstacles.iter().find(|o| o.position.distance_to(&future_position) < o.radius + self.constants.unit_radius);
+            let obstacle_in_the_way = self.obstacle_index.near(future_position, self.constants.unit_radius).into_iter().next();
             if obstacle_in_the_way.is_some() {
                 break obstacle_in_the_way;
             }
@@ -215,8 +286,7 @@ impl MyStrategy {
 
         let velocity = vec_order.vec;
         let turn_indicator = velocity.cross_product(&(obstacle.position - unit.position));
-        let leeway = 1.0;
-        let opposite = obstacle.radius + self.constants.unit_radius + leeway;
+        let opposite = obstacle.radius + self.constants.unit_radius + self.params.steer_leeway;
         let hypot = obstacle.position - unit.position;
         let sin_angle = opposite / hypot.length();
         let mut angle = sin_angle.asin();
@@ -230,38 +300,24 @@ impl MyStrategy {
             if let Some(debug) = debug_interface.as_mut() {
                 debug.add_segment(unit.position, unit.position + corrected_velocity, 0.1, Color::red());
             }
-            Some(Vec2Order { vec: corrected_velocity, description: Some("turning left".to_string()) })
+            Some(Vec2Order { vec: corrected_velocity, description: Some("turning left".to_string()), target: vec_order.target, score: vec_order.score })
         } else if turn_indicator > 0.0 {
             // turn right
             let corrected_velocity = velocity.rotate(-angle).clamp(self.constants.max_unit_forward_speed);
             if let Some(debug) = debug_interface.as_mut() {
                 debug.add_segment(unit.position, unit.position + corrected_velocity, 0.1, Color::red());
             }
-            Some(Vec2Order { vec: corrected_velocity, description: Some("turning right".to_string()) })
+            Some(Vec2Order { vec: corrected_velocity, description: Some("turning right".to_string()), target: vec_order.target, score: vec_order.score })
         } else {
             Some(vec_order)
         }
     }
 
-
-    fn velocity_correction_avoid_projectiles(&self, proposed_velocity: Vec2, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Vec2 {
-        let threatening_projectiles = self.projectiles_aimed_at_target(game, HittableEntity::from(unit));
-
-        if threatening_projectiles.is_empty() {
-            proposed_velocity
-        } else {
-            // if let Some(debug) = debug_interface.as_mut() {
-            //     for p in threatening_projectiles.iter() {
-            //         let final_position = p.position + p.velocity * p.life_time;
-            //         debug.add_segment(p.position, final_position, 0.1, Color::red().a(0.4))
-            //     }
-            // }
-            Vec2::zero()
-        }
-    }
-
-    fn projectiles_aimed_at_target(&self, game: &Game, hittable: HittableEntity) -> Vec<&Projectile> {
-        self.seen_projectiles.values()
+    fn projectiles_aimed_at_target(&self, hittable: HittableEntity) -> Vec<&Projectile> {
+        // no projectile flies farther than its weapon's range
+        let max_range = self.constants.weapons.iter().map(|w| w.range()).fold(0.0, f64::max);
+        self.dynamic_index.projectiles.within(hittable.position, max_range + hittable.radius).iter()
+            .filter_map(|id| self.seen_projectiles.get(id))
             .filter(|p| {
                 let final_position = p.position + p.velocity * p.life_time;
                 hittable.intersects_with(&p.position, &final_position)
@@ -275,40 +331,13 @@ impl MyStrategy {
     }
 
     fn velocity_go_to_weapon(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if self.is_action_cooldown(unit) {
-            return None;
-        }
-
-        if !matches!(unit.priority(), LootPriority::Weapon | LootPriority::Whatever) {
-            return None;
-        }
-        let (bow_idx, _) = self.constants.weapons.iter().enumerate().find(|(_, w)| w.name == "Bow").unwrap();
-
-        match unit.weapon {
-            Some(i) if i as usize == bow_idx => {
-                return None;
-            }
-            _ => {
-                let predicate = |loot: &Loot| {
-                    matches!(loot.item, Item::Weapon{type_index: i} if i as usize == bow_idx)
-                };
-                self.velocity_go_to_loot(unit, game, &predicate, debug_interface)
-            }
-        }
+        let predicate = |loot: &Loot| {
+            matches!(loot.item, Item::Weapon{..})
+        };
+        self.velocity_go_to_loot(unit, game, &predicate, debug_interface)
     }
 
     fn velocity_go_to_shield(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if self.is_action_cooldown(unit) {
-            return None;
-        }
-
-        if !matches!(unit.priority(), LootPriority::Shield | LootPriority::Whatever) {
-            return None;
-        }
-        if unit.shield_potions >= self.constants.max_shield_potions_in_inventory {
-            return None;
-        }
-
         let predicate = |loot: &Loot| {
             matches!(loot.item, Item::ShieldPotions{..})
         };
@@ -316,33 +345,16 @@ impl MyStrategy {
     }
 
     fn velocity_go_to_ammo(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if self.is_action_cooldown(unit) {
-            return None;
-        }
-
-        if !matches!(unit.priority(), LootPriority::Ammo | LootPriority::Whatever) {
-            return None;
-        }
-
-        let weapon_idx = unit.weapon? as usize;
-        let (bow_idx, _) = self.constants.weapons.iter().enumerate().find(|(_, w)| w.name == "Bow").unwrap();
-
-        let weapon = self.constants.weapons.get(weapon_idx)?;
-
-        if unit.ammo[weapon_idx] >= weapon.max_inventory_ammo {
-            return None;
-        }
-
         let predicate = |loot: &Loot| {
-            matches!(loot.item, Item::Ammo{weapon_type_index: i, ..} if i as usize == weapon_idx || i as usize == bow_idx)
+            matches!(loot.item, Item::Ammo{..})
         };
         self.velocity_go_to_loot(unit, game, &predicate, debug_interface)
     }
 
-    fn velocity_close_in_on_an_enemy(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+    fn velocity_close_in_on_an_enemy(&self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
         // if has someone within weapon range already
-        if self.enemy_units.iter().any(|enemy| enemy.position.distance_to(&unit.position) <= unit.weapon_range(&self.constants)) {
-            return None
+        if !self.enemies_within(unit.position, unit.weapon_range(&self.constants)).is_empty() {
+            return self.reject("an enemy is in range already");
         }
 
         let closest_unit = self.enemy_units.iter().min_by_key(|enemy| (enemy.position.distance_to(&unit.position) * 10f64.powi(9)) as i64);
@@ -353,28 +365,33 @@ impl MyStrategy {
                 dist.angle()
             );
             Vec2Order{
-                vec: vec.clamp_min(6.0),
+                vec: vec.clamp_min(self.params.approach_speed),
                 description: Some(format!("closing in on {}", enemy.id)),
+                target: Some(unit.position + vec),
+                score: 1.0 / (1.0 + dist.length() / self.params.close_in_falloff),
             }
-        })
+        }).or_else(|| self.reject("no enemy in sight"))
     }
 
-    fn velocity_continue_to_waypoint(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if self.is_over_loot_move_target(unit, game) {
-            return None;
+    fn velocity_continue_to_waypoint(&self, unit: &Unit, game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        if self.is_over_loot_move_target(unit) {
+            return self.reject("standing on loot it's going for");
         }
-        let waypoint = self.waypoints.get(&unit.id)?;
+        let waypoint = self.waypoints.get(&unit.id).or_else(|| self.reject("no waypoint"))?;
 
         Some(Vec2Order {
             vec: *waypoint - unit.position,
             description: Some("going to waypoint".to_string()),
+            target: Some(*waypoint),
+            // a waypoint the zone is shrinking away from is less and less worth going to
+            score: 0.5 * (1.0 - waypoint.distance_to(&game.zone.next_center) / game.zone.next_radius).clamp(0.0, 1.0),
         })
     }
 
-    fn velocity_go_closer_to_allies(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+    fn velocity_go_closer_to_allies(&mut self, unit: &Unit, game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
         let allies = game.units.iter().filter(|u| u.player_id == unit.player_id).filter(|u| u.id != unit.id).collect_vec();
         if allies.len() < 2 {
-            return None;
+            return self.reject("fewer than two allies");
         }
 
         let mut center = Vec2::zero();
@@ -384,20 +401,21 @@ impl MyStrategy {
         Some(Vec2Order {
             vec: center - unit.position,
             description: Some("going closer to allies".to_string()),
+            target: Some(center),
+            score: 0.3 * (center.distance_to(&unit.position) / FORMATION_RADIUS).min(1.0),
         })
     }
 
-    fn velocity_go_to_somewhere_in_the_zone(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
-        if self.is_over_loot_move_target(unit, game) {
-            return None;
+    fn velocity_go_to_somewhere_in_the_zone(&mut self, unit: &Unit, game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        if self.is_over_loot_move_target(unit) {
+            return self.reject("standing on loot it's going for");
         }
         let center = game.zone.next_center;
         let radius = game.zone.next_radius;
 
-        let mut rng = rand::thread_rng();
-        let mut random_point = loop {
-            let p = center + Vec2::from_length_and_angle(rng.gen_range(0.0..radius), rng.gen_range(0.0..2.0 * PI));
-            if !self.constants.obstacles.iter().any(|o| o.position.distance_to(&p) < (o.radius + self.constants.unit_radius / 2.0) + 0.2) {
+        let random_point = loop {
+            let p = center + Vec2::from_length_and_angle(self.rng.gen_range(0.0..radius), self.rng.gen_range(0.0..2.0 * PI));
+            if self.obstacle_index.near(p, self.constants.unit_radius / 2.0 + self.params.wander_clearance).is_empty() {
                 break p;
             }
         };
@@ -407,84 +425,70 @@ impl MyStrategy {
         Some(Vec2Order {
             vec: random_point - unit.position,
             description: Some("going to a random point".to_string()),
+            target: Some(random_point),
+            // wandering is a way into the zone for units far from its center
+            score: 0.1 * (unit.position.distance_to(&center) / radius).min(1.0),
         })
     }
 
-    fn velocity_go_to_loot(&mut self, unit: &Unit, game: &Game, predicate: &dyn Fn(&Loot) -> bool, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+    fn velocity_go_to_loot(&mut self, unit: &Unit, _game: &Game, predicate: &dyn Fn(&Loot) -> bool, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        // looting fails if the unit walks away from the loot before it's done
+        if unit.action.as_ref().is_some_and(|action| action.action_type == ActionType::Looting && action.finish_tick > self.current_tick) {
+            return Some(Vec2Order {
+                vec: Vec2::zero(),
+                description: Some("looting".to_string()),
+                target: None,
+                score: 1.0,
+            });
+        }
         if self.is_action_cooldown(unit) {
-            return None;
+            return self.reject("action on cooldown");
         }
-        let nearest_loot: Option<&Loot> = self
-            .seen_loot
-            .values()
-            .filter(|loot| {
-                match self.move_targets.get(&loot.id) {
-                    Some(id) if *id == unit.id => true,
-                    None => true,
-                    _ => false,
-                }
-            })
-            .filter(|loot| predicate(*loot))
-            .filter(|loot| loot.position.distance_to(&game.zone.current_center) <= game.zone.current_radius * 0.9)
-            .filter(|loot| !self.enemy_units.iter().any(|enemy| enemy.position.distance_to(&loot.position) <= enemy.weapon_range(&self.constants)))
-            .min_by_key(|loot| unit.position.distance_to(&loot.position) as i32)
-            .and_then(|loot| {
-                if !self.move_targets.contains_key(&loot.id) {
-                    // println!("adding move target {} for unit {}", loot.id, unit.id);
-                    self.move_targets.entry(loot.id).or_insert(unit.id);
-                }
-                Some(loot)
-            });
+        // loot is shared out between the units by plan_loot
+        let assignment = self.loot_plan.get(&unit.id).or_else(|| self.reject("no loot assigned"))?;
+        let loot = self.seen_loot.get(&assignment.loot_id).filter(|loot| predicate(loot))
+            .or_else(|| self.reject("assigned loot is of another kind"))?;
 
-        nearest_loot
+        Some(loot)
             .filter(|loot| loot.position.distance_to(&unit.position) > self.constants.unit_radius)
             .map(|loot| {
                 Vec2Order {
-                    vec: (loot.position - unit.position).clamp_min(6.0),
-                    description: Some("going to loot".to_string()),
+                    vec: (loot.position - unit.position).clamp_min(self.params.approach_speed),
+                    description: Some(format!("going to loot {:?} worth {:.2}", loot.item, assignment.value)),
+                    target: Some(loot.position),
+                    score: assignment.utility,
                 }
             })
+            .or_else(|| self.reject("standing on the loot already"))
     }
 
-    fn action_drink_shield(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
-        if unit.shield >= self.constants.max_shield {
-            return None;
-        }
+    fn action_shoot_at_target(&self, unit: &Unit, _game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
+        let enemy_id = self.targets.get(&unit.id).or_else(|| self.reject("no target"))?;
+        let enemy = self.units_by_id.get(enemy_id).or_else(|| self.reject("target out of sight"))?;
+        let ammo = unit.ammo[unit.weapon.or_else(|| self.reject("no weapon"))? as usize];
 
-        if unit.shield_potions <= 0 {
-            return None;
+        if ammo == 0 {
+            return self.reject("out of ammo");
         }
 
-        Some(ActionOrderOrder {
-            action_order: ActionOrder::UseShieldPotion {},
-            description: Some("drinking shield".to_string()),
-        })
-    }
-
-    fn action_shoot_at_target(&self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
-        let enemy_id = self.targets.get(&unit.id)?;
-        let enemy = self.units_by_id.get(enemy_id)?;
-        let ammo = unit.ammo[unit.weapon? as usize];
-
-        if ammo == 0 {
-            return None;
+        let shot = self.aim_at(enemy, unit).or_else(|| self.reject("projectiles can't catch the target"))?;
+        if !self.position_is_hittable_by(&shot.aim_point.into(), unit, debug_interface) {
+            return self.aim_from_cover(unit, enemy).or_else(|| self.reject("shot is blocked by an obstacle"));
         }
+        if self.shot_blocked_by_ally(unit, shot.aim_point) {
+            return self.reject("an ally is in the line of fire");
+        }
+        // keep aiming without firing until the shot is worth the ammo
+        let shoot = shot.hit_probability >= self.params.aiming.min_hit_probability;
 
         Some(ActionOrderOrder {
-            action_order: ActionOrder::Aim { shoot: true },
-            description: Some(format!("shooting {} at {}", enemy.id, enemy.position.to_short_string())),
+            action_order: ActionOrder::Aim { shoot },
+            description: Some(format!("{} {} at {}, {:.0}% to hit", if shoot { "shooting" } else { "aiming at" }, enemy.id, shot.aim_point.to_short_string(), shot.hit_probability * 100.0)),
+            score: shot.hit_probability,
         })
-            .filter(|vec_order| {
-                let fire_target = self.simple_projected_position(enemy, unit);
-                self.position_is_hittable_by(&fire_target.into(), unit, &self.constants, debug_interface)
-            })
     }
 
     fn action_pick_up_shield(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
-        if unit.shield_potions >= self.constants.max_shield_potions_in_inventory {
-            return None;
-        }
-
         let predicate = |loot: &Loot| {
             matches!(loot.item, Item::ShieldPotions{..})
         };
@@ -492,72 +496,51 @@ impl MyStrategy {
     }
 
     fn action_pick_up_ammo(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
-        let weapon_idx = unit.weapon? as usize;
-        let (bow_idx, _) = self.constants.weapons.iter().enumerate().find(|(_, w)| w.name == "Bow").unwrap();
-        let weapon = self.constants.weapons.get(weapon_idx)?;
-
-        if unit.ammo[weapon_idx] >= weapon.max_inventory_ammo {
-            return None;
-        }
-
         let predicate = |loot: &Loot| {
-            matches!(loot.item, Item::Ammo{weapon_type_index: i, ..} if i as usize == weapon_idx || i as usize == bow_idx)
+            matches!(loot.item, Item::Ammo{..})
         };
         self.action_pick_up_loot(unit, game, &predicate, debug_interface)
     }
 
     fn action_pick_up_weapon(&mut self, unit: &Unit, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
-        let (bow_idx, _) = self.constants.weapons.iter().enumerate().find(|(_, w)| w.name == "Bow").unwrap();
-
-        match unit.weapon {
-            Some(i) if i as usize == bow_idx => {
-                return None;
-            }
-            _ => {
-                let predicate = |loot: &Loot| {
-                    matches!(loot.item, Item::Weapon{type_index: i} if i as usize == bow_idx)
-                };
-                self.action_pick_up_loot(unit, game, &predicate, debug_interface)
-            }
-        }
+        let predicate = |loot: &Loot| {
+            matches!(loot.item, Item::Weapon{..})
+        };
+        self.action_pick_up_loot(unit, game, &predicate, debug_interface)
     }
 
-    fn action_pick_up_loot(&mut self, unit: &Unit, game: &Game, predicate: &dyn Fn(&Loot) -> bool, debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
+    fn action_pick_up_loot(&mut self, unit: &Unit, _game: &Game, predicate: &dyn Fn(&Loot) -> bool, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
         if self.is_action_cooldown(unit) {
-            return None;
-        }
-        let order = self
-            .seen_loot
-            .values()
-            .filter(|loot| predicate(*loot))
-            .find(|loot| loot.position.distance_to(&unit.position) <= self.constants.unit_radius)
-            .and_then(|loot| {
-                // println!("removing move target {} for unit {}", loot.id, unit.id);
-                self.move_targets.remove(&loot.id);
-                Some(loot)
-            })
-            .map(|loot| ActionOrder::Pickup { loot: loot.id });
-
-        if let Some(ActionOrder::Pickup { loot: loot_id }) = order {
-            self.seen_loot.remove(&loot_id);
-        }
-        order.map(|action_order| {
-            ActionOrderOrder {
-                description: Some(format!("picking up loot {:?}", action_order)),
-                action_order,
-            }
+            return self.reject("action on cooldown");
+        }
+        let (loot_id, value) = self
+            .loot_within(unit.position, self.constants.unit_radius)
+            .into_iter()
+            .filter(|loot| predicate(loot))
+            .filter(|loot| self.wants_loot(unit, loot))
+            // several may be in reach, don't let the map's order decide
+            .min_by_key(|loot| loot.id)
+            .map(|loot| (loot.id, self.loot_value(unit, &loot.item)))
+            .or_else(|| self.reject("no wanted loot in reach"))?;
+
+        self.move_targets.remove(&loot_id);
+        self.seen_loot.remove(&loot_id);
+        let action_order = ActionOrder::Pickup { loot: loot_id };
+        Some(ActionOrderOrder {
+            description: Some(format!("picking up loot {:?}", action_order)),
+            score: value,
+            action_order,
         })
     }
 
-    pub fn position_is_hittable_by(&self, enemy: &HittableEntity, unit: &Unit, constants: &Constants, debug_interface: &mut Option<&mut DebugInterface>) -> bool {
-        let obstacles_in_los = constants
-            .obstacles
-            .iter()
+    pub fn position_is_hittable_by(&self, enemy: &HittableEntity, unit: &Unit, debug_interface: &mut Option<&mut DebugInterface>) -> bool {
+        let obstacles_in_los = self.obstacle_index
+            .along_segment(enemy.position, unit.position, 0.0)
+            .into_iter()
             .filter(|o| !o.can_shoot_through)
-            .filter(|o| o.intersects_with(&enemy.position, &unit.position))
             .collect::<Vec<_>>();
 
-        if obstacles_in_los.len() > 0 {
+        if !obstacles_in_los.is_empty() {
             if let Some(debug) = debug_interface.as_mut() {
                 for o in obstacles_in_los.iter() {
                     debug.add_circle(o.position, o.radius, Color::red())
@@ -569,38 +552,13 @@ impl MyStrategy {
         }
     }
 
-    fn is_over_loot_move_target(&self, unit: &Unit, game: &Game) -> bool {
+    fn is_over_loot_move_target(&self, unit: &Unit) -> bool {
         self
-            .seen_loot
-            .values()
-            .filter(|loot| loot.position.distance_to(&unit.position) < self.constants.unit_radius)
+            .loot_within(unit.position, self.constants.unit_radius)
+            .iter()
             .any(|loot| self.move_targets.contains_key(&loot.id) && *self.move_targets.get(&loot.id).unwrap() == unit.id)
     }
 
-    pub fn angle_for_leading_shot(&self, enemy: &Unit, unit: &Unit, debug_interface: &mut Option<&mut DebugInterface>) -> f64 {
-        let weapon_idx = unit.weapon.unwrap();
-        let weapon = &self.constants.weapons[weapon_idx as usize];
-        let projectile_speed = weapon.projectile_speed;
-        let enemy_speed = enemy.velocity.length();
-
-        let d = enemy.position - unit.position;
-        let angle_b = (enemy.velocity - enemy.position).angle_with(&(d));
-        let sin_angle_a = angle_b.sin() * enemy_speed / projectile_speed;
-        let angle_a = sin_angle_a.asin(); // abs
-        let angle_c = PI - angle_a - angle_b;
-
-        let moving_right = (enemy.position + enemy.velocity).arg() > d.arg();
-        let result = if moving_right {
-            d.arg() - angle_a
-        } else {
-            d.arg() + angle_a
-        };
-        // if let Some(debug) = debug_interface.as_mut() {
-        //     debug.add_segment(unit.position, unit.position + Vec2::from_length_and_angle(d.length(), result), 0.15, Color::green().a(0.5));
-        // }
-        result
-    }
-
     pub fn is_action_cooldown(&self, unit: &Unit) -> bool {
         if let Some(action) = unit.action.as_ref() {
             action.finish_tick > self.current_tick
@@ -609,19 +567,10 @@ impl MyStrategy {
         }
     }
 
-    pub fn go_to_next_point_from_the_hardcoded_list(&self, unit: &Unit) -> (Vec2, Vec2) {
-        let random_point = Vec2 {
-            x: vec![100.0, -100.0, -100.0, 100.0, -80.0, -80.0, 80.0][(self.current_tick as usize / 30) % 7],
-            y: vec![100.0, 100.0, -100.0, -100.0, -80.0, 80.0, 80.0][(self.current_tick as usize / 30) % 7],
-        };
-        let target_direction = random_point - unit.position;
-        let target_velocity = random_point - unit.position;
-        (target_direction, target_velocity)
-    }
-
-    pub fn clear_waypoint_if_in_storm(&mut self, unit: &Unit, game: &Game) {
+    pub fn clear_waypoint_if_in_storm(&mut self, unit: &Unit) {
         if let Some(wp) = self.waypoints.get(&unit.id) {
-            if game.zone.current_center.distance_to(wp) > game.zone.current_radius {
+            // the storm gets there before we do
+            if self.zone_forecast.time_until_outside(*wp) < self.travel_time(unit, *wp) {
                 self.waypoints.remove(&unit.id);
             }
         }
@@ -634,12 +583,4 @@ impl MyStrategy {
             }
         }
     }
-
-    pub fn simple_projected_position(&self, enemy: &Unit, unit: &Unit) -> Vec2 {
-        let weapon = &self.constants.weapons[unit.weapon.unwrap() as usize];
-        let projectile_travel_time = unit.position.distance_to(&enemy.position) / weapon.projectile_speed;
-        let remaining_aim_time = weapon.aim_time - weapon.aim_time * unit.aim;
-        let fire_target = enemy.position + (enemy.velocity * (projectile_travel_time + remaining_aim_time));
-        fire_target
-    }
 }
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+use itertools::Itertools;
+use crate::model::*;
+use crate::{ActionOrderOrder, DebugInterface, MyStrategy};
+
+// distance weapons are compared at, most fights happen around it
+const ENGAGEMENT_DISTANCE: f64 = 20.0;
+// a loot this many seconds away is worth half as much as one underfoot
+const LOOT_TRAVEL_SECONDS: f64 = 3.0;
+// supplies are only dropped for allies this close
+const SHARE_DISTANCE: f64 = 10.0;
+const KEEP_SHIELD_POTIONS: i32 = 3;
+// the unit that dropped something doesn't pick it up again for this long
+const DROP_MEMORY_SECONDS: f64 = 5.0;
+
+/// Loot a unit is sent to, picked by `plan_loot`.
+#[derive(Clone, Debug)]
+pub struct LootAssignment {
+    pub loot_id: i32,
+    pub value: f64,
+    /// Value discounted by the time it takes to get there
+    pub utility: f64,
+}
+
+/// Damage per second a weapon is expected to deal at `ENGAGEMENT_DISTANCE`, accounting for spread, dodging and aiming.
+pub fn weapon_value(weapon: &WeaponProperties, constants: &Constants) -> f64 {
+    let distance = ENGAGEMENT_DISTANCE.min(weapon.range());
+    if distance <= 0.0 {
+        return 0.0;
+    }
+    let spread_width = distance * (weapon.spread.to_radians() / 2.0).tan();
+    let spread_hit = (constants.unit_radius / spread_width.max(constants.unit_radius)).min(1.0);
+    let dodge_distance = constants.max_unit_forward_speed * distance / weapon.projectile_speed;
+    let dodge_hit = (constants.unit_radius / dodge_distance.max(constants.unit_radius)).clamp(0.1, 1.0);
+    let range_factor = (weapon.range() / ENGAGEMENT_DISTANCE).min(1.0);
+
+    weapon.projectile_damage * weapon.rounds_per_second * spread_hit * dodge_hit * range_factor / (1.0 + weapon.aim_time)
+}
+
+impl MyStrategy {
+    fn weapon_value_by_index(&self, weapon_idx: i32) -> f64 {
+        self.constants.weapons.get(weapon_idx as usize).map_or(0.0, |w| weapon_value(w, &self.constants))
+    }
+
+    fn best_weapon_value(&self) -> f64 {
+        self.constants.weapons.iter().map(|w| weapon_value(w, &self.constants)).fold(0.0, f64::max)
+    }
+
+    /// Value of the weapon the unit holds, a weapon without ammo is worth little.
+    fn current_weapon_value(&self, unit: &Unit) -> f64 {
+        match unit.weapon {
+            Some(w) if unit.ammo[w as usize] > 0 => self.weapon_value_by_index(w),
+            _ => 0.0,
+        }
+    }
+
+    /// Whether the unit has a use for ammo of the weapon: it holds the weapon or would switch to it.
+    /// Any other ammo is only good for handing over, it's never picked up and fine to drop.
+    pub fn keeps_ammo(&self, unit: &Unit, weapon_type_index: i32) -> bool {
+        unit.weapon == Some(weapon_type_index)
+            || self.weapon_value_by_index(weapon_type_index) > self.current_weapon_value(unit)
+    }
+
+    /// How much picking up the item improves the unit's inventory, roughly from 0 to 1; 0 means it's useless.
+    pub fn loot_value(&self, unit: &Unit, item: &Item) -> f64 {
+        let best = self.best_weapon_value();
+        match *item {
+            Item::Weapon { type_index } => {
+                if unit.weapon == Some(type_index) || best <= 0.0 {
+                    return 0.0;
+                }
+                let readiness = if unit.ammo[type_index as usize] > 0 {
+                    1.0
+                } else if self.seen_loot.values().any(|l| matches!(l.item, Item::Ammo { weapon_type_index, .. } if weapon_type_index == type_index)) {
+                    0.3
+                } else {
+                    0.1
+                };
+                let gain = self.weapon_value_by_index(type_index) * readiness - self.current_weapon_value(unit);
+                (gain / best).max(0.0)
+            }
+            Item::Ammo { weapon_type_index, amount } => {
+                let Some(weapon) = self.constants.weapons.get(weapon_type_index as usize) else { return 0.0 };
+                let ammo = unit.ammo[weapon_type_index as usize];
+                if amount.min(weapon.max_inventory_ammo - ammo) <= 0 || !self.keeps_ammo(unit, weapon_type_index) {
+                    return 0.0;
+                }
+                let relevance = if unit.weapon == Some(weapon_type_index) {
+                    1.0
+                } else {
+                    // stocking up for a better weapon
+                    0.5
+                };
+                relevance * (1.0 - ammo as f64 / weapon.max_inventory_ammo as f64)
+            }
+            Item::ShieldPotions { amount } => {
+                let max = self.constants.max_shield_potions_in_inventory;
+                if amount.min(max - unit.shield_potions) <= 0 {
+                    return 0.0;
+                }
+                0.6 * (1.0 - unit.shield_potions as f64 / max as f64)
+            }
+        }
+    }
+
+    /// Whether the unit should pick the loot up when standing on it.
+    pub fn wants_loot(&self, unit: &Unit, loot: &Loot) -> bool {
+        let dropped_here = self.drops.get(&unit.id).is_some_and(|(position, tick)| {
+            position.distance_to(&loot.position) <= self.constants.unit_radius
+                && ((self.current_tick - tick) as f64) < DROP_MEMORY_SECONDS * self.constants.ticks_per_second
+        });
+        !dropped_here && self.loot_value(unit, &loot.item) > 0.0
+    }
+
+    /// Sends every unit to at most one loot, the best value for the trip goes first.
+    pub(crate) fn plan_loot(&mut self, game: &Game) {
+        let strategy: &MyStrategy = self;
+        let units = strategy.my_units.iter()
+            .filter(|u| u.remaining_spawn_time.is_none())
+            .filter(|u| !strategy.is_action_cooldown(u))
+            .collect_vec();
+
+        let candidates = units.iter()
+            .flat_map(|unit| {
+                strategy.seen_loot.values()
+                    .filter(|loot| loot.position.distance_to(&game.zone.current_center) <= game.zone.current_radius * strategy.params.loot_zone_fraction)
+                    .filter(|loot| !strategy.is_in_enemy_fire_range(loot.position))
+                    .filter(|loot| strategy.wants_loot(unit, loot))
+                    .map(move |loot| {
+                        let value = strategy.loot_value(unit, &loot.item);
+                        let utility = value / (1.0 + strategy.travel_time(unit, loot.position) / LOOT_TRAVEL_SECONDS);
+                        (unit.id, LootAssignment { loot_id: loot.id, value, utility })
+                    })
+            })
+            .sorted_by(|(_, a1), (_, a2)| a2.utility.total_cmp(&a1.utility))
+            .collect_vec();
+
+        let mut taken = HashSet::new();
+        let mut plan: HashMap<i32, LootAssignment> = HashMap::new();
+        for (unit_id, assignment) in candidates {
+            if plan.contains_key(&unit_id) || taken.contains(&assignment.loot_id) {
+                continue;
+            }
+            taken.insert(assignment.loot_id);
+            plan.insert(unit_id, assignment);
+        }
+
+        self.move_targets = plan.iter().map(|(unit_id, a)| (a.loot_id, *unit_id)).collect();
+        self.loot_plan = plan;
+    }
+
+    /// Drops supplies the unit can't use for a nearby ally who can.
+    pub(crate) fn action_share_supplies(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
+        if self.is_in_enemy_fire_range(unit.position) {
+            return self.reject("enemies in range");
+        }
+        let allies = self.allies_within(unit.position, SHARE_DISTANCE).into_iter()
+            .filter(|ally| ally.id != unit.id && ally.remaining_spawn_time.is_none())
+            .collect_vec();
+
+        let order = self.shared_ammo(unit, &allies)
+            .or_else(|| self.shared_shield_potions(unit, &allies))
+            .or_else(|| self.shared_weapon(unit, &allies))
+            .or_else(|| self.reject("nothing an ally nearby needs"))?;
+
+        self.drops.insert(unit.id, (unit.position, self.current_tick));
+        Some(order)
+    }
+
+    /// Drops ammo the unit doesn't keep for an ally holding the weapon and running low.
+    pub fn shared_ammo(&self, unit: &Unit, allies: &[&Unit]) -> Option<ActionOrderOrder> {
+        self.constants.weapons.iter().enumerate()
+            .filter(|(w, _)| unit.ammo[*w] > 0 && !self.keeps_ammo(unit, *w as i32))
+            .find_map(|(w, weapon)| {
+                let ally = allies.iter().find(|ally| ally.weapon == Some(w as i32) && ally.ammo[w] < weapon.max_inventory_ammo / 2)?;
+                Some(ActionOrderOrder {
+                    action_order: ActionOrder::DropAmmo { weapon_type_index: w as i32, amount: unit.ammo[w] },
+                    description: Some(format!("dropping {} ammo for {}", weapon.name, ally.id)),
+                    score: 1.0 - ally.ammo[w] as f64 / weapon.max_inventory_ammo as f64,
+                })
+            })
+    }
+
+    fn shared_shield_potions(&self, unit: &Unit, allies: &[&Unit]) -> Option<ActionOrderOrder> {
+        if unit.shield_potions <= KEEP_SHIELD_POTIONS {
+            return None;
+        }
+        let ally = allies.iter().find(|ally| ally.shield_potions == 0)?;
+        Some(ActionOrderOrder {
+            action_order: ActionOrder::DropShieldPotions { amount: unit.shield_potions / 2 },
+            description: Some(format!("dropping shield potions for {}", ally.id)),
+            score: 1.0 - ally.shield / self.constants.max_shield,
+        })
+    }
+
+    /// A weapon without ammo is better off with an ally who has ammo for it and holds something worse.
+    fn shared_weapon(&self, unit: &Unit, allies: &[&Unit]) -> Option<ActionOrderOrder> {
+        let w = unit.weapon?;
+        if unit.ammo[w as usize] > 0 {
+            return None;
+        }
+        let value = self.weapon_value_by_index(w);
+        let ally = allies.iter().find(|ally| ally.ammo[w as usize] > 0 && self.current_weapon_value(ally) < value)?;
+        Some(ActionOrderOrder {
+            action_order: ActionOrder::DropWeapon {},
+            description: Some(format!("dropping {} for {}", self.constants.weapons[w as usize].name, ally.id)),
+            score: 1.0 - self.current_weapon_value(ally) / value,
+        })
+    }
+}
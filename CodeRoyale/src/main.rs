@@ -1,32 +1,15 @@
-mod my_strategy;
-mod debug_interface;
-mod visualization;
-pub use visualization::*;
-mod logic;
-pub use logic::*;
-mod bookkeeping;
-pub use bookkeeping::*;
-mod simulation;
-pub use simulation::*;
-mod simulatable_model;
-pub use simulatable_model::*;
-mod visibility;
-pub use visibility::*;
-mod basic_game_entity;
-pub use basic_game_entity::*;
-mod geometry;
-pub use geometry::*;
-
-pub use basic_game_entity::*;
-
-use debug_interface::DebugInterface;
-use my_strategy::MyStrategy;
 use ai_cup_22::*;
 
 struct Args {
     host: String,
     port: u16,
     token: String,
+    replay_path: Option<String>,
+    behaviours: BehaviourConfig,
+    params: StrategyParams,
+    /// Directory with the weights of an `MlpEvaluator` to score rollouts with, the heuristic one otherwise
+    model: Option<std::path::PathBuf>,
+    time_budget: TimeBudgetConfig,
 }
 
 impl Args {
@@ -38,13 +21,29 @@ impl Args {
             .next()
             .map_or(31001, |s| s.parse().expect("Can't parse port"));
         let token = args.next().unwrap_or_else(|| "0000000000000000".to_owned());
-        Self { host, port, token }
+        let replay_path = args.next();
+        let behaviours = std::env::var("ROYALE_BEHAVIOURS")
+            .map_or_else(|_| BehaviourConfig::default(), |path| BehaviourConfig::from_file(path).expect("Can't read behaviours"));
+        let params = std::env::var("ROYALE_PARAMS")
+            .map_or_else(|_| StrategyParams::default(), |path| StrategyParams::from_file(path).expect("Can't read params"));
+        let model = std::env::var_os("ROYALE_MODEL").map(std::path::PathBuf::from);
+        // a recorded game has to play out the same when replayed, whatever machine it's replayed on
+        let time_budget = TimeBudgetConfig {
+            report_on_finish: std::env::var("ROYALE_PROFILE").is_ok(),
+            ..if replay_path.is_some() { TimeBudgetConfig::reproducible() } else { TimeBudgetConfig::default() }
+        };
+        Self { host, port, token, replay_path, behaviours, params, model, time_budget }
     }
 }
 
 struct Runner {
     reader: Box<dyn std::io::BufRead>,
     writer: Box<dyn std::io::Write>,
+    recorder: Option<ReplayWriter>,
+    behaviours: BehaviourConfig,
+    params: StrategyParams,
+    model: Option<std::path::PathBuf>,
+    time_budget: TimeBudgetConfig,
 }
 
 impl Runner {
@@ -61,31 +60,58 @@ impl Runner {
         1i32.write_to(&mut writer)?;
         1i32.write_to(&mut writer)?;
         writer.flush()?;
+        let recorder = args.replay_path.as_ref().map(ReplayWriter::create).transpose()?;
         Ok(Self {
             reader: Box::new(reader),
             writer: Box::new(writer),
+            recorder,
+            behaviours: args.behaviours.clone(),
+            params: args.params.clone(),
+            model: args.model.clone(),
+            time_budget: args.time_budget.clone(),
         })
     }
-    fn debug_interface(&mut self) -> DebugInterface {
-        DebugInterface::new(&mut self.reader, &mut self.writer)
+    fn debug_interface(&mut self) -> DebugInterface<'_> {
+        DebugInterface::with_recorder(&mut self.reader, &mut self.writer, self.recorder.as_mut())
+    }
+    fn send(&mut self, message: codegame::ClientMessage) -> std::io::Result<()> {
+        use trans::Trans;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_client(&message)?;
+        }
+        message.write_to(&mut self.writer)?;
+        self.writer.flush()
     }
     fn run(mut self) -> std::io::Result<()> {
         use trans::Trans;
         let mut strategy = None;
         loop {
-            match codegame::ServerMessage::read_from(&mut self.reader)? {
+            let message = codegame::ServerMessage::read_from(&mut self.reader)?;
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.record_server(&message)?;
+            }
+            match message {
                 codegame::ServerMessage::UpdateConstants {
                     constants
                 } => {
-                    strategy = Some(MyStrategy::new(constants));
+                    let behaviours = BehaviourTree::from_config(&self.behaviours).map_err(std::io::Error::other)?;
+                    let evaluator = self.model.as_ref().map(|dir| MlpEvaluator::load(dir, &constants)).transpose()?;
+                    let mut new_strategy = MyStrategy::with_behaviours(constants, behaviours)
+                        .with_time_budget(self.time_budget.clone())
+                        .with_params(self.params.clone());
+                    if let Some(evaluator) = evaluator {
+                        new_strategy = new_strategy.with_evaluator(std::rc::Rc::new(evaluator));
+                    }
+                    strategy = Some(new_strategy);
                 }
                 codegame::ServerMessage::GetOrder {
                     player_view,
                     debug_available,
                 } => {
+                    let strategy = started(&mut strategy, "GetOrder")?;
                     let mut debug_interface = self.debug_interface();
                     let message = codegame::ClientMessage::OrderMessage {
-                        order: strategy.as_mut().unwrap().get_order(
+                        order: strategy.get_order(
                             &player_view,
                             if debug_available {
                                 Some(&mut debug_interface)
@@ -94,17 +120,15 @@ impl Runner {
                             },
                         ),
                     };
-                    message.write_to(&mut self.writer)?;
-                    self.writer.flush()?;
+                    self.send(message)?;
                 }
                 codegame::ServerMessage::Finish {} => {
-                    strategy.as_mut().unwrap().finish();
+                    started(&mut strategy, "Finish")?.finish();
                     break;
                 }
                 codegame::ServerMessage::DebugUpdate { displayed_tick } => {
-                    strategy.as_mut().unwrap().debug_update(displayed_tick, &mut self.debug_interface());
-                    codegame::ClientMessage::DebugUpdateDone {}.write_to(&mut self.writer)?;
-                    self.writer.flush()?;
+                    started(&mut strategy, "DebugUpdate")?.debug_update(displayed_tick, &mut self.debug_interface());
+                    self.send(codegame::ClientMessage::DebugUpdateDone {})?;
                 }
             }
         }
@@ -112,6 +136,14 @@ impl Runner {
     }
 }
 
+/// The strategy, which only exists once the server has sent the constants.
+fn started<'a>(strategy: &'a mut Option<MyStrategy>, message: &str) -> std::io::Result<&'a mut MyStrategy> {
+    strategy.as_mut().ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("server sent {} before UpdateConstants", message),
+    ))
+}
+
 fn main() -> std::io::Result<()> {
     Runner::new(&Args::parse())?.run()
 }
\ No newline at end of file
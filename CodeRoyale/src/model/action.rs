@@ -15,7 +15,7 @@ impl trans::Trans for Action {
         self.action_type.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let finish_tick: i32 = trans::Trans::read_from(reader)?;
         let action_type: model::ActionType = trans::Trans::read_from(reader)?;
         Ok(Self {
@@ -73,7 +73,7 @@ impl trans::Trans for ActionOrder {
         }
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let tag = <i32 as trans::Trans>::read_from(reader)?;
         match tag {
             0 => {
@@ -110,9 +110,7 @@ impl trans::Trans for ActionOrder {
                     shoot,
                 })
             }
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Unexpected tag {:?}", tag))),
+            _ => Err(trans::TransError::UnexpectedTag { type_name: "ActionOrder", tag }),
         }
     }
 }
\ No newline at end of file
@@ -17,14 +17,12 @@ impl trans::Trans for ActionType {
         };
         trans::Trans::write_to(&tag, writer)
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let tag = <i32 as trans::Trans>::read_from(reader)?;
         match tag {
             0 => Ok(Self::Looting),
             1 => Ok(Self::UseShieldPotion),
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Unexpected tag {:?}", tag))),
+            _ => Err(trans::TransError::UnexpectedTag { type_name: "ActionType", tag }),
         }
     }
 }
\ No newline at end of file
@@ -126,7 +126,7 @@ impl trans::Trans for Constants {
         self.obstacles.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let ticks_per_second: f64 = trans::Trans::read_from(reader)?;
         let team_size: i32 = trans::Trans::read_from(reader)?;
         let initial_zone_radius: f64 = trans::Trans::read_from(reader)?;
@@ -33,7 +33,7 @@ impl trans::Trans for Game {
         self.sounds.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let my_id: i32 = trans::Trans::read_from(reader)?;
         let players: Vec<model::Player> = trans::Trans::read_from(reader)?;
         let current_tick: i32 = trans::Trans::read_from(reader)?;
@@ -1,5 +1,5 @@
-use std::f64::consts::FRAC_PI_2;
 use crate::model::*;
+use crate::segment_intersects_circle;
 
 pub struct HittableEntity {
     pub position: Vec2,
@@ -8,13 +8,7 @@ pub struct HittableEntity {
 
 impl HittableEntity {
     pub fn intersects_with(&self, p0: &Vec2, p1: &Vec2) -> bool {
-        let p = p0.sub(p1);
-        let c = self.position.sub(p1);
-        let angle = p.angle_with(&c);
-        let perp_radius = c.length() * angle.sin();
-        perp_radius <= self.radius &&
-            c.length() < p.length() &&
-            angle < FRAC_PI_2
+        segment_intersects_circle(*p0, *p1, self.position, self.radius)
     }
 
     pub fn from_position_and_radius(position: Vec2, radius: f64) -> Self {
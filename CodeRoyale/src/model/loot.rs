@@ -19,7 +19,7 @@ impl trans::Trans for Loot {
         self.item.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let id: i32 = trans::Trans::read_from(reader)?;
         let position: model::Vec2 = trans::Trans::read_from(reader)?;
         let item: model::Item = trans::Trans::read_from(reader)?;
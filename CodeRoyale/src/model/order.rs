@@ -12,7 +12,7 @@ impl trans::Trans for Order {
         self.unit_orders.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let unit_orders: std::collections::HashMap<i32, model::UnitOrder> = trans::Trans::read_from(reader)?;
         Ok(Self {
             unit_orders,
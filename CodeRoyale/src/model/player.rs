@@ -24,7 +24,7 @@ impl trans::Trans for Player {
         self.score.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let id: i32 = trans::Trans::read_from(reader)?;
         let kills: i32 = trans::Trans::read_from(reader)?;
         let damage: f64 = trans::Trans::read_from(reader)?;
@@ -32,7 +32,7 @@ impl trans::Trans for Projectile {
         self.life_time.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let id: i32 = trans::Trans::read_from(reader)?;
         let weapon_type_index: i32 = trans::Trans::read_from(reader)?;
         let shooter_id: i32 = trans::Trans::read_from(reader)?;
@@ -18,7 +18,7 @@ impl trans::Trans for Sound {
         self.position.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let type_index: i32 = trans::Trans::read_from(reader)?;
         let unit_id: i32 = trans::Trans::read_from(reader)?;
         let position: model::Vec2 = trans::Trans::read_from(reader)?;
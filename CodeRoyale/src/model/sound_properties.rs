@@ -18,7 +18,7 @@ impl trans::Trans for SoundProperties {
         self.offset.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let name: String = trans::Trans::read_from(reader)?;
         let distance: f64 = trans::Trans::read_from(reader)?;
         let offset: f64 = trans::Trans::read_from(reader)?;
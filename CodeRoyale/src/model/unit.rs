@@ -39,13 +39,6 @@ pub struct Unit {
     pub seen_on_tick: i32,
 }
 
-pub enum LootPriority {
-    Weapon,
-    Shield,
-    Ammo,
-    Whatever,
-}
-
 impl Unit {
     pub fn intersects_with(&self, p0: &Vec2, p1: &Vec2) -> bool {
         HittableEntity::from(self).intersects_with(p0, p1)
@@ -62,29 +55,6 @@ impl Unit {
         }
     }
 
-    pub fn priority(&self) -> LootPriority {
-
-        let weapon_idx = self.weapon.unwrap_or(2) as usize;
-        let bow_idx = 2;
-        let max_ammo = [100, 250, 25];
-
-        for widx in [weapon_idx, bow_idx] {
-            if self.ammo[widx] <= max_ammo[widx] / 5 {
-                return LootPriority::Ammo
-            }
-        }
-
-        if self.weapon.is_none() || self.weapon.unwrap() != 2 {
-            return LootPriority::Weapon{}
-        }
-
-        if self.shield_potions < 3 {
-            return LootPriority::Shield
-        }
-
-        LootPriority::Whatever
-    }
-
     pub fn weapon_range(&self, constants: &Constants) -> f64 {
         match self.get_weapon(constants) {
             None => 0.0,
@@ -117,7 +87,7 @@ impl trans::Trans for Unit {
         self.shield_potions.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let id: i32 = trans::Trans::read_from(reader)?;
         let player_id: i32 = trans::Trans::read_from(reader)?;
         let health: f64 = trans::Trans::read_from(reader)?;
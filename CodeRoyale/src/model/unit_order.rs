@@ -18,7 +18,7 @@ impl trans::Trans for UnitOrder {
         self.action.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let target_velocity: model::Vec2 = trans::Trans::read_from(reader)?;
         let target_direction: model::Vec2 = trans::Trans::read_from(reader)?;
         let action: Option<model::ActionOrder> = trans::Trans::read_from(reader)?;
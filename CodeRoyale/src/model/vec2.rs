@@ -1,7 +1,6 @@
 use super::*;
 use core::fmt;
 use std::f64::consts::PI;
-use std::iter::Sum;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 /// 2 dimensional vector.
@@ -42,7 +41,7 @@ impl Vec2 {
     // if( cross > 0.0f)
     // turn right
     pub fn cross_product(&self, other: &Self) -> f64 {
-        return self.x * other.y - self.y * other.x;
+        self.x * other.y - self.y * other.x
     }
 
     pub fn dot_product(&self, other: &Self) -> f64 {
@@ -175,7 +174,7 @@ impl trans::Trans for Vec2 {
         self.y.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let x: f64 = trans::Trans::read_from(reader)?;
         let y: f64 = trans::Trans::read_from(reader)?;
         Ok(Self { x, y })
@@ -54,7 +54,7 @@ impl trans::Trans for WeaponProperties {
         self.max_inventory_ammo.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let name: String = trans::Trans::read_from(reader)?;
         let rounds_per_second: f64 = trans::Trans::read_from(reader)?;
         let spread: f64 = trans::Trans::read_from(reader)?;
@@ -21,7 +21,7 @@ impl trans::Trans for Zone {
         self.next_radius.write_to(writer)?;
         Ok(())
     }
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, trans::TransError> {
         let current_center: model::Vec2 = trans::Trans::read_from(reader)?;
         let current_radius: f64 = trans::Trans::read_from(reader)?;
         let next_center: model::Vec2 = trans::Trans::read_from(reader)?;
@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+use std::time::Duration;
+use crate::model::*;
+use crate::simulation::{SimulationResult, Simulator};
+use crate::Evaluator;
+use crate::spatial_index::ObstacleGrid;
+use crate::time_budget::Deadline;
+
+#[derive(Clone, Debug)]
+pub struct MovementSearchConfig {
+    /// Headings tried at every step, evenly spread around the circle
+    pub headings: usize,
+    /// Speeds tried for every heading, as fractions of the max forward speed
+    pub speeds: Vec<f64>,
+    /// How many ticks each order of a plan is held
+    pub ticks_per_step: usize,
+    /// Number of orders in a plan
+    pub depth: usize,
+    /// Plans kept after every step
+    pub beam_width: usize,
+    /// Most the search may take, the tick's time budget can cut it shorter
+    pub time_budget: Duration,
+}
+
+impl Default for MovementSearchConfig {
+    fn default() -> Self {
+        Self {
+            headings: 12,
+            speeds: vec![1.0, 0.5],
+            ticks_per_step: 5,
+            depth: 3,
+            beam_width: 6,
+            time_budget: Duration::from_millis(5),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MovementPlan {
+    pub orders: Vec<UnitOrder>,
+    pub result: SimulationResult,
+    pub cost: f64,
+    /// Where the unit is expected to be after each order
+    pub positions: Vec<Vec2>,
+}
+
+struct Node<'a> {
+    simulator: Simulator<'a>,
+    plan: MovementPlan,
+}
+
+/// Beam search over sequences of unit orders, every candidate is played out by `Simulator`.
+pub struct MovementSearch<'a> {
+    config: &'a MovementSearchConfig,
+    /// Scores every step's rollout
+    evaluator: &'a dyn Evaluator,
+    constants: &'a Constants,
+    obstacles: &'a ObstacleGrid,
+}
+
+impl<'a> MovementSearch<'a> {
+    pub fn new(config: &'a MovementSearchConfig, evaluator: &'a dyn Evaluator, constants: &'a Constants, obstacles: &'a ObstacleGrid) -> Self {
+        Self { config, evaluator, constants, obstacles }
+    }
+
+    /// Best plan found before the deadline, the first step is always searched in full so there is one.
+    pub fn run(&self, game: &Game, unit: &Unit, deadline: Deadline) -> Option<MovementPlan> {
+        let mut simulated_ticks = 0;
+
+        let root = Node {
+            simulator: Simulator::new(game, self.constants, self.obstacles, unit.id, UnitOrder {
+                target_velocity: Vec2::zero(),
+                target_direction: unit.direction,
+                action: None,
+            }),
+            plan: MovementPlan { orders: vec![], result: SimulationResult::default(), cost: 0.0, positions: vec![] },
+        };
+        let original_direction = if unit.velocity.length() > 0.0 { unit.velocity } else { unit.direction };
+
+        let mut beam = vec![root];
+        let mut best: Option<MovementPlan> = None;
+        'search: for _ in 0..self.config.depth {
+            let mut children = vec![];
+            for node in beam.iter() {
+                for order in self.candidate_orders(unit, original_direction) {
+                    if best.is_some() && deadline.reached(simulated_ticks) {
+                        break 'search;
+                    }
+                    simulated_ticks += self.config.ticks_per_step;
+                    let mut simulator = node.simulator.clone();
+                    simulator.set_unit_order(order.clone());
+                    let result = simulator.simulate_n_ticks(self.config.ticks_per_step, &mut None);
+                    let mut orders = node.plan.orders.clone();
+                    orders.push(order);
+                    let mut positions = node.plan.positions.clone();
+                    positions.extend(simulator.unit().map(|u| u.position));
+                    let cost = self.evaluator.cost(&simulator);
+                    children.push(Node { simulator, plan: MovementPlan { orders, result, cost, positions } });
+                }
+            }
+            if children.is_empty() {
+                break;
+            }
+
+            children.sort_by(|n1, n2| n1.plan.cost.total_cmp(&n2.plan.cost));
+            children.truncate(self.config.beam_width);
+            best = Some(children[0].plan.clone());
+            beam = children;
+        }
+
+        // a level cut short by the budget is dropped, its costs cover more ticks than the previous level's
+        best
+    }
+
+    fn candidate_orders(&self, unit: &Unit, original_direction: Vec2) -> Vec<UnitOrder> {
+        let mut orders = vec![];
+        for heading in 0..self.config.headings {
+            let angle = 2.0 * PI * heading as f64 / self.config.headings as f64;
+            for speed in self.config.speeds.iter() {
+                orders.push(UnitOrder {
+                    target_velocity: Vec2::from_length_and_angle(self.constants.max_unit_forward_speed * speed, original_direction.angle()).rotate(angle),
+                    target_direction: unit.direction,
+                    action: None,
+                });
+            }
+        }
+        orders
+    }
+}
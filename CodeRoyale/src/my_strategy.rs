@@ -1,10 +1,26 @@
 use std::collections::HashMap;
-use ai_cup_22::debugging::Color;
+use std::rc::Rc;
+use std::time::Instant;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 
-use ai_cup_22::model::*;
+use crate::model::*;
 
 use crate::debug_interface::DebugInterface;
-use crate::simulation::Simulator;
+use crate::enemy_beliefs::EnemyBelief;
+use crate::visibility::VisibilityMask;
+use crate::behaviour_tree::BehaviourTree;
+use crate::team::TeamPlan;
+use crate::zone_forecast::ZoneForecast;
+use crate::path_planner::{PathPlanner, UnitPath};
+use crate::loot_economy::LootAssignment;
+use crate::spatial_index::{DynamicIndex, ObstacleGrid};
+use crate::time_budget::{TimeBudget, TimeBudgetConfig};
+use crate::debug_console::DebugConsole;
+use crate::sustain::SustainPlan;
+use crate::cover_map::{CoverMap, PeekPlan};
+use crate::evaluator::{Evaluator, HeuristicEvaluator};
+use crate::strategy_params::StrategyParams;
 
 pub struct MyStrategy {
     pub(crate) constants: Constants,
@@ -16,14 +32,62 @@ pub struct MyStrategy {
     pub(crate) seen_loot: HashMap<i32, Loot>,
     pub(crate) seen_projectiles: HashMap<i32, Projectile>,
     pub(crate) current_tick: i32,
-    pub(crate) next_positions: HashMap<i32, (Vec2, Vec2, Vec2)>,
     pub(crate) waypoints: HashMap<i32, Vec2>,
     pub(crate) next_imaginary_id: i32,
+    pub(crate) enemy_beliefs: HashMap<i32, EnemyBelief>,
+    pub(crate) observed: VisibilityMask,
+    pub(crate) params: StrategyParams,
+    /// Scores movement search rollouts
+    pub(crate) evaluator: Rc<dyn Evaluator>,
+    pub(crate) behaviours: Rc<BehaviourTree>,
+    pub(crate) team_plan: TeamPlan,
+    pub(crate) zone_forecast: ZoneForecast,
+    pub(crate) rotation_targets: HashMap<i32, Vec2>,
+    pub(crate) path_planner: Rc<PathPlanner>,
+    pub(crate) paths: HashMap<i32, UnitPath>,
+    pub(crate) loot_plan: HashMap<i32, LootAssignment>,
+    /// Where and when each unit last dropped something
+    pub(crate) drops: HashMap<i32, (Vec2, i32)>,
+    pub(crate) sustain_plan: HashMap<i32, SustainPlan>,
+    pub(crate) obstacle_index: Rc<ObstacleGrid>,
+    pub(crate) cover_map: Rc<CoverMap>,
+    pub(crate) peeks: HashMap<i32, PeekPlan>,
+    pub(crate) landing_spots: HashMap<i32, Vec2>,
+    /// Loot each unit goes for one after another since it spawned, by unit id
+    pub(crate) loadout_paths: HashMap<i32, Vec<i32>>,
+    pub(crate) dynamic_index: DynamicIndex,
+    pub(crate) time_budget: TimeBudget,
+    pub(crate) debug_console: DebugConsole,
+    /// Seeded from the game, so a replayed game makes the same random choices
+    pub(crate) rng: StdRng,
 }
 
 impl MyStrategy {
     pub fn new(constants: Constants) -> Self {
+        Self::with_behaviours(constants, BehaviourTree::default())
+    }
+
+    pub fn with_behaviours(constants: Constants, behaviours: BehaviourTree) -> Self {
+        let zone = Zone {
+            current_center: Vec2::zero(),
+            current_radius: constants.initial_zone_radius,
+            next_center: Vec2::zero(),
+            next_radius: constants.initial_zone_radius,
+        };
+        let obstacle_index = Rc::new(ObstacleGrid::new(&constants));
         Self {
+            rng: StdRng::seed_from_u64(game_seed(&constants)),
+            zone_forecast: ZoneForecast::new(&zone, &constants),
+            path_planner: Rc::new(PathPlanner::new(&constants, obstacle_index.clone())),
+            cover_map: Rc::new(CoverMap::new(&constants, &obstacle_index)),
+            peeks: HashMap::new(),
+            landing_spots: HashMap::new(),
+            loadout_paths: HashMap::new(),
+            obstacle_index,
+            dynamic_index: DynamicIndex::default(),
+            time_budget: TimeBudget::default(),
+            debug_console: DebugConsole::default(),
+            paths: HashMap::new(),
             constants,
             units_by_id: HashMap::new(),
             my_units: vec![],
@@ -33,48 +97,88 @@ impl MyStrategy {
             seen_loot: HashMap::new(),
             seen_projectiles: HashMap::new(),
             current_tick: 0,
-            next_positions: HashMap::new(),
             waypoints: HashMap::new(),
             next_imaginary_id: -1,
+            enemy_beliefs: HashMap::new(),
+            observed: VisibilityMask::default(),
+            params: StrategyParams::default(),
+            evaluator: Rc::new(HeuristicEvaluator::default()),
+            behaviours: Rc::new(behaviours),
+            team_plan: TeamPlan::default(),
+            rotation_targets: HashMap::new(),
+            loot_plan: HashMap::new(),
+            drops: HashMap::new(),
+            sustain_plan: HashMap::new(),
         }
     }
+
+    pub fn with_time_budget(mut self, config: TimeBudgetConfig) -> Self {
+        self.time_budget = TimeBudget::new(config);
+        self
+    }
+
+    /// Scores movement search rollouts with the evaluator instead of the heuristic one.
+    pub fn with_evaluator(mut self, evaluator: Rc<dyn Evaluator>) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+
+    pub fn with_params(mut self, params: StrategyParams) -> Self {
+        self.params = params;
+        self
+    }
+
     pub fn get_order(
         &mut self,
         game: &Game,
         mut debug_interface: Option<&mut DebugInterface>,
     ) -> Order {
+        self.time_budget.start_tick();
         if let Some(debug) = debug_interface.as_mut() {
             debug.clear();
             debug.set_auto_flush(false);
         }
-
+        self.debug_console.tracing = debug_interface.is_some();
+        self.debug_console.traces.clear();
+        self.debug_console.rollouts.clear();
 
         let mut orders = HashMap::new();
 
+        let started_at = Instant::now();
         self.rebuild_indexes(game);
-        // self.process_sounds(&game);
-        self.check_expected_position_vs_actual(game, &mut debug_interface);
+        self.time_budget.record("rebuild_indexes", started_at.elapsed());
+        let started_at = Instant::now();
+        self.update_zone_forecast(game);
+        self.plan_team();
+        self.plan_loot(game);
+        self.plan_spawns(game);
+        self.plan_sustain(game);
+        self.plan_peeks();
+        self.time_budget.record("planning", started_at.elapsed());
 
         self.show_status_labels_for_units(&mut debug_interface);
 
+        let mut units_left = game.units.iter().filter(|u| u.player_id == game.my_id).count();
         for unit in game.units.iter() {
             if unit.player_id != game.my_id {
                 continue;
             }
+            self.time_budget.start_unit(units_left);
+            units_left -= 1;
 
             self.visualize_sounds(unit, game, &mut debug_interface);
-            self.visualize_weapon_ranges(game, &mut debug_interface);
+            if self.debug_console.layers.weapon_ranges {
+                self.visualize_weapon_ranges(game, &mut debug_interface);
+            }
             self.visualize_projectiles(game, &mut debug_interface);
             self.visualize_waypoint(unit, &mut debug_interface);
-            self.clear_waypoint_if_in_storm(unit, game);
+            self.clear_waypoint_if_in_storm(unit);
             self.clear_waypoint_if_reached(unit);
 
             let target_direction: Vec2 = self.get_direction(unit, game, &mut debug_interface);
             let action: Option<ActionOrder> = self.get_action_order(unit, game, &mut debug_interface);
             let target_velocity: Vec2 = self.get_velocity(unit, game, &mut debug_interface);
 
-            // let (target_direction, target_velocity) = self.go_to_next_point_from_the_hardcoded_list(unit);
-
             // if let Some(debug) = debug_interface.as_mut() {
             //     debug.add_segment(unit.position, unit.position + target_velocity, 0.2, Color::blue());
             // };
@@ -85,18 +189,40 @@ impl MyStrategy {
                 action,
             };
 
-            self.predict_next_positions(game, unit, &unit_order, &mut debug_interface);
             orders.insert(unit.id, unit_order);
         }
+        if self.debug_console.layers.beliefs {
+            self.visualize_enemy_beliefs(&mut debug_interface);
+        }
+        if self.debug_console.layers.loot {
+            self.visualize_loot_plan(&mut debug_interface);
+            self.visualize_spawn_plans(&mut debug_interface);
+        }
+        if self.debug_console.layers.rollouts {
+            self.visualize_rollouts(&mut debug_interface);
+        }
+        if self.debug_console.layers.cover {
+            self.visualize_cover(&mut debug_interface);
+        }
         if let Some(debug) = debug_interface.as_mut() {
             debug.flush();
         }
+        self.time_budget.end_tick();
         Order {
             unit_orders: orders,
         }
     }
 
-    pub fn finish(&mut self) {}
-
+    pub fn finish(&mut self) {
+        if self.time_budget.config.report_on_finish {
+            eprint!("{}", self.time_budget.report());
+        }
+    }
+}
 
+/// The server doesn't tell the seed, the obstacles it generated from it are the same every time though.
+fn game_seed(constants: &Constants) -> u64 {
+    constants.obstacles.iter().fold(constants.obstacles.len() as u64, |seed, o| {
+        seed.rotate_left(7) ^ o.position.x.to_bits() ^ o.position.y.to_bits().rotate_left(32)
+    })
 }
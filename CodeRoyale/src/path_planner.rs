@@ -0,0 +1,253 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::f64::consts::SQRT_2;
+use std::rc::Rc;
+use crate::model::*;
+use crate::{DebugInterface, MyStrategy, ObstacleGrid, Vec2Order};
+use crate::debugging::Color;
+
+const CELL_SIZE: f64 = 1.0;
+// how far to look for a free cell when the start or the goal is inside an inflated obstacle
+const MAX_SNAP_CELLS: i64 = 3;
+// unreachable goals would otherwise flood the whole grid
+const MAX_EXPANSIONS: usize = 30_000;
+
+/// Occupancy grid over the obstacles inflated by the unit radius, built once per game.
+pub struct PathPlanner {
+    origin: Vec2,
+    size: usize,
+    blocked: Vec<bool>,
+    obstacle_index: Rc<ObstacleGrid>,
+    clearance: f64,
+}
+
+impl PathPlanner {
+    pub fn new(constants: &Constants, obstacle_index: Rc<ObstacleGrid>) -> Self {
+        let half_extent = constants.initial_zone_radius + constants.unit_radius;
+        let size = (2.0 * half_extent / CELL_SIZE).ceil() as usize + 1;
+        let origin = Vec2::from_xy(-half_extent, -half_extent);
+        let clearance = constants.unit_radius;
+
+        let mut planner = Self {
+            origin,
+            size,
+            blocked: vec![false; size * size],
+            obstacle_index,
+            clearance,
+        };
+        // cell centers get a bit of extra room, moving between them can cut corners
+        let inflation = clearance + CELL_SIZE / 2.0;
+        for o in constants.obstacles.iter() {
+            let r = o.radius + inflation;
+            let (min_x, min_y) = planner.cell_xy(o.position - Vec2::from_xy(r, r));
+            let (max_x, max_y) = planner.cell_xy(o.position + Vec2::from_xy(r, r));
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    if planner.cell_center(x, y).distance_to(&o.position) < r {
+                        planner.blocked[y * size + x] = true;
+                    }
+                }
+            }
+        }
+        planner
+    }
+
+    fn cell_xy(&self, point: Vec2) -> (usize, usize) {
+        let x = ((point.x - self.origin.x) / CELL_SIZE).floor().clamp(0.0, (self.size - 1) as f64);
+        let y = ((point.y - self.origin.y) / CELL_SIZE).floor().clamp(0.0, (self.size - 1) as f64);
+        (x as usize, y as usize)
+    }
+
+    fn cell_center(&self, x: usize, y: usize) -> Vec2 {
+        self.origin + Vec2::from_xy((x as f64 + 0.5) * CELL_SIZE, (y as f64 + 0.5) * CELL_SIZE)
+    }
+
+    /// Whether a unit can walk the segment without touching an obstacle.
+    pub fn is_segment_clear(&self, from: Vec2, to: Vec2) -> bool {
+        !self.obstacle_index.along_segment(from, to, self.clearance).into_iter()
+            .any(|o| {
+                // already touching it, only moving further in is a problem
+                from.distance_to(&o.position) >= o.radius + self.clearance
+                    || (to - from).dot_product(&(o.position - from)) > 0.0
+            })
+    }
+
+    /// Smoothed waypoints from `from` to `to` (the last one is `to` itself), `None` if there's no way.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        if self.is_segment_clear(from, to) {
+            return Some(vec![to]);
+        }
+
+        let start = self.snap_to_free(self.cell_xy(from))?;
+        let goal = self.snap_to_free(self.cell_xy(to))?;
+        let cells = self.a_star(start, goal)?;
+
+        let mut points = cells.iter().map(|(x, y)| self.cell_center(*x, *y)).collect::<Vec<_>>();
+        points.push(to);
+        Some(self.smooth(from, &points))
+    }
+
+    fn snap_to_free(&self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        let mut best = None;
+        for dy in -MAX_SNAP_CELLS..=MAX_SNAP_CELLS {
+            for dx in -MAX_SNAP_CELLS..=MAX_SNAP_CELLS {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if nx < 0 || ny < 0 || nx >= self.size as i64 || ny >= self.size as i64 {
+                    continue;
+                }
+                if !self.blocked[ny as usize * self.size + nx as usize] {
+                    let d = dx * dx + dy * dy;
+                    if best.is_none_or(|(bd, _)| d < bd) {
+                        best = Some((d, (nx as usize, ny as usize)));
+                    }
+                }
+            }
+        }
+        best.map(|(_, cell)| cell)
+    }
+
+    fn a_star(&self, start: (usize, usize), goal: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        let index = |(x, y): (usize, usize)| y * self.size + x;
+        let heuristic = |(x, y): (usize, usize)| {
+            let dx = (x as f64 - goal.0 as f64).abs();
+            let dy = (y as f64 - goal.1 as f64).abs();
+            dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy)
+        };
+        // costs are kept in thousandths of a cell so the heap can order them
+        let scale = |cost: f64| (cost * 1000.0) as u64;
+
+        let mut cost_so_far: HashMap<usize, f64> = HashMap::new();
+        let mut came_from: HashMap<usize, (usize, usize)> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        cost_so_far.insert(index(start), 0.0);
+        queue.push(Reverse((scale(heuristic(start)), start)));
+
+        let mut expansions = 0;
+        while let Some(Reverse((_, current))) = queue.pop() {
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                return None;
+            }
+            if current == goal {
+                let mut cells = vec![current];
+                let mut cell = current;
+                while let Some(prev) = came_from.get(&index(cell)) {
+                    cell = *prev;
+                    cells.push(cell);
+                }
+                cells.reverse();
+                return Some(cells);
+            }
+
+            let current_cost = cost_so_far[&index(current)];
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                let (nx, ny) = (current.0 as i64 + dx, current.1 as i64 + dy);
+                if nx < 0 || ny < 0 || nx >= self.size as i64 || ny >= self.size as i64 {
+                    continue;
+                }
+                let next = (nx as usize, ny as usize);
+                if self.blocked[index(next)] {
+                    continue;
+                }
+                // no squeezing diagonally between two blocked cells
+                if dx != 0 && dy != 0
+                    && (self.blocked[index((nx as usize, current.1))] || self.blocked[index((current.0, ny as usize))]) {
+                    continue;
+                }
+
+                let step = if dx != 0 && dy != 0 { SQRT_2 } else { 1.0 };
+                let new_cost = current_cost + step;
+                if cost_so_far.get(&index(next)).is_none_or(|c| new_cost < *c) {
+                    cost_so_far.insert(index(next), new_cost);
+                    came_from.insert(index(next), current);
+                    queue.push(Reverse((scale(new_cost + heuristic(next)), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// String pulling: keeps only the points where the straight line to the next one would hit something.
+    fn smooth(&self, from: Vec2, points: &[Vec2]) -> Vec<Vec2> {
+        let mut result = vec![];
+        let mut anchor = from;
+        for idx in 1..points.len() {
+            if !self.is_segment_clear(anchor, points[idx]) {
+                anchor = points[idx - 1];
+                result.push(anchor);
+            }
+        }
+        result.push(*points.last().unwrap());
+        result
+    }
+}
+
+/// Path a unit is following, replanned when the goal moves or the next waypoint gets out of reach.
+#[derive(Clone, Debug)]
+pub struct UnitPath {
+    pub goal: Vec2,
+    pub waypoints: Vec<Vec2>,
+}
+
+impl UnitPath {
+    pub fn next_waypoint(&mut self, position: Vec2, reached_distance: f64) -> Option<Vec2> {
+        while self.waypoints.len() > 1 && self.waypoints[0].distance_to(&position) < reached_distance {
+            self.waypoints.remove(0);
+        }
+        self.waypoints.first().copied()
+    }
+}
+
+impl MyStrategy {
+    /// Turns an order heading for a target into one following the planned path, other orders are only steered.
+    pub(crate) fn route_order(&mut self, unit: &Unit, vec_order: Vec2Order, debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        if unit.remaining_spawn_time.is_some() {
+            // spawning units pass through obstacles, there's nothing to go around
+            return Some(vec_order);
+        }
+        let Some(goal) = vec_order.target else {
+            return self.velocity_steer_around_obstacles(unit, vec_order, debug_interface);
+        };
+        let reached_distance = self.constants.unit_radius;
+
+        let cached = self.paths.get_mut(&unit.id)
+            .filter(|path| path.goal.distance_to(&goal) < reached_distance)
+            .and_then(|path| path.next_waypoint(unit.position, reached_distance))
+            .filter(|waypoint| self.path_planner.is_segment_clear(unit.position, *waypoint));
+
+        let waypoint = match cached {
+            Some(waypoint) => waypoint,
+            None => match self.path_planner.find_path(unit.position, goal) {
+                Some(waypoints) => {
+                    let mut path = UnitPath { goal, waypoints };
+                    let waypoint = path.next_waypoint(unit.position, reached_distance);
+                    self.paths.insert(unit.id, path);
+                    waypoint?
+                }
+                None => {
+                    self.paths.remove(&unit.id);
+                    return self.velocity_steer_around_obstacles(unit, vec_order, debug_interface);
+                }
+            },
+        };
+
+        if let Some(debug) = debug_interface.as_mut() {
+            if let Some(path) = self.paths.get(&unit.id) {
+                let mut from = unit.position;
+                for p in path.waypoints.iter() {
+                    debug.add_segment(from, *p, 0.1, Color::blue().a(0.4));
+                    from = *p;
+                }
+            }
+        }
+
+        let direction = waypoint - unit.position;
+        if direction.length() == 0.0 {
+            return Some(vec_order);
+        }
+        Some(Vec2Order {
+            vec: Vec2::from_length_and_angle(vec_order.vec.length(), direction.angle()),
+            ..vec_order
+        })
+    }
+}
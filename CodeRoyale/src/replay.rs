@@ -0,0 +1,396 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::codegame::{ClientMessage, ServerMessage};
+use crate::debugging::{DebugCommand, DebugData, DebugState};
+use crate::model;
+use crate::trans::{Trans, TransError};
+use crate::{idle_debug_state, render_svg, scene, BehaviourConfig, BehaviourTree, DebugInterface, MlpEvaluator, MyStrategy, SimValidator, StrategyParams, SvgView, TimeBudgetConfig};
+
+/// One message of a recorded game, in the order it went over the wire.
+#[derive(Clone, Debug)]
+pub enum ReplayRecord {
+    Server(Box<ServerMessage>),
+    Client(ClientMessage),
+    /// The app's answer to `ClientMessage::RequestDebugState`
+    DebugState(DebugState),
+}
+
+impl Trans for ReplayRecord {
+    fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        match self {
+            Self::Server(message) => {
+                <i32 as Trans>::write_to(&0, writer)?;
+                message.write_to(writer)?;
+            }
+            Self::Client(message) => {
+                <i32 as Trans>::write_to(&1, writer)?;
+                message.write_to(writer)?;
+            }
+            Self::DebugState(state) => {
+                <i32 as Trans>::write_to(&2, writer)?;
+                state.write_to(writer)?;
+            }
+        }
+        Ok(())
+    }
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        let tag = <i32 as Trans>::read_from(reader)?;
+        match tag {
+            0 => Ok(Self::Server(Box::new(Trans::read_from(reader)?))),
+            1 => Ok(Self::Client(Trans::read_from(reader)?)),
+            2 => Ok(Self::DebugState(Trans::read_from(reader)?)),
+            _ => Err(TransError::UnexpectedTag { type_name: "ReplayRecord", tag }),
+        }
+    }
+}
+
+pub struct ReplayWriter {
+    writer: BufWriter<File>,
+}
+
+impl ReplayWriter {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn record_server(&mut self, message: &ServerMessage) -> std::io::Result<()> {
+        <i32 as Trans>::write_to(&0, &mut self.writer)?;
+        message.write_to(&mut self.writer)?;
+        // the game may be killed right after the last message, keep the file readable up to it
+        if matches!(message, ServerMessage::Finish {}) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn record_client(&mut self, message: &ClientMessage) -> std::io::Result<()> {
+        <i32 as Trans>::write_to(&1, &mut self.writer)?;
+        message.write_to(&mut self.writer)?;
+        if matches!(message, ClientMessage::OrderMessage { .. }) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn record_debug_state(&mut self, state: &DebugState) -> std::io::Result<()> {
+        <i32 as Trans>::write_to(&2, &mut self.writer)?;
+        state.write_to(&mut self.writer)
+    }
+}
+
+pub struct ReplayReader {
+    reader: BufReader<File>,
+}
+
+impl ReplayReader {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+        })
+    }
+}
+
+impl Iterator for ReplayReader {
+    type Item = std::io::Result<ReplayRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // only a record boundary is a clean end, running out inside a record is an error
+        match self.reader.fill_buf() {
+            Ok([]) => None,
+            Ok(_) => Some(ReplayRecord::read_from(&mut self.reader).map_err(std::io::Error::from)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// What `render_replay` draws for the ticks it renders.
+#[derive(Clone, Debug)]
+pub struct RenderConfig {
+    /// Unit the view follows, the whole zone is shown otherwise
+    pub unit: Option<i32>,
+    /// Half the side of the view around the followed unit
+    pub view_radius: f64,
+    pub from_tick: i32,
+    pub to_tick: i32,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            unit: None,
+            view_radius: 30.0,
+            from_tick: 0,
+            to_tick: i32::MAX,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlaybackStats {
+    pub ticks: usize,
+    pub mismatched_ticks: Vec<i32>,
+}
+
+/// Feeds recorded player views into a fresh `MyStrategy` and compares its orders with the recorded ones.
+///
+/// The strategy gets the same tick budget the recording was made with (`TimeBudgetConfig::reproducible`),
+/// games recorded with wall-clock deadlines can't be expected to match. Behaviours, params and the model
+/// have to be the ones the game was played with too.
+pub struct Playback {
+    reader: ReplayReader,
+    strategy: Option<MyStrategy>,
+    behaviours: BehaviourConfig,
+    params: StrategyParams,
+    model: Option<PathBuf>,
+    last_tick: i32,
+    pending_order: Option<(i32, model::Order)>,
+    validation_horizons: Option<Vec<usize>>,
+    validator: Option<SimValidator>,
+    /// Player view the next recorded order was sent for, kept only when validating
+    pending_view: Option<model::Game>,
+    pub stats: PlaybackStats,
+}
+
+impl Playback {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            reader: ReplayReader::open(path)?,
+            strategy: None,
+            behaviours: BehaviourConfig::default(),
+            params: StrategyParams::default(),
+            model: None,
+            last_tick: -1,
+            pending_order: None,
+            validation_horizons: None,
+            validator: None,
+            pending_view: None,
+            stats: PlaybackStats::default(),
+        })
+    }
+
+    pub fn with_behaviours(mut self, behaviours: BehaviourConfig) -> Self {
+        self.behaviours = behaviours;
+        self
+    }
+
+    pub fn with_params(mut self, params: StrategyParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_model(mut self, model: Option<PathBuf>) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Also checks `Simulator` against the recorded states, replaying the recorded orders for the given horizons.
+    pub fn with_validation(mut self, horizons: Vec<usize>) -> Self {
+        self.validation_horizons = Some(horizons);
+        self
+    }
+
+    pub fn validator(&self) -> Option<&SimValidator> {
+        self.validator.as_ref()
+    }
+
+    pub fn strategy(&mut self) -> Option<&mut MyStrategy> {
+        self.strategy.as_mut()
+    }
+
+    /// Plays the replay up to and including `until_tick` (or to the end), returns the last played tick.
+    pub fn run(&mut self, until_tick: Option<i32>) -> std::io::Result<i32> {
+        while until_tick.is_none_or(|t| self.last_tick < t) {
+            if !self.step()? {
+                break;
+            }
+        }
+        Ok(self.last_tick)
+    }
+
+    /// Processes one record, returns false once the replay is over.
+    pub fn step(&mut self) -> std::io::Result<bool> {
+        let record = match self.reader.next() {
+            Some(record) => record?,
+            None => return Ok(false),
+        };
+
+        match record {
+            ReplayRecord::Server(message) => return self.play_server(*message),
+            ReplayRecord::Client(ClientMessage::OrderMessage { order: recorded }) => {
+                if let Some((tick, order)) = self.pending_order.take() {
+                    if !orders_match(&order, &recorded) {
+                        self.stats.mismatched_ticks.push(tick);
+                    }
+                }
+                if let (Some(view), Some(validator)) = (self.pending_view.take(), self.validator.as_mut()) {
+                    validator.update(&view, &recorded);
+                }
+            }
+            ReplayRecord::Client(_) | ReplayRecord::DebugState(_) => {}
+        }
+        Ok(true)
+    }
+
+    fn play_server(&mut self, message: ServerMessage) -> std::io::Result<bool> {
+        match message {
+            ServerMessage::UpdateConstants { constants } => {
+                self.validator = self.validation_horizons.clone().map(|horizons| SimValidator::new(constants.clone(), horizons));
+                let behaviours = BehaviourTree::from_config(&self.behaviours).map_err(std::io::Error::other)?;
+                let evaluator = self.model.as_ref().map(|dir| MlpEvaluator::load(dir, &constants)).transpose()?;
+                let mut strategy = MyStrategy::with_behaviours(constants, behaviours)
+                    .with_time_budget(TimeBudgetConfig::reproducible())
+                    .with_params(self.params.clone());
+                if let Some(evaluator) = evaluator {
+                    strategy = strategy.with_evaluator(Rc::new(evaluator));
+                }
+                self.strategy = Some(strategy);
+            }
+            ServerMessage::GetOrder { player_view, debug_available } => {
+                let strategy = self.strategy.as_mut()
+                    .ok_or_else(|| std::io::Error::other("GetOrder before UpdateConstants in replay"))?;
+                let order = if debug_available {
+                    strategy.get_order(&player_view, Some(&mut DebugInterface::headless(&mut vec![], idle_debug_state(None, player_view.zone.current_center))))
+                } else {
+                    strategy.get_order(&player_view, None)
+                };
+                self.last_tick = player_view.current_tick;
+                self.pending_order = Some((player_view.current_tick, order));
+                if self.validator.is_some() {
+                    self.pending_view = Some(player_view);
+                }
+                self.stats.ticks += 1;
+            }
+            ServerMessage::Finish {} => {
+                if let Some(strategy) = self.strategy.as_mut() {
+                    strategy.finish();
+                }
+                return Ok(false);
+            }
+            ServerMessage::DebugUpdate { displayed_tick } => {
+                let states = self.read_debug_update()?;
+                // key presses change what the strategy draws and traces from here on
+                if let (Some(strategy), false) = (self.strategy.as_mut(), states.is_empty()) {
+                    strategy.debug_update(displayed_tick, &mut DebugInterface::replayed(&mut vec![], states));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads up to the end of a recorded debug update, returns the debug states the app answered with during it.
+    fn read_debug_update(&mut self) -> std::io::Result<Vec<DebugState>> {
+        let mut states = vec![];
+        for record in self.reader.by_ref() {
+            match record? {
+                ReplayRecord::DebugState(state) => states.push(state),
+                ReplayRecord::Client(ClientMessage::DebugUpdateDone {}) => break,
+                _ => {}
+            }
+        }
+        Ok(states)
+    }
+}
+
+/// What the app shows from the debug commands sent to it, with auto flush off commands wait for a flush.
+#[derive(Default)]
+struct DebugCanvas {
+    shown: Vec<DebugData>,
+    pending: Vec<DebugCommand>,
+    manual_flush: bool,
+}
+
+impl DebugCanvas {
+    fn apply(&mut self, command: DebugCommand) {
+        match command {
+            DebugCommand::SetAutoFlush { enable } => {
+                self.manual_flush = !enable;
+                if enable {
+                    self.flush();
+                }
+            }
+            DebugCommand::Flush {} => self.flush(),
+            command if self.manual_flush => self.pending.push(command),
+            command => self.perform(command),
+        }
+    }
+
+    fn flush(&mut self) {
+        for command in std::mem::take(&mut self.pending) {
+            self.perform(command);
+        }
+    }
+
+    fn perform(&mut self, command: DebugCommand) {
+        match command {
+            DebugCommand::Add { debug_data } => self.shown.push(debug_data),
+            DebugCommand::Clear {} => self.shown.clear(),
+            DebugCommand::SetAutoFlush { .. } | DebugCommand::Flush {} => {}
+        }
+    }
+}
+
+/// Draws the recorded ticks in range the way the app showed them: the player's view, what was drawn
+/// while getting the tick's order, and over it what the last debug update by the end of the tick drew.
+/// Only games recorded with the app open, or by the arena with `debug_layers`, have anything drawn.
+pub fn render_replay(path: impl AsRef<Path>, config: &RenderConfig) -> std::io::Result<Vec<(i32, String)>> {
+    let mut frames = vec![];
+    let mut constants = None;
+    let mut view: Option<model::Game> = None;
+    let mut tick = DebugCanvas::default();
+    let mut overlay = DebugCanvas::default();
+    let mut in_debug_update = false;
+
+    let mut render = |game: &model::Game, constants: &model::Constants, tick: &DebugCanvas, overlay: &DebugCanvas| {
+        if !(config.from_tick..=config.to_tick).contains(&game.current_tick) {
+            return;
+        }
+        let svg_view = config.unit
+            .and_then(|id| game.units.iter().find(|u| u.id == id))
+            .map_or_else(|| SvgView::zone(game), |unit| SvgView { center: unit.position, radius: config.view_radius });
+        frames.push((game.current_tick, render_svg(&[&scene(game, constants), &tick.shown, &overlay.shown], &svg_view)));
+    };
+
+    for record in ReplayReader::open(path)? {
+        match record? {
+            ReplayRecord::Server(message) => match *message {
+                ServerMessage::UpdateConstants { constants: c } => constants = Some(c),
+                ServerMessage::GetOrder { player_view, .. } => {
+                    let constants = constants.as_ref()
+                        .ok_or_else(|| std::io::Error::other("GetOrder before UpdateConstants in replay"))?;
+                    if let Some(game) = view.take() {
+                        render(&game, constants, &tick, &overlay);
+                    }
+                    if player_view.current_tick > config.to_tick {
+                        break;
+                    }
+                    view = Some(player_view);
+                    tick = DebugCanvas::default();
+                }
+                ServerMessage::DebugUpdate { .. } => in_debug_update = true,
+                ServerMessage::Finish {} => break,
+            },
+            ReplayRecord::Client(ClientMessage::DebugMessage { command }) if in_debug_update => overlay.apply(command),
+            ReplayRecord::Client(ClientMessage::DebugMessage { command }) => tick.apply(command),
+            ReplayRecord::Client(ClientMessage::DebugUpdateDone {}) => in_debug_update = false,
+            ReplayRecord::Client(_) | ReplayRecord::DebugState(_) => {}
+        }
+    }
+    if let (Some(game), Some(constants)) = (view, constants.as_ref()) {
+        render(&game, constants, &tick, &overlay);
+    }
+    Ok(frames)
+}
+
+fn orders_match(left: &model::Order, right: &model::Order) -> bool {
+    left.unit_orders.len() == right.unit_orders.len() && left.unit_orders.iter().all(|(id, l)| {
+        right.unit_orders.get(id).is_some_and(|r| {
+            l.target_velocity.approx_equal(r.target_velocity)
+                && l.target_direction.approx_equal(r.target_direction)
+                && format!("{:?}", l.action) == format!("{:?}", r.action)
+        })
+    })
+}
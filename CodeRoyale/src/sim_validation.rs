@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use crate::model::*;
+use crate::{ObstacleGrid, Simulator};
+
+// how close to an obstacle a unit has to get for the window to count as a collision
+const COLLISION_MARGIN: f64 = 0.1;
+
+/// What was going on with the unit during a predicted window, errors differ a lot between these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Situation {
+    Spawning,
+    ObstacleCollision,
+    Aiming,
+    Free,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ErrorStats {
+    pub samples: usize,
+    pub position_total: f64,
+    pub position_max: f64,
+    /// Radians
+    pub direction_total: f64,
+    pub direction_max: f64,
+    pub velocity_total: f64,
+    pub velocity_max: f64,
+}
+
+impl ErrorStats {
+    fn add(&mut self, position: f64, direction: f64, velocity: f64) {
+        self.samples += 1;
+        self.position_total += position;
+        self.position_max = self.position_max.max(position);
+        self.direction_total += direction;
+        self.direction_max = self.direction_max.max(direction);
+        self.velocity_total += velocity;
+        self.velocity_max = self.velocity_max.max(velocity);
+    }
+
+    pub fn position_mean(&self) -> f64 {
+        self.position_total / self.samples.max(1) as f64
+    }
+
+    pub fn direction_mean(&self) -> f64 {
+        self.direction_total / self.samples.max(1) as f64
+    }
+
+    pub fn velocity_mean(&self) -> f64 {
+        self.velocity_total / self.samples.max(1) as f64
+    }
+}
+
+/// Replays the orders actually sent through `Simulator` and compares where it says our units end up
+/// with where the server puts them, `horizon` ticks later.
+pub struct SimValidator {
+    constants: Constants,
+    obstacles: ObstacleGrid,
+    horizons: Vec<usize>,
+    /// Last states with the orders sent for them, oldest first
+    history: VecDeque<(Game, Order)>,
+    stats: BTreeMap<(usize, Situation), ErrorStats>,
+}
+
+impl SimValidator {
+    pub fn new(constants: Constants, horizons: Vec<usize>) -> Self {
+        Self {
+            obstacles: ObstacleGrid::new(&constants),
+            constants,
+            horizons,
+            history: VecDeque::new(),
+            stats: BTreeMap::new(),
+        }
+    }
+
+    /// Errors by horizon (in ticks) and situation.
+    pub fn stats(&self) -> &BTreeMap<(usize, Situation), ErrorStats> {
+        &self.stats
+    }
+
+    /// Checks the predictions ending at this state, then remembers it with the order sent for it.
+    pub fn update(&mut self, game: &Game, order: &Order) {
+        if self.history.back().is_some_and(|(last, _)| last.current_tick + 1 != game.current_tick) {
+            // a gap in the ticks, nothing before it can be replayed
+            self.history.clear();
+        }
+
+        for horizon in self.horizons.clone() {
+            if horizon == 0 || horizon > self.history.len() {
+                continue;
+            }
+            self.validate(game, horizon);
+        }
+
+        self.history.push_back((game.clone(), order.clone()));
+        let max_horizon = self.horizons.iter().copied().max().unwrap_or(0);
+        while self.history.len() > max_horizon {
+            self.history.pop_front();
+        }
+    }
+
+    fn validate(&mut self, game: &Game, horizon: usize) {
+        let window = self.history.range(self.history.len() - horizon..).collect::<Vec<_>>();
+        let start = &window[0].0;
+
+        for unit in start.units.iter().filter(|u| u.player_id == start.my_id) {
+            let observed = match game.units.iter().find(|u| u.id == unit.id) {
+                Some(observed) => observed,
+                None => continue,
+            };
+            let max_speed = self.constants.max_unit_forward_speed.max(self.constants.spawn_movement_speed);
+            if observed.position.distance_to(&unit.position) > max_speed * horizon as f64 / self.constants.ticks_per_second + self.constants.unit_radius {
+                // died and respawned somewhere else, nothing to predict
+                continue;
+            }
+            let unit_order = |order: &Order| order.unit_orders.get(&unit.id).cloned().unwrap_or(UnitOrder {
+                target_velocity: Vec2::zero(),
+                target_direction: unit.direction,
+                action: None,
+            });
+
+            let mut simulator = Simulator::new(start, &self.constants, &self.obstacles, unit.id, unit_order(&window[0].1));
+            for (_, order) in window.iter() {
+                simulator.set_unit_order(unit_order(order));
+                // allies bump into the unit, where they go is known just as well
+                simulator.set_other_orders(order.unit_orders.clone());
+                simulator.simulate_tick(&mut None);
+            }
+            let predicted = match simulator.unit() {
+                Some(predicted) => predicted,
+                None => continue,
+            };
+
+            let situation = self.situation(unit, observed, predicted.position, window.iter().map(|(_, order)| unit_order(order)));
+            let direction_error = Some(predicted.direction.angle_with(&observed.direction))
+                .filter(|angle| !angle.is_nan())
+                .unwrap_or(0.0);
+            self.stats.entry((horizon, situation)).or_default().add(
+                predicted.position.distance_to(&observed.position),
+                direction_error,
+                (predicted.velocity - observed.velocity).length(),
+            );
+        }
+    }
+
+    fn situation(&self, start: &Unit, observed: &Unit, predicted: Vec2, orders: impl Iterator<Item = UnitOrder>) -> Situation {
+        let reach = self.constants.unit_radius + COLLISION_MARGIN;
+        if start.remaining_spawn_time.is_some() || observed.remaining_spawn_time.is_some() {
+            Situation::Spawning
+        } else if [start.position, observed.position, predicted].iter().any(|p| !self.obstacles.near(*p, reach).is_empty()) {
+            Situation::ObstacleCollision
+        } else if start.aim > 0.0 || observed.aim > 0.0 || orders.into_iter().any(|o| matches!(o.action, Some(ActionOrder::Aim { .. }))) {
+            Situation::Aiming
+        } else {
+            Situation::Free
+        }
+    }
+
+    /// Error table, one line per horizon and situation, directions in degrees.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "{:>7} {:<18} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}\n",
+            "horizon", "situation", "samples", "pos avg", "pos max", "dir avg", "dir max", "vel avg", "vel max",
+        );
+        for ((horizon, situation), stats) in self.stats.iter() {
+            writeln!(
+                report,
+                "{:>7} {:<18} {:>7} {:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>9.4}",
+                horizon,
+                format!("{:?}", situation),
+                stats.samples,
+                stats.position_mean(),
+                stats.position_max,
+                stats.direction_mean().to_degrees(),
+                stats.direction_max.to_degrees(),
+                stats.velocity_mean(),
+                stats.velocity_max,
+            ).unwrap();
+        }
+        report
+    }
+}
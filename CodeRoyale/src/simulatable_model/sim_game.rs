@@ -1,26 +1,701 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use crate::model::*;
+use crate::{segment_circle_contact, ObstacleGrid, VisibilitySector};
 use super::*;
 
+#[derive(Clone)]
 pub struct SimGame {
     pub my_id: i32,
     pub current_tick: i32,
+    pub players: Vec<Player>,
     pub units: Vec<SimUnit>,
+    pub loot: Vec<Loot>,
     pub projectiles: Vec<SimProjectile>,
     pub zone: Zone,
+    pub sounds: Vec<Sound>,
+    pub next_id: i32,
+    rng: StdRng,
 }
 
 impl SimGame {
     pub fn new(game: &Game) -> Self {
+        let max_id = game.units.iter().map(|u| u.id)
+            .chain(game.loot.iter().map(|l| l.id))
+            .chain(game.projectiles.iter().map(|p| p.id))
+            .max()
+            .unwrap_or(0);
+
         Self {
             my_id: game.my_id,
             current_tick: game.current_tick,
+            players: game.players.clone(),
             units: game.units.iter().map(|u| u.into()).collect(),
+            loot: game.loot.clone(),
             projectiles: game.projectiles.iter().map(|p| p.into()).collect(),
             zone: game.zone.clone(),
+            sounds: vec![],
+            next_id: max_id + 1,
+            rng: StdRng::seed_from_u64(game.current_tick as u64),
         }
     }
 
-    pub fn update(&mut self, game: &Game) {
+    /// Fresh match with `team_count` teams spread over the initial zone.
+    pub fn new_match(constants: &Constants, obstacles: &ObstacleGrid, team_count: usize, seed: u64) -> Self {
+        let mut game = Self {
+            my_id: -1,
+            current_tick: 0,
+            players: (0..team_count as i32)
+                .map(|id| Player { id, kills: 0, damage: 0.0, place: team_count as i32, score: 0.0 })
+                .collect(),
+            units: vec![],
+            loot: vec![],
+            projectiles: vec![],
+            zone: Zone {
+                current_center: Vec2::zero(),
+                current_radius: constants.initial_zone_radius,
+                next_center: Vec2::zero(),
+                next_radius: constants.initial_zone_radius,
+            },
+            sounds: vec![],
+            next_id: 0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+
+        game.pick_next_zone(constants, obstacles);
+
+        for player_id in 0..team_count as i32 {
+            let team_position = game.random_point_in_zone(constants, obstacles);
+            for _ in 0..constants.team_size {
+                let id = game.gen_id();
+                let direction = Vec2::from_length_and_angle(1.0, game.rng.gen_range(0.0..2.0 * PI));
+                let offset = Vec2::from_length_and_angle(game.rng.gen_range(0.0..=3.0 * constants.unit_radius), direction.arg());
+                let mut ammo = vec![0; constants.weapons.len()];
+                if let Some(weapon_idx) = constants.starting_weapon {
+                    ammo[weapon_idx as usize] = constants.starting_weapon_ammo;
+                }
+                let unit = Unit {
+                    id,
+                    player_id,
+                    health: constants.unit_health,
+                    shield: constants.spawn_shield,
+                    extra_lives: constants.extra_lives,
+                    position: team_position + offset,
+                    remaining_spawn_time: Some(constants.spawn_time).filter(|t| *t > 0.0),
+                    velocity: Vec2::zero(),
+                    direction,
+                    aim: 0.0,
+                    action: None,
+                    health_regeneration_start_tick: 0,
+                    weapon: constants.starting_weapon,
+                    next_shot_tick: 0,
+                    ammo,
+                    shield_potions: 0,
+                    seen_on_tick: -1,
+                };
+                game.units.push((&unit).into());
+            }
+        }
+
+        game
+    }
+
+    pub fn alive_players(&self) -> Vec<i32> {
+        self.players
+            .iter()
+            .filter(|p| self.units.iter().any(|u| u.player_id == p.id))
+            .map(|p| p.id)
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.alive_players().len() <= 1
+    }
+
+    /// What the given player sees this tick, in the same shape the server sends it.
+    pub fn player_view(&self, player_id: i32, constants: &Constants) -> Game {
+        let observers = self.units.iter().filter(|u| u.player_id == player_id).collect::<Vec<_>>();
+        let sectors = observers.iter()
+            .map(|u| VisibilitySector::new(u.position, u.direction, u.aim, u.weapon, constants))
+            .collect::<Vec<_>>();
+        let is_visible = |point: Vec2| sectors.iter().any(|sec| sec.cover_point(point));
+
+        Game {
+            my_id: player_id,
+            players: self.players.clone(),
+            current_tick: self.current_tick,
+            units: self.units.iter()
+                .filter(|u| u.player_id == player_id || is_visible(u.position))
+                .map(|u| u.into())
+                .collect(),
+            loot: self.loot.iter().filter(|l| is_visible(l.position)).cloned().collect(),
+            projectiles: self.projectiles.iter().filter(|p| is_visible(p.position)).map(|p| p.into()).collect(),
+            zone: self.zone.clone(),
+            sounds: self.sounds.iter()
+                .filter(|s| observers.iter().any(|u| u.id == s.unit_id))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Advances the game by one tick, applying orders of every player (keyed by player id).
+    pub fn tick(&mut self, constants: &Constants, obstacles: &ObstacleGrid, orders: &HashMap<i32, Order>) {
+        self.sounds.clear();
+
+        for unit in self.units.iter_mut() {
+            unit.last_position = unit.position;
+            unit.last_velocity = unit.velocity;
+            unit.last_aim = unit.aim;
+        }
+
+        let unit_orders = self.collect_unit_orders(orders);
+
+        self.finish_actions(constants);
+        self.apply_actions(constants, &unit_orders);
+        self.apply_rotation(constants, &unit_orders);
+        self.apply_movement(constants, obstacles, &unit_orders);
+        self.update_spawning(constants, obstacles);
+        self.move_projectiles(constants, obstacles);
+        self.regenerate_health(constants);
+        self.update_zone(constants, obstacles);
+        self.apply_zone_damage(constants);
+        self.handle_deaths(constants, obstacles);
+
+        self.current_tick += 1;
+    }
+
+    fn collect_unit_orders(&self, orders: &HashMap<i32, Order>) -> HashMap<i32, UnitOrder> {
+        let mut unit_orders = HashMap::new();
+        for unit in self.units.iter() {
+            let order = orders
+                .get(&unit.player_id)
+                .and_then(|o| o.unit_orders.get(&unit.id))
+                .cloned()
+                .unwrap_or(UnitOrder {
+                    target_velocity: Vec2::zero(),
+                    target_direction: unit.direction,
+                    action: None,
+                });
+            unit_orders.insert(unit.id, order);
+        }
+        unit_orders
+    }
+
+    pub(crate) fn finish_actions(&mut self, constants: &Constants) {
+        for idx in 0..self.units.len() {
+            let finished = matches!(&self.units[idx].action, Some(action) if action.finish_tick <= self.current_tick);
+            if !finished {
+                continue;
+            }
+            self.units[idx].action = None;
+            if let Some(action_order) = self.units[idx].action_order.take() {
+                self.complete_action(idx, action_order, constants);
+            }
+        }
+    }
+
+    fn complete_action(&mut self, idx: usize, action_order: ActionOrder, constants: &Constants) {
+        let position = self.units[idx].position;
+        match action_order {
+            ActionOrder::Pickup { loot: loot_id } => self.pick_up(idx, loot_id, constants),
+            ActionOrder::UseShieldPotion {} => {
+                let unit = &mut self.units[idx];
+                if unit.shield_potions > 0 {
+                    unit.shield_potions -= 1;
+                    unit.shield = (unit.shield + constants.shield_per_potion).min(constants.max_shield);
+                }
+            }
+            ActionOrder::DropShieldPotions { amount } => {
+                let amount = amount.min(self.units[idx].shield_potions);
+                if amount > 0 {
+                    self.units[idx].shield_potions -= amount;
+                    self.spawn_loot(position, Item::ShieldPotions { amount });
+                }
+            }
+            ActionOrder::DropWeapon {} => {
+                if let Some(type_index) = self.units[idx].weapon.take() {
+                    self.units[idx].aim = 0.0;
+                    self.spawn_loot(position, Item::Weapon { type_index });
+                }
+            }
+            ActionOrder::DropAmmo { weapon_type_index, amount } => {
+                let ammo = match usize::try_from(weapon_type_index).ok().and_then(|i| self.units[idx].ammo.get_mut(i)) {
+                    Some(ammo) => ammo,
+                    None => return,
+                };
+                let amount = amount.min(*ammo);
+                if amount > 0 {
+                    *ammo -= amount;
+                    self.spawn_loot(position, Item::Ammo { weapon_type_index, amount });
+                }
+            }
+            ActionOrder::Aim { .. } => {}
+        }
+    }
+
+    fn pick_up(&mut self, idx: usize, loot_id: i32, constants: &Constants) {
+        let loot_idx = match self.loot.iter().position(|l| l.id == loot_id) {
+            Some(loot_idx) => loot_idx,
+            None => return,
+        };
+        if self.loot[loot_idx].position.distance_to(&self.units[idx].position) > constants.unit_radius {
+            return;
+        }
+
+        let position = self.units[idx].position;
+        let unit = &mut self.units[idx];
+        let left = match self.loot[loot_idx].item {
+            Item::Weapon { type_index } => {
+                let previous = unit.weapon.replace(type_index);
+                unit.aim = 0.0;
+                previous.map(|type_index| Item::Weapon { type_index })
+            }
+            Item::ShieldPotions { amount } => {
+                let taken = amount.min(constants.max_shield_potions_in_inventory - unit.shield_potions).max(0);
+                unit.shield_potions += taken;
+                Some(Item::ShieldPotions { amount: amount - taken }).filter(|_| taken < amount)
+            }
+            Item::Ammo { weapon_type_index, amount } => {
+                let max_ammo = constants.weapons[weapon_type_index as usize].max_inventory_ammo;
+                let taken = amount.min(max_ammo - unit.ammo[weapon_type_index as usize]).max(0);
+                unit.ammo[weapon_type_index as usize] += taken;
+                Some(Item::Ammo { weapon_type_index, amount: amount - taken }).filter(|_| taken < amount)
+            }
+        };
 
+        match left {
+            Some(Item::Weapon { type_index }) => {
+                self.loot.remove(loot_idx);
+                self.spawn_loot(position, Item::Weapon { type_index });
+            }
+            Some(item) => self.loot[loot_idx].item = item,
+            None => {
+                self.loot.remove(loot_idx);
+            }
+        }
+    }
+
+    pub fn spawn_loot(&mut self, position: Vec2, item: Item) {
+        let id = self.gen_id();
+        self.loot.push(Loot {
+            id,
+            position,
+            item,
+            seen_on_tick: self.current_tick,
+        });
+    }
+
+    fn apply_actions(&mut self, constants: &Constants, unit_orders: &HashMap<i32, UnitOrder>) {
+        let aim_ticks = |weapon: &WeaponProperties| (weapon.aim_time * constants.ticks_per_second).max(1.0);
+
+        for idx in 0..self.units.len() {
+            let unit = &self.units[idx];
+            let action = if unit.is_spawned() && unit.action.is_none() {
+                unit_orders.get(&unit.id).and_then(|o| o.action.clone())
+            } else {
+                None
+            };
+
+            let aim_order = match action {
+                Some(ActionOrder::Aim { shoot }) => Some(shoot),
+                _ => None,
+            };
+
+            let unit = &mut self.units[idx];
+            match unit.weapon.map(|w| &constants.weapons[w as usize]) {
+                Some(weapon) if aim_order.is_some() => unit.aim = (unit.aim + 1.0 / aim_ticks(weapon)).min(1.0),
+                Some(weapon) => unit.aim = (unit.aim - 1.0 / aim_ticks(weapon)).max(0.0),
+                None => unit.aim = 0.0,
+            }
+
+            match action {
+                Some(ActionOrder::Aim { shoot: true }) => self.try_shoot(idx, constants),
+                Some(ActionOrder::Aim { shoot: false }) | None => {}
+                Some(action_order) => self.start_action(idx, action_order, constants),
+            }
+        }
+    }
+
+    fn start_action(&mut self, idx: usize, action_order: ActionOrder, constants: &Constants) {
+        let unit = &mut self.units[idx];
+        let (action_type, duration) = match action_order {
+            ActionOrder::UseShieldPotion {} => {
+                if unit.shield_potions <= 0 || unit.shield >= constants.max_shield {
+                    return;
+                }
+                (ActionType::UseShieldPotion, constants.shield_potion_use_time)
+            }
+            _ => (ActionType::Looting, constants.looting_time),
+        };
+
+        unit.action = Some(Action {
+            finish_tick: self.current_tick + (duration * constants.ticks_per_second).ceil() as i32,
+            action_type,
+        });
+        unit.action_order = Some(action_order);
+    }
+
+    fn try_shoot(&mut self, idx: usize, constants: &Constants) {
+        let unit = &self.units[idx];
+        let weapon_idx = match unit.weapon {
+            Some(w) => w,
+            None => return,
+        };
+        let weapon = &constants.weapons[weapon_idx as usize];
+        if unit.aim < 1.0 || unit.next_shot_tick > self.current_tick || unit.ammo[weapon_idx as usize] <= 0 {
+            return;
+        }
+
+        let half_spread = weapon.spread.to_radians() / 2.0;
+        let deviation = self.rng.gen_range(-half_spread..=half_spread);
+        let id = self.gen_id();
+        let unit = &mut self.units[idx];
+        let projectile = SimProjectile {
+            last_position: unit.position,
+            last_life_time: weapon.projectile_life_time,
+            id,
+            weapon_type_index: weapon_idx,
+            shooter_id: unit.id,
+            shooter_player_id: unit.player_id,
+            position: unit.position,
+            velocity: Vec2::from_length_and_angle(weapon.projectile_speed, unit.direction.arg() + deviation),
+            life_time: weapon.projectile_life_time,
+        };
+
+        unit.ammo[weapon_idx as usize] -= 1;
+        unit.next_shot_tick = self.current_tick + (constants.ticks_per_second / weapon.rounds_per_second).ceil() as i32;
+        let position = unit.position;
+        self.projectiles.push(projectile);
+
+        if let Some(sound_idx) = weapon.shot_sound_type_index {
+            self.emit_sound(sound_idx, position, constants);
+        }
+    }
+
+    fn apply_rotation(&mut self, constants: &Constants, unit_orders: &HashMap<i32, UnitOrder>) {
+        for unit in self.units.iter_mut() {
+            if let Some(order) = unit_orders.get(&unit.id) {
+                unit.direction = unit.next_direction(order.target_direction, constants);
+            }
+        }
+    }
+
+    fn apply_movement(&mut self, constants: &Constants, obstacles: &ObstacleGrid, unit_orders: &HashMap<i32, UnitOrder>) {
+        for idx in 0..self.units.len() {
+            let unit = &self.units[idx];
+            let target_velocity = unit_orders.get(&unit.id).map(|o| o.target_velocity).unwrap_or_else(Vec2::zero);
+            let (velocity, position) = unit.next_position_among(target_velocity, constants, obstacles.near(unit.position, unit.reach(constants)));
+            self.units[idx].velocity = velocity;
+            self.units[idx].position = position;
+        }
+
+        self.resolve_unit_collisions(constants);
+
+        if let Some(steps_sound_idx) = constants.steps_sound_type_index {
+            for idx in 0..self.units.len() {
+                let unit = &self.units[idx];
+                let moved_by = unit.position.distance_to(&unit.last_position);
+                let probability = (moved_by / constants.steps_sound_travel_distance).min(1.0);
+                if unit.is_spawned() && moved_by > 0.0 && self.rng.gen_bool(probability) {
+                    let position = unit.position;
+                    self.emit_sound(steps_sound_idx, position, constants);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn resolve_unit_collisions(&mut self, constants: &Constants) {
+        let min_distance = 2.0 * constants.unit_radius;
+        for i in 0..self.units.len() {
+            for j in (i + 1)..self.units.len() {
+                if !self.units[i].is_spawned() || !self.units[j].is_spawned() {
+                    continue;
+                }
+                let delta = self.units[j].position - self.units[i].position;
+                let distance = delta.length();
+                if distance >= min_distance || distance == 0.0 {
+                    continue;
+                }
+                let push = delta.normalize() * ((min_distance - distance) / 2.0);
+                self.units[i].position -= push;
+                self.units[j].position += push;
+            }
+        }
     }
-}
\ No newline at end of file
+
+    fn update_spawning(&mut self, constants: &Constants, obstacles: &ObstacleGrid) {
+        let delta_time = 1.0 / constants.ticks_per_second;
+        let spawn_damage = constants.spawn_collision_damage_per_second * delta_time;
+
+        for idx in 0..self.units.len() {
+            let remaining = match self.units[idx].remaining_spawn_time {
+                Some(remaining) => (remaining - delta_time).max(0.0),
+                None => continue,
+            };
+            if remaining > 0.0 {
+                self.units[idx].remaining_spawn_time = Some(remaining);
+                continue;
+            }
+
+            let position = self.units[idx].position;
+            let collides_with_obstacle = obstacles.near(position, constants.unit_radius).iter()
+                .any(|o| o.position.distance_to(&position) < o.radius + constants.unit_radius);
+            let collides_with_unit = self.units.iter()
+                .any(|u| u.id != self.units[idx].id && u.is_spawned() && u.position.distance_to(&position) < 2.0 * constants.unit_radius);
+
+            if collides_with_obstacle || collides_with_unit {
+                self.units[idx].remaining_spawn_time = Some(0.0);
+                let current_tick = self.current_tick;
+                self.units[idx].receive_damage(spawn_damage, current_tick, constants);
+            } else {
+                self.units[idx].remaining_spawn_time = None;
+            }
+        }
+    }
+
+    fn move_projectiles(&mut self, constants: &Constants, obstacles: &ObstacleGrid) {
+        let delta_time = 1.0 / constants.ticks_per_second;
+        let mut projectiles = std::mem::take(&mut self.projectiles);
+
+        for projectile in projectiles.iter_mut() {
+            projectile.last_position = projectile.position;
+            projectile.last_life_time = projectile.life_time;
+
+            let travel_time = delta_time.min(projectile.life_time);
+            let start = projectile.position;
+            let end = start + projectile.velocity * travel_time;
+
+            let obstacle_hit = obstacles.along_segment(start, end, 0.0).into_iter()
+                .filter(|o| !o.can_shoot_through)
+                .filter_map(|o| segment_circle_contact(start, end, o.position, o.radius))
+                .min_by(|a, b| a.total_cmp(b));
+
+            let unit_hit = self.units.iter().enumerate()
+                .filter(|(_, u)| u.is_spawned() && u.id != projectile.shooter_id)
+                .filter(|(_, u)| constants.friendly_fire || u.player_id != projectile.shooter_player_id)
+                .filter_map(|(idx, u)| segment_circle_contact(start, end, u.position, constants.unit_radius).map(|t| (idx, t)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            let weapon = &constants.weapons[projectile.weapon_type_index as usize];
+            match (unit_hit, obstacle_hit) {
+                (Some((idx, t)), obstacle_t) if obstacle_t.is_none_or(|o| t <= o) => {
+                    projectile.position = start + (end - start) * t;
+                    projectile.life_time = 0.0;
+                    self.deal_damage(idx, weapon.projectile_damage, Some(projectile.shooter_player_id), constants);
+                }
+                (_, Some(t)) => {
+                    projectile.position = start + (end - start) * t;
+                    projectile.life_time = 0.0;
+                }
+                _ => {
+                    projectile.position = end;
+                    projectile.life_time -= travel_time;
+                    continue;
+                }
+            }
+
+            if let Some(sound_idx) = weapon.projectile_hit_sound_type_index {
+                self.emit_sound(sound_idx, projectile.position, constants);
+            }
+        }
+
+        projectiles.retain(|p| p.life_time > 0.0);
+        self.projectiles = projectiles;
+    }
+
+    fn deal_damage(&mut self, idx: usize, damage: f64, attacker_player_id: Option<i32>, constants: &Constants) {
+        let current_tick = self.current_tick;
+        let victim = &mut self.units[idx];
+        if victim.health <= 0.0 {
+            return;
+        }
+        let dealt = victim.receive_damage(damage, current_tick, constants);
+        let killed = victim.health <= 0.0;
+        let victim_player_id = victim.player_id;
+
+        let attacker = attacker_player_id
+            .filter(|id| *id != victim_player_id)
+            .and_then(|id| self.players.iter_mut().find(|p| p.id == id));
+        if let Some(player) = attacker {
+            player.damage += dealt;
+            player.score += dealt * constants.damage_score_multiplier;
+            if killed {
+                player.kills += 1;
+                player.score += constants.kill_score;
+            }
+        }
+    }
+
+    fn regenerate_health(&mut self, constants: &Constants) {
+        let regen = constants.health_regeneration_per_second / constants.ticks_per_second;
+        for unit in self.units.iter_mut() {
+            if unit.is_spawned() && unit.health > 0.0 && unit.health_regeneration_start_tick <= self.current_tick {
+                unit.health = (unit.health + regen).min(constants.unit_health);
+            }
+        }
+    }
+
+    fn update_zone(&mut self, constants: &Constants, obstacles: &ObstacleGrid) {
+        let step = constants.zone_speed / constants.ticks_per_second;
+        let zone = &mut self.zone;
+
+        let remaining = zone.current_radius - zone.next_radius;
+        if remaining > step {
+            let fraction = step / remaining;
+            zone.current_center += (zone.next_center - zone.current_center) * fraction;
+            zone.current_radius -= step;
+            return;
+        }
+
+        zone.current_center = zone.next_center;
+        zone.current_radius = zone.next_radius;
+        self.pick_next_zone(constants, obstacles);
+    }
+
+    fn pick_next_zone(&mut self, constants: &Constants, obstacles: &ObstacleGrid) {
+        let next_radius = self.zone.current_radius / 2.0;
+        if next_radius < constants.unit_radius {
+            // final circle, it stays as it is
+            return;
+        }
+
+        let max_offset = self.zone.current_radius - next_radius;
+        let mut next_center = self.zone.current_center;
+        for _ in 0..100 {
+            let offset = Vec2::from_length_and_angle(self.rng.gen_range(0.0..=max_offset), self.rng.gen_range(0.0..2.0 * PI));
+            next_center = self.zone.current_center + offset;
+            let clear = obstacles.near(next_center, next_radius + constants.unit_radius).iter()
+                .all(|o| o.position.distance_to(&next_center) >= o.radius + next_radius + constants.unit_radius);
+            if clear {
+                break;
+            }
+        }
+        self.zone.next_center = next_center;
+        self.zone.next_radius = next_radius;
+    }
+
+    fn apply_zone_damage(&mut self, constants: &Constants) {
+        let zone_damage = constants.zone_damage_per_second / constants.ticks_per_second;
+        for idx in 0..self.units.len() {
+            let unit = &self.units[idx];
+            if unit.is_spawned() && unit.position.distance_to(&self.zone.current_center) > self.zone.current_radius {
+                self.deal_damage(idx, zone_damage, None, constants);
+            }
+        }
+    }
+
+    fn handle_deaths(&mut self, constants: &Constants, obstacles: &ObstacleGrid) {
+        let alive_before = self.alive_players();
+
+        let dead = self.units.iter().enumerate().filter(|(_, u)| u.health <= 0.0).map(|(idx, _)| idx).collect::<Vec<_>>();
+        for idx in dead.into_iter().rev() {
+            self.drop_inventory(idx);
+            let can_respawn = self.units[idx].extra_lives > 0 && self.zone.current_radius >= constants.last_respawn_zone_radius;
+            if can_respawn {
+                self.respawn(idx, constants, obstacles);
+            } else {
+                self.units.remove(idx);
+            }
+        }
+
+        let alive_after = self.alive_players();
+        let eliminated = alive_before.iter().filter(|id| !alive_after.contains(id)).count();
+        if eliminated == 0 {
+            return;
+        }
+
+        for player in self.players.iter_mut() {
+            if alive_before.contains(&player.id) {
+                player.place = alive_before.len() as i32;
+            }
+            if alive_after.contains(&player.id) {
+                player.place = alive_after.len() as i32;
+                player.score += eliminated as f64 * constants.score_per_place;
+            }
+        }
+    }
+
+    fn drop_inventory(&mut self, idx: usize) {
+        let unit = &mut self.units[idx];
+        let position = unit.position;
+        let mut items = vec![];
+        if let Some(type_index) = unit.weapon.take() {
+            items.push(Item::Weapon { type_index });
+        }
+        for (weapon_type_index, amount) in unit.ammo.iter_mut().enumerate() {
+            if *amount > 0 {
+                items.push(Item::Ammo { weapon_type_index: weapon_type_index as i32, amount: *amount });
+                *amount = 0;
+            }
+        }
+        if unit.shield_potions > 0 {
+            items.push(Item::ShieldPotions { amount: unit.shield_potions });
+            unit.shield_potions = 0;
+        }
+        for item in items {
+            self.spawn_loot(position, item);
+        }
+    }
+
+    fn respawn(&mut self, idx: usize, constants: &Constants, obstacles: &ObstacleGrid) {
+        let position = self.random_point_in_zone(constants, obstacles);
+        let unit = &mut self.units[idx];
+
+        unit.extra_lives -= 1;
+        unit.health = constants.unit_health;
+        unit.shield = constants.spawn_shield;
+        unit.position = position;
+        unit.last_position = position;
+        unit.velocity = Vec2::zero();
+        unit.remaining_spawn_time = Some(constants.spawn_time);
+        unit.aim = 0.0;
+        unit.action = None;
+        unit.action_order = None;
+        unit.health_regeneration_start_tick = self.current_tick;
+        unit.weapon = constants.starting_weapon;
+        unit.next_shot_tick = self.current_tick;
+        unit.ammo = vec![0; constants.weapons.len()];
+        if let Some(weapon_idx) = constants.starting_weapon {
+            unit.ammo[weapon_idx as usize] = constants.starting_weapon_ammo;
+        }
+        unit.shield_potions = 0;
+    }
+
+    pub fn random_point_in_zone(&mut self, constants: &Constants, obstacles: &ObstacleGrid) -> Vec2 {
+        let center = self.zone.current_center;
+        let radius = (self.zone.current_radius - constants.unit_radius).max(0.0);
+        for _ in 0..100 {
+            let p = center + Vec2::from_length_and_angle(radius * self.rng.gen::<f64>().sqrt(), self.rng.gen_range(0.0..2.0 * PI));
+            if !obstacles.near(p, constants.unit_radius).iter().any(|o| o.position.distance_to(&p) < o.radius + constants.unit_radius) {
+                return p;
+            }
+        }
+        center
+    }
+
+    fn emit_sound(&mut self, type_index: i32, source: Vec2, constants: &Constants) {
+        let properties = &constants.sounds[type_index as usize];
+        for unit in self.units.iter() {
+            let distance = unit.position.distance_to(&source);
+            if distance == 0.0 || distance > properties.distance {
+                continue;
+            }
+            let offset = Vec2::from_length_and_angle(
+                self.rng.gen_range(0.0..=distance * properties.offset),
+                self.rng.gen_range(0.0..2.0 * PI),
+            );
+            self.sounds.push(Sound {
+                type_index,
+                unit_id: unit.id,
+                position: source + offset,
+            });
+        }
+    }
+
+    fn gen_id(&mut self) -> i32 {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+}
@@ -30,4 +30,18 @@ impl From<&Projectile> for SimProjectile {
             life_time: projectile.life_time,
         }
     }
-}
\ No newline at end of file
+}
+impl From<&SimProjectile> for Projectile {
+    fn from(projectile: &SimProjectile) -> Self {
+        Self {
+            id: projectile.id,
+            weapon_type_index: projectile.weapon_type_index,
+            shooter_id: projectile.shooter_id,
+            shooter_player_id: projectile.shooter_player_id,
+            position: projectile.position,
+            velocity: projectile.velocity,
+            life_time: projectile.life_time,
+            seen_on_tick: -1,
+        }
+    }
+}
@@ -1,11 +1,14 @@
+use std::f64::consts::PI;
 use crate::model::*;
+use crate::f64_approx_eq;
 
 #[derive(Clone)]
 pub struct SimUnit {
     pub last_position: Vec2,
     pub last_velocity: Vec2,
     pub last_aim: f64,
-    
+    pub action_order: Option<ActionOrder>,
+
     // unit fields
     pub id: i32,
     pub player_id: i32,
@@ -25,12 +28,141 @@ pub struct SimUnit {
     pub shield_potions: i32,
 }
 
+impl SimUnit {
+    pub fn is_spawned(&self) -> bool {
+        self.remaining_spawn_time.is_none()
+    }
+
+    pub fn next_direction(&self, target_direction: Vec2, constants: &Constants) -> Vec2 {
+        if target_direction.length() < constants.unit_radius / 2.0 {
+            return self.direction;
+        }
+
+        let delta_time = 1.0 / constants.ticks_per_second;
+        let a1 = target_direction.arg();
+        let a2 = self.direction.arg();
+        let delta_angle = if (a1 - a2).abs() < PI { a1 - a2 } else { a2 - a1 };
+        let rotation_speed = constants.rotation_speed.to_radians();
+        let aim_rotation_speed = if let Some(weapon_idx) = self.weapon {
+            constants.weapons[weapon_idx as usize].aim_rotation_speed.to_radians()
+        } else {
+            rotation_speed
+        };
+        let rotation_cap = (rotation_speed - (rotation_speed - aim_rotation_speed) * self.aim) * delta_time;
+        let turn_this_tick = delta_angle.clamp(-rotation_cap, rotation_cap);
+
+        self.direction.rotate(turn_this_tick)
+    }
+
+    /// Farthest the unit can get this tick, obstacles beyond that can't be touched.
+    pub fn reach(&self, constants: &Constants) -> f64 {
+        let delta_time = 1.0 / constants.ticks_per_second;
+        constants.unit_radius + (self.velocity.length() + constants.unit_acceleration * delta_time) * delta_time
+    }
+
+    /// Velocity and position after a tick, colliding only with the given obstacles, the ones within `reach` are enough.
+    pub fn next_position_among<'o>(&self, mut target_velocity: Vec2, constants: &Constants, obstacles: impl IntoIterator<Item = &'o Obstacle>) -> (Vec2, Vec2) {
+        let delta_time = 1.0 / constants.ticks_per_second;
+
+        let target_speed = self.max_speed(target_velocity, constants);
+        target_velocity = target_velocity.clamp(target_speed);
+
+        let delta_velocity = (target_velocity - self.velocity).clamp(constants.unit_acceleration * delta_time);
+
+        let velocity = self.velocity + delta_velocity;
+
+        let mut position = self.position + velocity * delta_time;
+        if !self.is_spawned() {
+            // spawning units are not physical yet, they pass through obstacles
+            return (velocity, position);
+        }
+
+        let collision = obstacles.into_iter().find(|o| {
+            o.position.distance_to(&position) <= o.radius + constants.unit_radius
+        });
+
+        // * считаем точку столкновения с кругом, пусть это pt
+        // * считаем вектор obstacle.center - pt - это нормаль
+        // * поворачиваем вектор на 90 градусов влево (или вправо, в зависимости от того с какой стороны столкнулись с препятствием), это вектор касательной, назовем его m
+        // * считаем k = dot(v, m) / dot(m, m); v_new = m * k; (где v - изначальная скорость юнита до столкновения), это проекция вектора скорости на касательную = новый вектор скорости, по которому будет двигаться юнит после столкновения
+        //
+        // PS. dot - скалярное произведение векторов
+
+        if let Some(obs) = collision {
+            let pushback_length = obs.radius + constants.unit_radius - obs.position.distance_to(&position);
+            let normal = obs.position - position;
+
+            let wanted_to_move_by = velocity.length() * delta_time;
+            let pushback_vec = Vec2::from_length_and_angle(pushback_length, normal.angle());
+            position -= pushback_vec;
+            let moved_by_so_far = position.distance_to(&self.position);
+            let movement_left = wanted_to_move_by - moved_by_so_far;
+
+            let angle = normal.angle_with(&target_velocity);
+            // no slide along the obstacle when not trying to move, the angle isn't even defined then
+            if !angle.is_nan() {
+                let velocity_correction = Vec2::from_length_and_angle(velocity.length() * angle.cos(), normal.angle());
+
+                let tangential_velocity = Vec2::from_length_and_angle(movement_left * angle.sin(), (velocity - velocity_correction).angle());
+                position += tangential_velocity;
+            }
+        }
+        (velocity, position)
+    }
+
+    pub fn max_speed(&self, target_velocity: Vec2, constants: &Constants) -> f64 {
+        if self.remaining_spawn_time.is_some() {
+            constants.spawn_movement_speed
+        } else {
+            let aim_movement_speed_modifier = if let Some(weapon_idx) = self.weapon {
+                constants.weapons[weapon_idx as usize].aim_movement_speed_modifier
+            } else {
+                1.0
+            };
+            let aim = self.aim;
+
+            let max_unit_forward_speed = constants.max_unit_forward_speed * (1.0 - (1.0 - aim_movement_speed_modifier) * aim);
+            let max_unit_backward_speed = constants.max_unit_backward_speed * (1.0 - (1.0 - aim_movement_speed_modifier) * aim);
+
+            let d = (max_unit_forward_speed - max_unit_backward_speed) / 2.0;
+            let r = (max_unit_forward_speed + max_unit_backward_speed) / 2.0;
+
+            let orig_v = target_velocity;
+            let offset = self.direction;
+
+            let angle_a = (offset.arg() - orig_v.arg()).abs();
+            if f64_approx_eq(angle_a, 0.0) {
+                return max_unit_forward_speed;
+            } else if f64_approx_eq(angle_a, PI) {
+                return max_unit_backward_speed;
+            }
+
+            let sin_b = d * angle_a.sin() / r;
+            let angle_b = sin_b.asin();
+
+            let angle_c = PI - angle_a - angle_b;
+
+            r * angle_c.sin() / angle_a.sin()
+        }
+    }
+
+    pub fn receive_damage(&mut self, damage: f64, current_tick: i32, constants: &Constants) -> f64 {
+        let shield_damage = damage.min(self.shield);
+        let health_damage = (damage - shield_damage).min(self.health);
+        self.shield -= shield_damage;
+        self.health -= damage - shield_damage;
+        self.health_regeneration_start_tick = current_tick + (constants.health_regeneration_delay * constants.ticks_per_second).ceil() as i32;
+        shield_damage + health_damage
+    }
+}
+
 impl From<&Unit> for SimUnit {
     fn from(unit: &Unit) -> Self {
         Self {
             last_position: unit.position,
             last_velocity: unit.velocity,
             last_aim: unit.aim,
+            action_order: None,
 
             id: unit.id,
             player_id: unit.player_id,
@@ -44,10 +176,34 @@ impl From<&Unit> for SimUnit {
             aim: unit.aim,
             action: unit.action.clone(),
             health_regeneration_start_tick: unit.health_regeneration_start_tick,
-            weapon: unit.weapon.clone(),
+            weapon: unit.weapon,
             next_shot_tick: unit.next_shot_tick,
             ammo: unit.ammo.clone(),
             shield_potions: unit.shield_potions,
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<&SimUnit> for Unit {
+    fn from(unit: &SimUnit) -> Self {
+        Self {
+            id: unit.id,
+            player_id: unit.player_id,
+            health: unit.health,
+            shield: unit.shield,
+            extra_lives: unit.extra_lives,
+            position: unit.position,
+            remaining_spawn_time: unit.remaining_spawn_time,
+            velocity: unit.velocity,
+            direction: unit.direction,
+            aim: unit.aim,
+            action: unit.action.clone(),
+            health_regeneration_start_tick: unit.health_regeneration_start_tick,
+            weapon: unit.weapon,
+            next_shot_tick: unit.next_shot_tick,
+            ammo: unit.ammo.clone(),
+            shield_potions: unit.shield_potions,
+            seen_on_tick: -1,
+        }
+    }
+}
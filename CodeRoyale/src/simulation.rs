@@ -1,58 +1,98 @@
-use std::cmp::{max, Ordering};
-use std::collections::HashSet;
-use std::f64::consts::{FRAC_PI_2, PI};
+use std::collections::{HashMap, HashSet};
 use itertools::Itertools;
-use ai_cup_22::debugging::Color;
-use ai_cup_22::model::*;
-use crate::{calculate_circle_line, DebugInterface, MyStrategy};
+use crate::debugging::Color;
+use crate::model::*;
+use crate::{DebugInterface, ObstacleGrid};
 use crate::simulatable_model::*;
 
-#[derive(Default, Clone, PartialEq)]
+#[derive(Default, Clone, Debug)]
 pub struct SimulationResult {
     pub damage_received: f64,
     pub avg_distance_to_enemies: f64,
+    pub ticks_outside_zone: usize,
+    /// Sum over simulated steps of enemies having a clear shot at the unit
+    pub exposed_to_enemies: usize,
 }
 
-impl Eq for SimulationResult {}
+/// How much each part of a `SimulationResult` costs, lower total cost is better.
+#[derive(Clone, Debug)]
+pub struct SimulationWeights {
+    pub damage_received: f64,
+    pub avg_distance_to_enemies: f64,
+    pub ticks_outside_zone: f64,
+    pub exposed_to_enemies: f64,
+}
 
-impl PartialOrd<Self> for SimulationResult {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (self.damage_received, -self.avg_distance_to_enemies).partial_cmp(&(other.damage_received, other.avg_distance_to_enemies))
+impl Default for SimulationWeights {
+    fn default() -> Self {
+        Self {
+            damage_received: 1.0,
+            avg_distance_to_enemies: -0.05,
+            ticks_outside_zone: 0.5,
+            exposed_to_enemies: 2.0,
+        }
     }
 }
 
-impl Ord for SimulationResult {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.damage_received.total_cmp(&other.damage_received) {
-            Ordering::Equal => other.avg_distance_to_enemies.total_cmp(&self.avg_distance_to_enemies),
-            other => other,
-        }
+impl SimulationResult {
+    pub fn cost(&self, weights: &SimulationWeights) -> f64 {
+        self.damage_received * weights.damage_received
+            + self.avg_distance_to_enemies * weights.avg_distance_to_enemies
+            + self.ticks_outside_zone as f64 * weights.ticks_outside_zone
+            + self.exposed_to_enemies as f64 * weights.exposed_to_enemies
     }
 }
 
-pub struct Simulator {
+#[derive(Clone)]
+pub struct Simulator<'a> {
     game: SimGame,
     unit_id: i32,
     unit_order: UnitOrder,
-    constants: Constants,
+    /// Orders of other units that are known, our allies' for one; the rest keep moving as they do
+    other_orders: HashMap<i32, UnitOrder>,
+    constants: &'a Constants,
+    obstacles: &'a ObstacleGrid,
     result: SimulationResult,
     units_received_damage: HashSet<i32>,
 }
 
-impl Simulator {
-    pub fn new(game: &Game, constants: &Constants, unit_id: i32, unit_order: UnitOrder) -> Self {
+impl<'a> Simulator<'a> {
+    pub fn new(game: &Game, constants: &'a Constants, obstacles: &'a ObstacleGrid, unit_id: i32, unit_order: UnitOrder) -> Self {
         Self {
             game: SimGame::new(game),
             unit_id,
             unit_order,
-            constants: constants.clone(),
+            other_orders: HashMap::new(),
+            constants,
+            obstacles,
             result: Default::default(),
             units_received_damage: HashSet::new(),
         }
     }
 
-    pub fn unit(&self) -> Option<SimUnit> {
-        self.game.units.iter().find(|u| u.id == self.unit_id).cloned()
+    pub fn unit(&self) -> Option<&SimUnit> {
+        self.game.units.iter().find(|u| u.id == self.unit_id)
+    }
+
+    pub fn set_unit_order(&mut self, unit_order: UnitOrder) {
+        self.unit_order = unit_order;
+    }
+
+    pub fn set_other_orders(&mut self, orders: HashMap<i32, UnitOrder>) {
+        self.other_orders = orders;
+    }
+
+    pub fn result(&self) -> &SimulationResult {
+        &self.result
+    }
+
+    pub fn constants(&self) -> &Constants {
+        self.constants
+    }
+
+    /// Simulated state, with nothing hidden from our units.
+    pub fn game(&self) -> &SimGame {
+        &self.game
     }
 
     pub fn simulate_n_ticks(&mut self, n: usize, debug_interface: &mut Option<&mut DebugInterface>) -> SimulationResult {
@@ -61,17 +101,21 @@ impl Simulator {
         }
 
         self.calc_distance_to_enemies();
+        self.calc_exposure_to_enemies();
         self.result.clone()
     }
 
     pub fn simulate_tick(&mut self, debug_interface: &mut Option<&mut DebugInterface>) {
+        // actions end on time, the unit can aim again right after drinking or looting
+        self.game.finish_actions(self.constants);
+        self.simulate_aim();
         self.simulate_rotation();
-        // self.simulate_action();
         self.simulate_movement(debug_interface);
         self.simulate_projectile_movement();
         self.simulate_zone_damage();
         // self.remove_dead_players();
         // self.regen_health();
+        self.game.current_tick += 1;
 
         if let Some(debug) = debug_interface {
             for unit in self.game.units.iter() {
@@ -91,14 +135,29 @@ impl Simulator {
     //     }
     // }
 
+    /// Aim of the simulated unit going up while it's ordered to aim and down otherwise, like the server does.
+    fn simulate_aim(&mut self) {
+        let aiming = matches!(self.unit_order.action, Some(ActionOrder::Aim { .. }));
+        if let Some(unit) = self.game.units.iter_mut().find(|u| u.id == self.unit_id) {
+            let can_act = unit.is_spawned() && unit.action.is_none();
+            match unit.weapon.map(|w| &self.constants.weapons[w as usize]) {
+                Some(weapon) => {
+                    let step = 1.0 / (weapon.aim_time * self.constants.ticks_per_second).max(1.0);
+                    unit.aim = if aiming && can_act { (unit.aim + step).min(1.0) } else { (unit.aim - step).max(0.0) };
+                }
+                None => unit.aim = 0.0,
+            }
+        }
+    }
+
     fn simulate_rotation(&mut self) {
         let directions = self.game.units.iter().map(|unit| {
             let direction = if unit.id == self.unit_id {
                 self.unit_order.target_direction
             } else {
-                unit.direction
+                self.other_orders.get(&unit.id).map_or(unit.direction, |o| o.target_direction)
             };
-            self.simulate_next_direction(&unit, direction)
+            unit.next_direction(direction, self.constants)
         }).collect_vec();
 
         for (idx, unit) in self.game.units.iter_mut().enumerate() {
@@ -106,14 +165,14 @@ impl Simulator {
         }
     }
 
-    fn simulate_movement(&mut self, debug_interface: &mut Option<&mut DebugInterface>) {
+    fn simulate_movement(&mut self, _debug_interface: &mut Option<&mut DebugInterface>) {
         let positions = self.game.units.iter().map(|unit| {
             let velocity = if unit.id == self.unit_id {
                 self.unit_order.target_velocity
             } else {
-                unit.velocity
+                self.other_orders.get(&unit.id).map_or(unit.velocity, |o| o.target_velocity)
             };
-            self.simulate_next_position(&unit, velocity)
+            unit.next_position_among(velocity, self.constants, self.obstacles.near(unit.position, unit.reach(self.constants)))
         }).collect_vec();
 
         for (idx, unit) in self.game.units.iter_mut().enumerate() {
@@ -121,6 +180,7 @@ impl Simulator {
             unit.position = p;
             unit.velocity = v;
         }
+        self.game.resolve_unit_collisions(self.constants);
     }
 
     fn simulate_projectile_movement(&mut self) {
@@ -130,7 +190,7 @@ impl Simulator {
             if projectile.life_time > 0.0 {
                 projectile.position += projectile.velocity * delta_time;
             }
-            if self.constants.obstacles.iter().filter(|o| o.can_shoot_through).any(|o| o.position.distance_to(&projectile.position) < o.radius) {
+            if self.obstacles.near(projectile.position, 0.0).iter().any(|o| !o.can_shoot_through) {
                 projectile.life_time = -1.0;
                 continue;
             }
@@ -159,185 +219,30 @@ impl Simulator {
                 unit.health -= zone_damage;
                 if unit.id == self.unit_id {
                     self.result.damage_received += zone_damage;
+                    self.result.ticks_outside_zone += 1;
                 }
             }
         }
     }
 
-    fn simulate_next_direction(&self, unit: &SimUnit, target_direction: Vec2) -> Vec2 {
-        if target_direction.length() < self.constants.unit_radius / 2.0 {
-            return unit.direction;
-        }
-
-        let delta_time = 1.0 / self.constants.ticks_per_second;
-        let a1 = target_direction.arg();
-        let a2 = unit.direction.arg();
-        let delta_angle = if (a1 - a2).abs() < PI { a1 - a2 } else { a2 - a1 };
-        let rotation_speed = self.constants.rotation_speed.to_radians();
-        let aim_rotation_speed = if let Some(weapon_idx) = unit.weapon {
-            self.constants.weapons[weapon_idx as usize].aim_rotation_speed.to_radians()
-        } else {
-            rotation_speed
-        };
-        let rotation_cap = (rotation_speed - (rotation_speed - aim_rotation_speed) * unit.aim) * delta_time;
-        let turn_this_tick = delta_angle.clamp(-rotation_cap, rotation_cap);
-
-        unit.direction.rotate(turn_this_tick)
-    }
-
-    fn simulate_next_position(&self, unit: &SimUnit, mut target_velocity: Vec2) -> (Vec2, Vec2) {
-        let delta_time = 1.0 / self.constants.ticks_per_second;
-
-        let current_speed = unit.velocity.length();
-        let target_speed = self.max_speed_for_unit(unit, target_velocity);
-        target_velocity = target_velocity.clamp(target_speed);
-
-        let delta_velocity = (target_velocity - unit.velocity).clamp(self.constants.unit_acceleration * delta_time);
-
-        let velocity = unit.velocity + delta_velocity;
-
-        let mut position = unit.position + velocity * delta_time;
-        let collision = self.constants.obstacles.iter().find(|o| {
-            o.position.distance_to(&position) <= o.radius + self.constants.unit_radius
-        });
-
-        // let collision = self.constants.obstacles.iter().find_map(|o| {
-        //     calculate_circle_line(o.position, o.radius, unit.position, position)
-        // });
-
-        // * считаем точку столкновения с кругом, пусть это pt
-        // * считаем вектор obstacle.center - pt - это нормаль
-        // * поворачиваем вектор на 90 градусов влево (или вправо, в зависимости от того с какой стороны столкнулись с препятствием), это вектор касательной, назовем его m
-        // * считаем k = dot(v, m) / dot(m, m); v_new = m * k; (где v - изначальная скорость юнита до столкновения), это проекция вектора скорости на касательную = новый вектор скорости, по которому будет двигаться юнит после столкновения
-        //
-        // PS. dot - скалярное произведение векторов
-
-
-        if let Some(obs) = collision {
-            let pushback_length = obs.radius + self.constants.unit_radius - obs.position.distance_to(&position);
-            let normal = obs.position - position;
-
-            let wanted_to_move_by = velocity.length() * delta_time;
-            let pushback_vec = Vec2::from_length_and_angle(pushback_length, normal.angle());
-            position -= pushback_vec;
-            let moved_by_so_far = position.distance_to(&unit.position);
-            let movement_left = wanted_to_move_by - moved_by_so_far;
-
-            let angle = normal.angle_with(&target_velocity);
-            let velocity_correction = Vec2::from_length_and_angle(velocity.length() * angle.cos(), normal.angle());
-
-            let tangential_velocity = Vec2::from_length_and_angle(movement_left * angle.sin(), (velocity - velocity_correction).angle());
-            position += tangential_velocity;
-        }
-        (velocity, position)
-    }
-
-    fn max_speed_for_unit(&self, unit: &SimUnit, target_velocity: Vec2) -> f64 {
-        if unit.remaining_spawn_time.is_some() {
-            return self.constants.spawn_movement_speed;
-        } else {
-            let aim_movement_speed_modifier = if let Some(weapon_idx) = unit.weapon {
-                self.constants.weapons[weapon_idx as usize].aim_movement_speed_modifier
-            } else {
-                1.0
-            };
-            let aim = unit.aim;
-
-            let max_unit_forward_speed = self.constants.max_unit_forward_speed * (1.0 - (1.0 - aim_movement_speed_modifier) * aim);
-            let max_unit_backward_speed = self.constants.max_unit_backward_speed * (1.0 - (1.0 - aim_movement_speed_modifier) * aim);
-
-            let d = (max_unit_forward_speed - max_unit_backward_speed) / 2.0;
-            let r = (max_unit_forward_speed + max_unit_backward_speed) / 2.0;
-
-            let orig_v = target_velocity;
-            let offset = unit.direction;
-            // println!("tick {} targ_v angle {}, dir angle {}", self.game.current_tick, orig_v, offset);
-
-            let angle_a = (offset.arg() - orig_v.arg()).abs();
-            if f64_approx_eq(angle_a, 0.0) {
-                return max_unit_forward_speed;
-            } else if f64_approx_eq(angle_a, PI) {
-                return max_unit_backward_speed;
-            }
-
-            // println!("angle_a {}", angle_a.to_degrees());
-            if angle_a.is_nan() {
-                // println!("offset: {}, orig_v: {}", offset, orig_v);
-            }
-
-            let sin_b = d * angle_a.sin() / r;
-            let angle_b = sin_b.asin();
-            // println!("angle_b {}", angle_b.to_degrees() );
-
-            let angle_c = PI - angle_a - angle_b;
-            // println!("angle_c {}", angle_c.to_degrees());
-
-            let v_len = r * angle_c.sin() / angle_a.sin();
-            // println!("v_len {}", v_len);
-
-            v_len
-        }
-    }
-
     fn calc_distance_to_enemies(&mut self) {
-        if let Some(me) = self.unit() {
-            let enemies = self.game.units.iter().filter(|u| u.player_id != me.player_id).collect_vec();
-            let total_distance: f64 = enemies.iter().map(|u| u.position.distance_to(&me.position)).sum();
+        let Some(me) = self.unit() else { return };
+        let enemies = self.game.units.iter().filter(|u| u.player_id != me.player_id).collect_vec();
+        let total_distance: f64 = enemies.iter().map(|u| u.position.distance_to(&me.position)).sum();
+        if !enemies.is_empty() {
             self.result.avg_distance_to_enemies = total_distance / enemies.len() as f64;
         }
     }
-}
-
-impl MyStrategy {
-    pub fn check_expected_position_vs_actual(&mut self, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) {
-        if debug_interface.is_none() {
-            return;
-        }
-
-        for unit in self.my_units.iter() {
-            if let Some((pos, dir, vel)) = self.next_positions.get(&unit.id) {
-                if !pos.approx_equal(unit.position) {
-                    // println!("tick {}: diff position {}", game.current_tick, (unit.position - *pos).length())
-                }
-
-                if (dir.arg() - unit.direction.arg()) > 10f64.powi(-9) {
-                    // println!(
-                    //     "tick {}: diff direction {}, expected {}, got {}",
-                    //     game.current_tick,
-                    //     (unit.direction.arg() - dir.arg()).to_degrees(),
-                    //     dir.arg().to_degrees(),
-                    //     unit.direction.arg().to_degrees(),
-                    // );
-                    // if let Some(debug) = debug_interface.as_mut() {
-                    //     debug.add_segment(unit.position, unit.position + unit.direction, 0.2, Color::green());
-                    //     debug.add_segment(unit.position, unit.position + *dir, 0.2, Color::red());
-                    // }
-                }
-
-                if !vel.approx_equal(unit.velocity) {
-                    // println!("tick {}: diff velocity {}, expected {}, got {}", game.current_tick, (unit.velocity - *vel).length(), *vel, unit.velocity);
-                    // if let Some(debug) = debug_interface.as_mut() {
-                    //     debug.add_segment(unit.position, unit.position + unit.velocity, 0.2, Color::green());
-                    //     debug.add_segment(unit.position, unit.position + *vel, 0.2, Color::red());
-                    //     debug.add_segment(unit.position, unit.position + unit.direction, 0.1, Color::blue().a(0.7));
-                    // }
-                }
-            }
-        }
-    }
 
-    pub fn predict_next_positions(&mut self, game: &Game, unit: &Unit, unit_order: &UnitOrder, debug_interface: &mut Option<&mut DebugInterface>) {
-        let mut simulation = Simulator::new(game, &self.constants, unit.id, unit_order.clone());
-        simulation.simulate_tick(debug_interface);
-        if let Some(sim_unit) = simulation.unit() {
-            self.next_positions
-                .entry(unit.id)
-                .and_modify(|(pos, dir, vel)| {
-                    *pos = sim_unit.position;
-                    *dir = sim_unit.direction;
-                    *vel = sim_unit.velocity;
+    fn calc_exposure_to_enemies(&mut self) {
+        if let Some(me) = self.game.units.iter().find(|u| u.id == self.unit_id) {
+            self.result.exposed_to_enemies += self.game.units.iter()
+                .filter(|u| u.player_id != me.player_id && u.weapon.is_some())
+                .filter(|enemy| {
+                    !self.obstacles.along_segment(enemy.position, me.position, 0.0).iter()
+                        .any(|o| !o.can_shoot_through)
                 })
-                .or_insert_with(|| (sim_unit.position, sim_unit.direction, sim_unit.velocity));
+                .count();
         }
     }
 }
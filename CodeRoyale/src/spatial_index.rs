@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use crate::model::*;
+use crate::{segment_intersects_circle, MyStrategy};
+
+const OBSTACLE_CELL_SIZE: f64 = 8.0;
+const DYNAMIC_CELL_SIZE: f64 = 10.0;
+
+/// Uniform grid over the obstacles, they never move so it's built once per game.
+pub struct ObstacleGrid {
+    origin: Vec2,
+    size: usize,
+    /// Indexes into `obstacles` of everything overlapping each cell
+    cells: Vec<Vec<usize>>,
+    obstacles: Vec<Obstacle>,
+}
+
+impl ObstacleGrid {
+    pub fn new(constants: &Constants) -> Self {
+        let max_radius = constants.obstacles.iter().map(|o| o.radius).fold(0.0, f64::max);
+        let half_extent = constants.initial_zone_radius + max_radius;
+        let size = (2.0 * half_extent / OBSTACLE_CELL_SIZE).ceil() as usize + 1;
+        let mut grid = Self {
+            origin: Vec2::from_xy(-half_extent, -half_extent),
+            size,
+            cells: vec![vec![]; size * size],
+            obstacles: constants.obstacles.clone(),
+        };
+        for (idx, o) in constants.obstacles.iter().enumerate() {
+            let margin = Vec2::from_xy(o.radius, o.radius);
+            let (min_x, min_y, max_x, max_y) = grid.cell_range(o.position - margin, o.position + margin);
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    grid.cells[y * size + x].push(idx);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    fn cell_range(&self, min: Vec2, max: Vec2) -> (usize, usize, usize, usize) {
+        let cell = |value: f64, origin: f64| ((value - origin) / OBSTACLE_CELL_SIZE).floor().clamp(0.0, (self.size - 1) as f64) as usize;
+        (cell(min.x, self.origin.x), cell(min.y, self.origin.y), cell(max.x, self.origin.x), cell(max.y, self.origin.y))
+    }
+
+    /// Obstacles overlapping the box, each one once.
+    fn in_box(&self, min: Vec2, max: Vec2) -> Vec<&Obstacle> {
+        let (min_x, min_y, max_x, max_y) = self.cell_range(min, max);
+        let mut indexes = vec![];
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                indexes.extend_from_slice(&self.cells[y * self.size + x]);
+            }
+        }
+        indexes.sort_unstable();
+        indexes.dedup();
+        indexes.into_iter().map(|idx| &self.obstacles[idx]).collect()
+    }
+
+    /// Obstacles with their edge within `distance` of the point.
+    pub fn near(&self, point: Vec2, distance: f64) -> Vec<&Obstacle> {
+        let margin = Vec2::from_xy(distance, distance);
+        let mut obstacles = self.in_box(point - margin, point + margin);
+        obstacles.retain(|o| o.position.distance_to(&point) <= o.radius + distance);
+        obstacles
+    }
+
+    /// Obstacles a circle of `radius` moving along the segment would touch.
+    pub fn along_segment(&self, start: Vec2, end: Vec2, radius: f64) -> Vec<&Obstacle> {
+        let min = Vec2::from_xy(start.x.min(end.x) - radius, start.y.min(end.y) - radius);
+        let max = Vec2::from_xy(start.x.max(end.x) + radius, start.y.max(end.y) + radius);
+        let mut obstacles = self.in_box(min, max);
+        obstacles.retain(|o| segment_intersects_circle(start, end, o.position, o.radius + radius));
+        obstacles
+    }
+}
+
+/// Spatial hash of ids by position, for things that move and get rebuilt every tick.
+#[derive(Clone, Debug, Default)]
+pub struct PointIndex {
+    cells: HashMap<(i64, i64), Vec<(i32, Vec2)>>,
+}
+
+impl PointIndex {
+    pub fn new(items: impl IntoIterator<Item = (i32, Vec2)>) -> Self {
+        let mut index = Self::default();
+        for (id, position) in items {
+            index.cells.entry(Self::cell(position)).or_default().push((id, position));
+        }
+        index
+    }
+
+    fn cell(position: Vec2) -> (i64, i64) {
+        ((position.x / DYNAMIC_CELL_SIZE).floor() as i64, (position.y / DYNAMIC_CELL_SIZE).floor() as i64)
+    }
+
+    /// Ids of everything within `radius` of the point.
+    pub fn within(&self, point: Vec2, radius: f64) -> Vec<i32> {
+        let (min_x, min_y) = Self::cell(point - Vec2::from_xy(radius, radius));
+        let (max_x, max_y) = Self::cell(point + Vec2::from_xy(radius, radius));
+        let mut ids = vec![];
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(items) = self.cells.get(&(x, y)) {
+                    ids.extend(items.iter().filter(|(_, p)| p.distance_to(&point) <= radius).map(|(id, _)| *id));
+                }
+            }
+        }
+        ids
+    }
+}
+
+/// Per tick index of units, projectiles and loot.
+#[derive(Clone, Debug, Default)]
+pub struct DynamicIndex {
+    pub allies: PointIndex,
+    pub enemies: PointIndex,
+    pub projectiles: PointIndex,
+    pub loot: PointIndex,
+}
+
+impl MyStrategy {
+    pub(crate) fn update_dynamic_index(&mut self) {
+        self.dynamic_index = DynamicIndex {
+            allies: PointIndex::new(self.my_units.iter().map(|u| (u.id, u.position))),
+            enemies: PointIndex::new(self.enemy_units.iter().map(|u| (u.id, u.position))),
+            projectiles: PointIndex::new(self.seen_projectiles.values().map(|p| (p.id, p.position))),
+            loot: PointIndex::new(self.seen_loot.values().map(|l| (l.id, l.position))),
+        };
+    }
+
+    pub fn enemies_within(&self, point: Vec2, radius: f64) -> Vec<&Unit> {
+        self.dynamic_index.enemies.within(point, radius).iter()
+            .filter_map(|id| self.units_by_id.get(id))
+            .collect()
+    }
+
+    pub fn allies_within(&self, point: Vec2, radius: f64) -> Vec<&Unit> {
+        self.dynamic_index.allies.within(point, radius).iter()
+            .filter_map(|id| self.units_by_id.get(id))
+            .collect()
+    }
+
+    /// Loot still believed to be there, picked up loot drops out of `seen_loot` before the index is rebuilt.
+    pub fn loot_within(&self, point: Vec2, radius: f64) -> Vec<&Loot> {
+        self.dynamic_index.loot.within(point, radius).iter()
+            .filter_map(|id| self.seen_loot.get(id))
+            .collect()
+    }
+
+    /// Whether any visible enemy can shoot at the point.
+    pub fn is_in_enemy_fire_range(&self, point: Vec2) -> bool {
+        let max_range = self.constants.weapons.iter().map(|w| w.range()).fold(0.0, f64::max);
+        self.enemies_within(point, max_range).iter()
+            .any(|enemy| enemy.position.distance_to(&point) <= enemy.weapon_range(&self.constants))
+    }
+}
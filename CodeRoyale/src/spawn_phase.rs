@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use crate::debugging::Color;
+use crate::model::*;
+use crate::{DebugInterface, LootAssignment, MyStrategy, Vec2Order};
+
+// landing spots are looked for on this many rings within reach
+const LANDING_RINGS: usize = 3;
+const LANDING_SAMPLES: usize = 12;
+// a landing spot has to stay inside the zone this long after the unit lands
+const LANDING_ZONE_SLACK: f64 = 5.0;
+// loot this many seconds of walking away from a landing spot is worth half as much
+const LOADOUT_TRAVEL_SECONDS: f64 = 3.0;
+// the landing spot picked before is kept unless another one scores this much more, the rings move with the unit
+const LANDING_SWITCH_MARGIN: f64 = 0.1;
+
+#[derive(Clone, Debug)]
+pub struct SpawnConfig {
+    /// Room kept between a landing spot and obstacles or other units, on top of touching
+    pub clearance: f64,
+    /// How much every enemy able to shoot at a landing spot takes off its score, loot values are about 0 to 1
+    pub danger_weight: f64,
+    /// Items on a loadout path
+    pub loadout_length: usize,
+    /// Loot further than this from where the unit lands isn't planned for
+    pub loadout_radius: f64,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            clearance: 0.5,
+            danger_weight: 1.0,
+            loadout_length: 3,
+            loadout_radius: 30.0,
+        }
+    }
+}
+
+/// Unit with the item picked up, roughly as the server would give it.
+fn with_item(unit: &Unit, item: &Item, constants: &Constants) -> Unit {
+    let mut unit = unit.clone();
+    match *item {
+        Item::Weapon { type_index } => unit.weapon = Some(type_index),
+        Item::Ammo { weapon_type_index, amount } => {
+            let max = constants.weapons[weapon_type_index as usize].max_inventory_ammo;
+            let ammo = &mut unit.ammo[weapon_type_index as usize];
+            *ammo = (*ammo + amount).min(max);
+        }
+        Item::ShieldPotions { amount } => {
+            unit.shield_potions = (unit.shield_potions + amount).min(constants.max_shield_potions_in_inventory);
+        }
+    }
+    unit
+}
+
+impl MyStrategy {
+    /// Whether dying now still means coming back.
+    pub fn can_respawn(&self, unit: &Unit, game: &Game) -> bool {
+        unit.extra_lives > 0 && game.zone.current_radius >= self.constants.last_respawn_zone_radius
+    }
+
+    /// From 0 on the last life to nearly 1 with many to spare, the more the unit can afford to take fights.
+    pub fn risk_tolerance(&self, unit: &Unit, game: &Game) -> f64 {
+        let lives = if self.can_respawn(unit, game) { unit.extra_lives } else { 0 } as f64;
+        lives / (lives + 1.0)
+    }
+
+    /// Picks landing spots for spawning units, and loadout paths for them to follow once they land.
+    pub(crate) fn plan_spawns(&mut self, game: &Game) {
+        let spawning = self.my_units.iter().filter(|u| u.remaining_spawn_time.is_some()).cloned().collect::<Vec<_>>();
+        self.landing_spots.retain(|id, _| spawning.iter().any(|u| u.id == *id));
+
+        for unit in spawning.iter() {
+            if let Some(spot) = self.landing_spot(unit, game) {
+                self.landing_spots.insert(unit.id, spot);
+            }
+        }
+
+        // the path is planned from where the unit lands and kept once it's out, until it's walked
+        self.loadout_paths.retain(|id, _| self.units_by_id.contains_key(id));
+        for unit in spawning.iter() {
+            let start = self.landing_spots.get(&unit.id).copied().unwrap_or(unit.position);
+            let path = self.loadout_path(unit, start);
+            self.loadout_paths.insert(unit.id, path);
+        }
+        self.follow_loadout_paths();
+    }
+
+    /// Where the spawning unit is about to land.
+    pub fn landing_spot_of(&self, unit_id: i32) -> Option<Vec2> {
+        self.landing_spots.get(&unit_id).copied()
+    }
+
+    /// Best spot within reach before spawning ends: clear of obstacles and other units, inside the zone,
+    /// close to useful loot and away from enemies. The spot picked before stays unless another is clearly better.
+    fn landing_spot(&self, unit: &Unit, game: &Game) -> Option<Vec2> {
+        let reach = unit.remaining_spawn_time? * self.constants.spawn_movement_speed;
+        let clearance = self.constants.unit_radius + self.params.spawn.clearance;
+        let others = self.units_by_id.values()
+            .filter(|other| other.id != unit.id)
+            .map(|other| self.landing_spots.get(&other.id).copied().unwrap_or(other.position))
+            .collect::<Vec<_>>();
+        let danger_weight = self.params.spawn.danger_weight * (1.0 - self.risk_tolerance(unit, game));
+        let previous = self.landing_spots.get(&unit.id).copied().filter(|spot| spot.distance_to(&unit.position) <= reach);
+
+        let candidates = previous.into_iter().chain(std::iter::once(unit.position)).chain((1..=LANDING_RINGS).flat_map(|ring| {
+            let distance = reach * ring as f64 / LANDING_RINGS as f64;
+            (0..LANDING_SAMPLES).map(move |i| unit.position + Vec2::from_length_and_angle(distance, 2.0 * PI * i as f64 / LANDING_SAMPLES as f64))
+        }));
+        candidates
+            .filter(|p| self.obstacle_index.near(*p, clearance).is_empty())
+            .filter(|p| others.iter().all(|other| other.distance_to(p) > self.constants.unit_radius + clearance))
+            .filter(|p| self.zone_forecast.time_until_outside(*p) > unit.remaining_spawn_time.unwrap_or(0.0) + LANDING_ZONE_SLACK)
+            .map(|p| {
+                let loot = self.seen_loot.values()
+                    .map(|loot| self.loot_value(unit, &loot.item) / (1.0 + self.walking_time(p, loot.position) / LOADOUT_TRAVEL_SECONDS))
+                    .fold(0.0, f64::max);
+                let threats = self.enemy_units.iter()
+                    .filter(|enemy| enemy.position.distance_to(&p) <= enemy.weapon_range(&self.constants))
+                    .count();
+                let kept = if previous.is_some_and(|spot| spot.distance_to(&p) < 1e-9) { LANDING_SWITCH_MARGIN } else { 0.0 };
+                (p, loot - danger_weight * threats as f64 + kept)
+            })
+            .max_by(|(_, s1), (_, s2)| s1.total_cmp(s2))
+            .map(|(p, _)| p)
+    }
+
+    fn walking_time(&self, from: Vec2, to: Vec2) -> f64 {
+        from.distance_to(&to) / self.constants.max_unit_forward_speed
+    }
+
+    /// Loot to pick up one after another from `start`, each the best value for the walk given what's picked up before it.
+    fn loadout_path(&self, unit: &Unit, start: Vec2) -> Vec<i32> {
+        let claimed = self.loadout_paths.iter()
+            .filter(|(id, _)| **id != unit.id)
+            .flat_map(|(_, path)| path.iter().copied())
+            .collect::<HashSet<_>>();
+        let mut inventory = unit.clone();
+        let mut position = start;
+        let mut path = vec![];
+        while path.len() < self.params.spawn.loadout_length {
+            let next = self.seen_loot.values()
+                .filter(|loot| !claimed.contains(&loot.id) && !path.contains(&loot.id))
+                .filter(|loot| loot.position.distance_to(&start) <= self.params.spawn.loadout_radius)
+                .map(|loot| (loot, self.loot_value(&inventory, &loot.item) / (1.0 + self.walking_time(position, loot.position) / LOADOUT_TRAVEL_SECONDS)))
+                .filter(|(_, utility)| *utility > 0.0)
+                .max_by(|(_, u1), (_, u2)| u1.total_cmp(u2));
+            let Some((loot, _)) = next else { break };
+            inventory = with_item(&inventory, &loot.item, &self.constants);
+            position = loot.position;
+            path.push(loot.id);
+        }
+        path
+    }
+
+    /// Drops loot from the front of the paths once it's gone or no longer wanted, and sends units
+    /// that are out after the next item on their path.
+    fn follow_loadout_paths(&mut self) {
+        let mut paths = std::mem::take(&mut self.loadout_paths);
+        for (unit_id, path) in paths.iter_mut() {
+            let Some(unit) = self.units_by_id.get(unit_id) else { continue };
+            while let Some(loot_id) = path.first() {
+                match self.seen_loot.get(loot_id) {
+                    Some(loot) if self.wants_loot(unit, loot) => break,
+                    _ => { path.remove(0); }
+                }
+            }
+            if unit.remaining_spawn_time.is_some() {
+                continue;
+            }
+            if let Some(loot_id) = path.first().filter(|id| !self.is_in_enemy_fire_range(self.seen_loot[*id].position)) {
+                let value = self.loot_value(unit, &self.seen_loot[loot_id].item);
+                // whoever the loot plan sent there goes elsewhere, the path was there first
+                self.loot_plan.retain(|id, assignment| *id == *unit_id || assignment.loot_id != *loot_id);
+                self.loot_plan.insert(*unit_id, LootAssignment { loot_id: *loot_id, value, utility: value });
+                self.move_targets.retain(|_, id| id != unit_id);
+                self.move_targets.insert(*loot_id, *unit_id);
+            }
+        }
+        paths.retain(|_, path| !path.is_empty());
+        self.loadout_paths = paths;
+    }
+
+    pub(crate) fn velocity_land_safely(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        if unit.remaining_spawn_time.is_none() {
+            return self.reject("already landed");
+        }
+        let spot = self.landing_spots.get(&unit.id).copied().or_else(|| self.reject("nowhere clear to land"))?;
+        Some(Vec2Order {
+            // arriving at the spot rather than flying past it
+            vec: ((spot - unit.position) * self.constants.ticks_per_second).clamp(self.constants.spawn_movement_speed),
+            description: Some(format!("landing at {}", spot.to_short_string())),
+            target: None,
+            score: 1.0,
+        })
+    }
+
+    pub fn visualize_spawn_plans(&self, debug_interface: &mut Option<&mut DebugInterface>) {
+        if let Some(debug) = debug_interface.as_mut() {
+            for (unit_id, spot) in self.landing_spots.iter() {
+                debug.add_ring(*spot, self.constants.unit_radius, 0.1, Color::green().a(0.8));
+                if let Some(unit) = self.units_by_id.get(unit_id) {
+                    debug.add_segment(unit.position, *spot, 0.05, Color::green().a(0.5));
+                }
+            }
+            for (unit_id, path) in self.loadout_paths.iter() {
+                let Some(unit) = self.units_by_id.get(unit_id) else { continue };
+                let mut from = self.landing_spots.get(unit_id).copied().unwrap_or(unit.position);
+                for loot in path.iter().filter_map(|id| self.seen_loot.get(id)) {
+                    debug.add_segment(from, loot.position, 0.1, Color::green().a(0.3));
+                    from = loot.position;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::path::Path;
+use crate::{AimingConfig, MovementSearchConfig, SpawnConfig, SustainConfig};
+
+/// Every tunable number by `section.name`, with the range the tuner searches it in.
+pub const PARAM_RANGES: &[(&str, f64, f64)] = &[
+    ("logic.approach_speed", 2.0, 10.0),
+    ("logic.close_in_falloff", 10.0, 60.0),
+    ("logic.steer_leeway", 0.0, 2.0),
+    ("logic.steer_look_ahead_ticks", 3.0, 30.0),
+    ("logic.belief_threat_confidence", 0.1, 0.9),
+    ("logic.stand_ground_risk_tolerance", 0.3, 1.0),
+    ("logic.wander_clearance", 0.0, 1.0),
+    ("logic.loot_zone_fraction", 0.5, 1.0),
+    ("aiming.min_hit_probability", 0.0, 0.5),
+    ("aiming.dodge_factor", 0.0, 1.0),
+    ("sustain.retreat_below", 0.2, 0.8),
+    ("sustain.drink_margin", 0.0, 1.0),
+    ("sustain.cover_search_radius", 5.0, 25.0),
+    ("spawn.clearance", 0.0, 2.0),
+    ("spawn.danger_weight", 0.0, 3.0),
+    ("spawn.loadout_length", 1.0, 5.0),
+    ("spawn.loadout_radius", 10.0, 60.0),
+    ("movement_search.headings", 4.0, 24.0),
+    ("movement_search.ticks_per_step", 2.0, 10.0),
+    ("movement_search.depth", 1.0, 5.0),
+    ("movement_search.beam_width", 2.0, 12.0),
+];
+
+/// Numbers the strategy plays by, read from an ini-like file with one section per group:
+///
+/// ```text
+/// [logic]
+/// approach_speed = 6.0
+/// [sustain]
+/// retreat_below = 0.4
+/// ```
+///
+/// Anything not in the file keeps its default.
+#[derive(Clone, Debug)]
+pub struct StrategyParams {
+    /// Slowest a unit walks up to loot or an enemy, so it doesn't crawl the last bit
+    pub approach_speed: f64,
+    /// Distance to an enemy at which closing in on it is worth half as much
+    pub close_in_falloff: f64,
+    /// Room kept between a unit and the obstacle it steers around
+    pub steer_leeway: f64,
+    /// How many ticks ahead an obstacle in the way is looked for
+    pub steer_look_ahead_ticks: usize,
+    /// Unseen enemies believed to be somewhere at least this likely are kept out of range of
+    pub belief_threat_confidence: f64,
+    /// Healthy units that can afford to die this much don't back off out of fire range
+    pub stand_ground_risk_tolerance: f64,
+    /// Room between random waypoints and obstacles, on top of half a unit
+    pub wander_clearance: f64,
+    /// Loot further from the zone center than this part of its radius is left alone, the zone gets there first
+    pub loot_zone_fraction: f64,
+    pub aiming: AimingConfig,
+    pub sustain: SustainConfig,
+    pub spawn: SpawnConfig,
+    pub movement_search: MovementSearchConfig,
+}
+
+impl Default for StrategyParams {
+    fn default() -> Self {
+        Self {
+            approach_speed: 6.0,
+            close_in_falloff: 30.0,
+            steer_leeway: 1.0,
+            steer_look_ahead_ticks: 10,
+            belief_threat_confidence: 0.5,
+            stand_ground_risk_tolerance: 0.5,
+            wander_clearance: 0.2,
+            loot_zone_fraction: 0.9,
+            aiming: AimingConfig::default(),
+            sustain: SustainConfig::default(),
+            spawn: SpawnConfig::default(),
+            movement_search: MovementSearchConfig::default(),
+        }
+    }
+}
+
+enum ParamMut<'a> {
+    Real(&'a mut f64),
+    Count(&'a mut usize),
+}
+
+impl StrategyParams {
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        text.parse().map_err(std::io::Error::other)
+    }
+
+    fn param_mut(&mut self, name: &str) -> Option<ParamMut<'_>> {
+        use ParamMut::*;
+        Some(match name {
+            "logic.approach_speed" => Real(&mut self.approach_speed),
+            "logic.close_in_falloff" => Real(&mut self.close_in_falloff),
+            "logic.steer_leeway" => Real(&mut self.steer_leeway),
+            "logic.steer_look_ahead_ticks" => Count(&mut self.steer_look_ahead_ticks),
+            "logic.belief_threat_confidence" => Real(&mut self.belief_threat_confidence),
+            "logic.stand_ground_risk_tolerance" => Real(&mut self.stand_ground_risk_tolerance),
+            "logic.wander_clearance" => Real(&mut self.wander_clearance),
+            "logic.loot_zone_fraction" => Real(&mut self.loot_zone_fraction),
+            "aiming.min_hit_probability" => Real(&mut self.aiming.min_hit_probability),
+            "aiming.dodge_factor" => Real(&mut self.aiming.dodge_factor),
+            "sustain.retreat_below" => Real(&mut self.sustain.retreat_below),
+            "sustain.drink_margin" => Real(&mut self.sustain.drink_margin),
+            "sustain.cover_search_radius" => Real(&mut self.sustain.cover_search_radius),
+            "spawn.clearance" => Real(&mut self.spawn.clearance),
+            "spawn.danger_weight" => Real(&mut self.spawn.danger_weight),
+            "spawn.loadout_length" => Count(&mut self.spawn.loadout_length),
+            "spawn.loadout_radius" => Real(&mut self.spawn.loadout_radius),
+            "movement_search.headings" => Count(&mut self.movement_search.headings),
+            "movement_search.ticks_per_step" => Count(&mut self.movement_search.ticks_per_step),
+            "movement_search.depth" => Count(&mut self.movement_search.depth),
+            "movement_search.beam_width" => Count(&mut self.movement_search.beam_width),
+            _ => return None,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "logic.approach_speed" => self.approach_speed,
+            "logic.close_in_falloff" => self.close_in_falloff,
+            "logic.steer_leeway" => self.steer_leeway,
+            "logic.steer_look_ahead_ticks" => self.steer_look_ahead_ticks as f64,
+            "logic.belief_threat_confidence" => self.belief_threat_confidence,
+            "logic.stand_ground_risk_tolerance" => self.stand_ground_risk_tolerance,
+            "logic.wander_clearance" => self.wander_clearance,
+            "logic.loot_zone_fraction" => self.loot_zone_fraction,
+            "aiming.min_hit_probability" => self.aiming.min_hit_probability,
+            "aiming.dodge_factor" => self.aiming.dodge_factor,
+            "sustain.retreat_below" => self.sustain.retreat_below,
+            "sustain.drink_margin" => self.sustain.drink_margin,
+            "sustain.cover_search_radius" => self.sustain.cover_search_radius,
+            "spawn.clearance" => self.spawn.clearance,
+            "spawn.danger_weight" => self.spawn.danger_weight,
+            "spawn.loadout_length" => self.spawn.loadout_length as f64,
+            "spawn.loadout_radius" => self.spawn.loadout_radius,
+            "movement_search.headings" => self.movement_search.headings as f64,
+            "movement_search.ticks_per_step" => self.movement_search.ticks_per_step as f64,
+            "movement_search.depth" => self.movement_search.depth as f64,
+            "movement_search.beam_width" => self.movement_search.beam_width as f64,
+            _ => return None,
+        })
+    }
+
+    /// Sets the parameter by `section.name`, counts are rounded and kept at least 1.
+    pub fn set(&mut self, name: &str, value: f64) -> Result<(), String> {
+        match self.param_mut(name).ok_or_else(|| format!("unknown parameter {:?}", name))? {
+            ParamMut::Real(param) => *param = value,
+            ParamMut::Count(param) => *param = value.round().max(1.0) as usize,
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for StrategyParams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        let mut section: Option<&str> = None;
+
+        for (line_idx, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", line_idx + 1, message);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(name.trim());
+                continue;
+            }
+
+            let section = section.ok_or_else(|| error("expected a section header".to_string()))?;
+            let (key, value) = line.split_once('=').ok_or_else(|| error(format!("expected `name = value`, got {:?}", line)))?;
+            let value = value.trim().parse().map_err(|_| error(format!("can't parse value {:?}", value.trim())))?;
+            params.set(&format!("{}.{}", section, key.trim()), value).map_err(error)?;
+        }
+
+        Ok(params)
+    }
+}
+
+/// Writes every parameter in the format `from_str` reads.
+impl fmt::Display for StrategyParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut last_section = None;
+        for (name, _, _) in PARAM_RANGES {
+            let (section, key) = name.split_once('.').unwrap();
+            if last_section != Some(section) {
+                if last_section.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(f, "[{}]", section)?;
+                last_section = Some(section);
+            }
+            writeln!(f, "{} = {}", key, self.get(name).unwrap())?;
+        }
+        Ok(())
+    }
+}
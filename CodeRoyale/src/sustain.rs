@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use crate::model::*;
+use crate::{segment_circle_contact, ActionOrderOrder, DebugInterface, MyStrategy, Vec2Order};
+
+// enemies out of a clear line of fire have to walk around the cover first, this takes about as long
+const FLANK_SECONDS: f64 = 1.0;
+// enemies further than their range plus this many seconds of walking aren't a threat to sustaining
+const THREAT_HORIZON: f64 = 5.0;
+
+#[derive(Clone, Debug)]
+pub struct SustainConfig {
+    /// Health plus shield, as a part of full health, below which the unit backs off to recover
+    pub retreat_below: f64,
+    /// Seconds without being shot wanted on top of the potion use time before drinking
+    pub drink_margin: f64,
+    /// Furthest a unit goes looking for cover
+    pub cover_search_radius: f64,
+}
+
+impl Default for SustainConfig {
+    fn default() -> Self {
+        Self {
+            retreat_below: 0.5,
+            drink_margin: 0.3,
+            cover_search_radius: 12.0,
+        }
+    }
+}
+
+/// What a unit does about its health and shield, picked by `plan_sustain`.
+#[derive(Clone, Copy, Debug)]
+pub enum SustainDecision {
+    /// Nothing to recover, or no time for it
+    Fight,
+    /// Nobody can shoot the unit before the potion kicks in
+    Drink,
+    /// Too hurt to stay exposed, get behind the cover first
+    Retreat { cover: Vec2 },
+    /// Safe for now with enemies around and nothing to drink, stay put until the health comes back
+    Regenerate,
+}
+
+#[derive(Clone, Debug)]
+pub struct SustainPlan {
+    pub decision: SustainDecision,
+    /// Seconds until anybody can hit the unit where it is
+    pub safe_for: f64,
+}
+
+impl MyStrategy {
+    pub(crate) fn plan_sustain(&mut self, game: &Game) {
+        let plan: HashMap<i32, SustainPlan> = self.my_units.iter()
+            .filter(|u| u.remaining_spawn_time.is_none())
+            .map(|unit| (unit.id, self.sustain_plan_for(unit, game)))
+            .collect();
+        self.sustain_plan = plan;
+    }
+
+    pub fn sustain_plan_for(&self, unit: &Unit, game: &Game) -> SustainPlan {
+        let safe_for = self.seconds_until_shot_at(unit.position).min(self.seconds_until_projectile_hit(unit));
+        // with lives to spare the unit stays in the fight longer
+        let retreat_below = self.params.sustain.retreat_below * (1.0 - self.risk_tolerance(unit, game) / 2.0);
+        let hurt = (unit.health + unit.shield) / self.constants.unit_health < retreat_below;
+        let can_drink = unit.shield_potions > 0 && unit.shield < self.constants.max_shield;
+
+        let decision = if can_drink && safe_for > self.constants.shield_potion_use_time + self.params.sustain.drink_margin {
+            SustainDecision::Drink
+        } else if !hurt || safe_for.is_infinite() {
+            // with nobody around there's nothing to hide from, regen comes while doing whatever else
+            SustainDecision::Fight
+        } else if safe_for > self.seconds_until_regenerated(unit).min(THREAT_HORIZON) {
+            SustainDecision::Regenerate
+        } else {
+            match self.find_cover(unit) {
+                Some(cover) => SustainDecision::Retreat { cover },
+                None => SustainDecision::Fight,
+            }
+        };
+        SustainPlan { decision, safe_for }
+    }
+
+    /// Seconds from now until a projectile can reach `position` from an enemy who turns, aims and fires
+    /// as soon as its weapon allows, or from where an unseen enemy is believed to be.
+    pub fn seconds_until_shot_at(&self, position: Vec2) -> f64 {
+        let enemy_shot = self.enemy_units.iter()
+            .filter(|enemy| enemy.remaining_spawn_time.is_none())
+            .filter_map(|enemy| self.seconds_until_shot_by(enemy, position))
+            .fold(f64::INFINITY, f64::min);
+
+        let believed_shot = self.unseen_enemies(self.params.belief_threat_confidence).iter()
+            .map(|belief| {
+                let walk = (belief.position.distance_to(&position) - belief.radius - belief.weapon_range(&self.constants)).max(0.0);
+                walk / self.constants.max_unit_forward_speed
+            })
+            .fold(f64::INFINITY, f64::min);
+
+        enemy_shot.min(believed_shot)
+    }
+
+    fn seconds_until_shot_by(&self, enemy: &Unit, position: Vec2) -> Option<f64> {
+        let weapon_idx = enemy.weapon?;
+        if enemy.ammo[weapon_idx as usize] <= 0 {
+            return None;
+        }
+        let weapon = &self.constants.weapons[weapon_idx as usize];
+        let distance = enemy.position.distance_to(&position);
+        let walk = (distance - weapon.range()).max(0.0) / self.constants.max_unit_forward_speed;
+        if walk > THREAT_HORIZON {
+            return None;
+        }
+
+        let reload = (enemy.next_shot_tick - self.current_tick).max(0) as f64 / self.constants.ticks_per_second;
+        let turn = Some(enemy.direction.angle_with(&(position - enemy.position)))
+            .filter(|angle| !angle.is_nan())
+            .map_or(0.0, |angle| angle.to_degrees() / self.constants.rotation_speed);
+        let aim = weapon.aim_time * (1.0 - enemy.aim);
+        let flank = if self.obstacle_index.along_segment(enemy.position, position, 0.0).iter().any(|o| !o.can_shoot_through) {
+            FLANK_SECONDS
+        } else {
+            0.0
+        };
+        let flight = distance.min(weapon.range()) / weapon.projectile_speed;
+
+        Some(reload.max(walk + turn.max(aim) + flank) + flight)
+    }
+
+    /// Seconds until one of the projectiles already flying hits the unit holding its velocity, infinity if none does.
+    pub fn seconds_until_projectile_hit(&self, unit: &Unit) -> f64 {
+        self.seen_projectiles.values()
+            .filter(|p| p.shooter_id != unit.id)
+            .filter(|p| self.constants.friendly_fire || p.shooter_player_id != unit.player_id)
+            .filter_map(|p| self.seconds_until_hit_by(p, unit))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn seconds_until_hit_by(&self, projectile: &Projectile, unit: &Unit) -> Option<f64> {
+        let flight = projectile.life_time.min(THREAT_HORIZON);
+        // seen from the unit the projectile flies with the velocities' difference
+        let relative_end = projectile.position + (projectile.velocity - unit.velocity) * flight;
+        let t = segment_circle_contact(projectile.position, relative_end, unit.position, self.constants.unit_radius)?;
+
+        let hit_point = projectile.position + projectile.velocity * (flight * t);
+        let blocked = self.obstacle_index.along_segment(projectile.position, hit_point, 0.0).iter()
+            .any(|o| !o.can_shoot_through);
+        (!blocked).then_some(flight * t)
+    }
+
+    /// Seconds until the unit is back to full health, if nobody hits it in the meantime.
+    pub fn seconds_until_regenerated(&self, unit: &Unit) -> f64 {
+        let delay = (unit.health_regeneration_start_tick - self.current_tick).max(0) as f64 / self.constants.ticks_per_second;
+        delay + (self.constants.unit_health - unit.health).max(0.0) / self.constants.health_regeneration_per_second
+    }
+
+    /// Closest cover point within reach hidden from every enemy who could shoot there soon, where the unit gets
+    /// and still has time to drink before anybody can shoot it, and the zone stays away long enough.
+    fn find_cover(&self, unit: &Unit) -> Option<Vec2> {
+        self.cover_map.near(unit.position, self.params.sustain.cover_search_radius).into_iter()
+            .filter(|cover| self.enemy_units.iter()
+                .filter(|enemy| enemy.remaining_spawn_time.is_none())
+                .filter(|enemy| self.seconds_until_shot_by(enemy, cover.position).is_some())
+                .all(|enemy| self.cover_map.shelters(cover, enemy.position)))
+            .map(|cover| cover.position)
+            .filter(|p| self.zone_forecast.time_until_outside(*p) > self.travel_time(unit, *p) + THREAT_HORIZON)
+            .find(|p| self.seconds_until_shot_at(*p) > self.travel_time(unit, *p) + self.constants.shield_potion_use_time)
+    }
+
+    pub(crate) fn velocity_retreat_to_recover(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let plan = self.sustain_plan.get(&unit.id).or_else(|| self.reject("spawning"))?;
+        match plan.decision {
+            SustainDecision::Retreat { cover } => Some(Vec2Order {
+                vec: cover - unit.position,
+                description: Some(format!("retreating to cover at {}", cover.to_short_string())),
+                target: Some(cover),
+                score: 1.0 - (unit.health + unit.shield) / self.constants.unit_health,
+            }),
+            SustainDecision::Regenerate => Some(Vec2Order {
+                vec: Vec2::zero(),
+                description: Some(format!("regenerating, safe for {:.1}s", plan.safe_for)),
+                target: None,
+                score: 1.0 - unit.health / self.constants.unit_health,
+            }),
+            SustainDecision::Drink => self.reject("safe enough to drink"),
+            SustainDecision::Fight => self.reject_with(|| format!("can be hit in {:.1}s, no cover nearby or not hurt", plan.safe_for)),
+        }
+    }
+
+    pub(crate) fn action_drink_shield(&self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<ActionOrderOrder> {
+        if unit.shield >= self.constants.max_shield {
+            return self.reject("shield is full");
+        }
+
+        if unit.shield_potions <= 0 {
+            return self.reject("no shield potions");
+        }
+
+        let plan = self.sustain_plan.get(&unit.id).or_else(|| self.reject("spawning"))?;
+        if !matches!(plan.decision, SustainDecision::Drink) {
+            return self.reject_with(|| format!("can be hit in {:.1}s, drinking takes {:.1}s", plan.safe_for, self.constants.shield_potion_use_time));
+        }
+
+        Some(ActionOrderOrder {
+            action_order: ActionOrder::UseShieldPotion {},
+            description: Some(format!("drinking shield, safe for {:.1}s", plan.safe_for)),
+            score: 1.0 - unit.shield / self.constants.max_shield,
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+use crate::debugging::{Color, ColoredVertex, DebugData};
+use crate::model::*;
+
+// pixels across a rendered frame
+const FRAME_SIZE: u32 = 800;
+// how much of the map around the zone is in the frame when not following a unit
+const VIEW_MARGIN: f64 = 5.0;
+
+/// Part of the world a frame shows.
+#[derive(Clone, Debug)]
+pub struct SvgView {
+    pub center: Vec2,
+    /// Half the side of the square shown, in world units
+    pub radius: f64,
+}
+
+impl SvgView {
+    /// The whole zone.
+    pub fn zone(game: &Game) -> Self {
+        Self { center: game.zone.current_center, radius: game.zone.current_radius + VIEW_MARGIN }
+    }
+
+    /// Whether a circle around the point may show up in the view, everything else is always drawn.
+    fn may_show(&self, data: &DebugData) -> bool {
+        let (position, radius) = match data {
+            DebugData::Circle { position, radius, .. }
+            | DebugData::GradientCircle { position, radius, .. }
+            | DebugData::Ring { position, radius, .. }
+            | DebugData::Pie { position, radius, .. }
+            | DebugData::Arc { position, radius, .. } => (position, *radius),
+            // texts run off to the side, a few world units is plenty
+            DebugData::PlacedText { position, size, .. } => (position, 30.0 * size),
+            _ => return true,
+        };
+        (position.x - self.center.x).abs() <= self.radius + radius && (position.y - self.center.y).abs() <= self.radius + radius
+    }
+}
+
+fn color(color: &Color) -> String {
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("rgb({},{},{})", channel(color.r), channel(color.g), channel(color.b))
+}
+
+fn fill(color: &Color) -> String {
+    format!("fill=\"{}\" fill-opacity=\"{:.3}\"", self::color(color), color.a)
+}
+
+fn stroke(color: &Color, width: f64) -> String {
+    format!("fill=\"none\" stroke=\"{}\" stroke-opacity=\"{:.3}\" stroke-width=\"{:.3}\"", self::color(color), color.a, width)
+}
+
+fn points(vertices: impl IntoIterator<Item = Vec2>) -> String {
+    vertices.into_iter().map(|v| format!("{:.3},{:.3}", v.x, v.y)).collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn next_gradient_id(counter: &mut usize) -> String {
+    *counter += 1;
+    format!("g{}", counter)
+}
+
+fn average(vertices: &[ColoredVertex]) -> Color {
+    let n = vertices.len().max(1) as f64;
+    let sum = |f: fn(&Color) -> f64| vertices.iter().map(|v| f(&v.color)).sum::<f64>() / n;
+    Color { r: sum(|c| c.r), g: sum(|c| c.g), b: sum(|c| c.b), a: sum(|c| c.a) }
+}
+
+/// Path of the arc from `start_angle` to `end_angle` counterclockwise, as `Pie` and `Arc` have it.
+/// Angles a full turn or more apart make a whole circle.
+fn arc_path(position: Vec2, radius: f64, start_angle: f64, end_angle: f64, closed: bool) -> String {
+    let turn = end_angle - start_angle;
+    let sweep = if turn.abs() >= 2.0 * PI - 1e-9 { 2.0 * PI } else { turn.rem_euclid(2.0 * PI) };
+    let start = position + Vec2::from_length_and_angle(radius, start_angle);
+    // drawn as two halves, a single SVG arc can't end where it starts
+    let arc = [start_angle + sweep / 2.0, start_angle + sweep].iter()
+        .map(|angle| {
+            let end = position + Vec2::from_length_and_angle(radius, *angle);
+            format!("A {r:.3} {r:.3} 0 0 1 {:.3} {:.3}", end.x, end.y, r = radius)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if closed {
+        format!("M {:.3} {:.3} L {:.3} {:.3} {} Z", position.x, position.y, start.x, start.y, arc)
+    } else {
+        format!("M {:.3} {:.3} {}", start.x, start.y, arc)
+    }
+}
+
+/// Draws each primitive, gradients get their definitions numbered from `next_gradient`.
+fn render_data(svg: &mut String, data: &DebugData, next_gradient: &mut usize) {
+    let stop = |offset: &str, color: &Color| format!("<stop offset=\"{}\" stop-color=\"{}\" stop-opacity=\"{:.3}\"/>", offset, self::color(color), color.a);
+    let _ = match data {
+        DebugData::PlacedText { position, text, alignment, size, color } => {
+            let anchor = if alignment.x < 0.25 { "start" } else if alignment.x > 0.75 { "end" } else { "middle" };
+            // text is drawn upright in the flipped world, its baseline moved so `alignment.y` of its height is at the position
+            writeln!(
+                svg,
+                "<text transform=\"translate({:.3},{:.3}) scale(1,-1)\" y=\"{:.3}\" font-size=\"{:.3}\" font-family=\"monospace\" text-anchor=\"{}\" {}>{}</text>",
+                position.x, position.y, alignment.y * size, size, anchor, fill(color), escape(text),
+            )
+        }
+        DebugData::Circle { position, radius, color } => {
+            writeln!(svg, "<circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"{:.3}\" {}/>", position.x, position.y, radius, fill(color))
+        }
+        DebugData::GradientCircle { position, radius, inner_color, outer_color } => {
+            let id = next_gradient_id(next_gradient);
+            writeln!(svg, "<defs><radialGradient id=\"{}\">{}{}</radialGradient></defs>", id, stop("0", inner_color), stop("1", outer_color))
+                .and_then(|_| writeln!(svg, "<circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"{:.3}\" fill=\"url(#{})\"/>", position.x, position.y, radius, id))
+        }
+        DebugData::Ring { position, radius, width, color } => {
+            writeln!(svg, "<circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"{:.3}\" {}/>", position.x, position.y, radius, stroke(color, *width))
+        }
+        DebugData::Pie { position, radius, start_angle, end_angle, color } => {
+            writeln!(svg, "<path d=\"{}\" {}/>", arc_path(*position, *radius, *start_angle, *end_angle, true), fill(color))
+        }
+        DebugData::Arc { position, radius, width, start_angle, end_angle, color } => {
+            writeln!(svg, "<path d=\"{}\" {}/>", arc_path(*position, *radius, *start_angle, *end_angle, false), stroke(color, *width))
+        }
+        DebugData::Rect { bottom_left, size, color } => {
+            writeln!(svg, "<rect x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" {}/>", bottom_left.x, bottom_left.y, size.x, size.y, fill(color))
+        }
+        DebugData::Polygon { vertices, color } => {
+            writeln!(svg, "<polygon points=\"{}\" {}/>", points(vertices.iter().copied()), fill(color))
+        }
+        DebugData::GradientPolygon { vertices } => {
+            // SVG has no per-vertex colors, the average one is close enough for debugging
+            writeln!(svg, "<polygon points=\"{}\" {}/>", points(vertices.iter().map(|v| v.position)), fill(&average(vertices)))
+        }
+        DebugData::Segment { first_end, second_end, width, color } => {
+            writeln!(svg, "<polyline points=\"{}\" {}/>", points([*first_end, *second_end]), stroke(color, *width))
+        }
+        DebugData::GradientSegment { first_end, first_color, second_end, second_color, width } => {
+            let id = next_gradient_id(next_gradient);
+            writeln!(
+                svg,
+                "<defs><linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\">{}{}</linearGradient></defs>",
+                id, first_end.x, first_end.y, second_end.x, second_end.y, stop("0", first_color), stop("1", second_color),
+            ).and_then(|_| writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"url(#{})\" stroke-width=\"{:.3}\"/>", points([*first_end, *second_end]), id, width))
+        }
+        DebugData::PolyLine { vertices, width, color } => {
+            writeln!(svg, "<polyline points=\"{}\" {}/>", points(vertices.iter().copied()), stroke(color, *width))
+        }
+        DebugData::GradientPolyLine { vertices, width } => {
+            for pair in vertices.windows(2) {
+                render_data(svg, &DebugData::GradientSegment {
+                    first_end: pair[0].position,
+                    first_color: pair[0].color.clone(),
+                    second_end: pair[1].position,
+                    second_color: pair[1].color.clone(),
+                    width: *width,
+                }, next_gradient);
+            }
+            Ok(())
+        }
+    };
+}
+
+/// One SVG document with every layer drawn over the previous ones, world y pointing up.
+pub fn render_svg(layers: &[&[DebugData]], view: &SvgView) -> String {
+    let mut svg = String::new();
+    let side = 2.0 * view.radius;
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\">",
+        view.center.x - view.radius, -view.center.y - view.radius, side, side, size = FRAME_SIZE,
+    );
+    let _ = writeln!(svg, "<rect x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" fill=\"rgb(24,24,24)\"/>", view.center.x - view.radius, -view.center.y - view.radius, side, side);
+    svg.push_str("<g transform=\"scale(1,-1)\">\n");
+    let mut next_gradient = 0;
+    for data in layers.iter().flat_map(|layer| layer.iter()).filter(|data| view.may_show(data)) {
+        render_data(&mut svg, data, &mut next_gradient);
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// What the official app draws on its own: the zone, obstacles, loot, units and projectiles of the player's view.
+pub fn scene(game: &Game, constants: &Constants) -> Vec<DebugData> {
+    let mut data = vec![DebugData::Circle {
+        position: game.zone.current_center,
+        radius: game.zone.current_radius,
+        color: Color { r: 0.2, g: 0.25, b: 0.2, a: 1.0 },
+    }, DebugData::Ring {
+        position: game.zone.next_center,
+        radius: game.zone.next_radius,
+        width: 0.3,
+        color: Color::blue().a(0.5),
+    }];
+    let radius = game.zone.current_radius + VIEW_MARGIN;
+    data.extend(constants.obstacles.iter()
+        .filter(|o| o.position.distance_to(&game.zone.current_center) < radius + o.radius)
+        .map(|o| DebugData::Circle {
+            position: o.position,
+            radius: o.radius,
+            color: if o.can_shoot_through { Color { r: 0.45, g: 0.4, b: 0.3, a: 1.0 } } else { Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 } },
+        }));
+    data.extend(game.loot.iter().map(|loot| DebugData::Circle {
+        position: loot.position,
+        radius: 0.4,
+        color: match loot.item {
+            Item::Weapon { .. } => Color { r: 1.0, g: 0.6, b: 0.0, a: 1.0 },
+            Item::ShieldPotions { .. } => Color { r: 0.3, g: 0.6, b: 1.0, a: 1.0 },
+            Item::Ammo { .. } => Color { r: 0.9, g: 0.9, b: 0.3, a: 1.0 },
+        },
+    }));
+    for unit in game.units.iter() {
+        let color = if unit.player_id == game.my_id { Color::green() } else { Color::red() };
+        let color = if unit.remaining_spawn_time.is_some() { color.a(0.4) } else { color };
+        data.push(DebugData::Circle { position: unit.position, radius: constants.unit_radius, color });
+        data.push(DebugData::Segment {
+            first_end: unit.position,
+            second_end: unit.position + unit.direction * constants.unit_radius * 1.5,
+            width: 0.15,
+            color: Color { r: 1.0, g: 1.0, b: 1.0, a: 0.8 },
+        });
+        data.push(DebugData::PlacedText {
+            position: unit.position,
+            text: unit.id.to_string(),
+            alignment: Vec2 { x: 0.5, y: 0.5 },
+            size: 0.7,
+            color: Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+        });
+    }
+    data.extend(game.projectiles.iter().map(|p| DebugData::Segment {
+        first_end: p.position - p.velocity.normalize() * 0.5,
+        second_end: p.position,
+        width: 0.15,
+        color: Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },
+    }));
+    data
+}
+
+/// Single HTML page stepping through the frames with a slider, arrow keys or play button.
+pub fn html_scrubber(frames: &[(i32, String)]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>royale frames</title>\n");
+    html.push_str("<style>body { background: #111; color: #ddd; font-family: monospace; } #controls { margin: 8px 0; }</style>\n");
+    html.push_str("</head>\n<body>\n<div id=\"controls\">\n");
+    let _ = writeln!(html, "<button id=\"play\">play</button> <input id=\"slider\" type=\"range\" min=\"0\" max=\"{}\" value=\"0\" style=\"width: 600px\"> tick <span id=\"tick\"></span>", frames.len().saturating_sub(1));
+    html.push_str("</div>\n<div id=\"frame\"></div>\n");
+    for (idx, (tick, svg)) in frames.iter().enumerate() {
+        let _ = writeln!(html, "<template id=\"f{}\" data-tick=\"{}\">{}</template>", idx, tick, svg);
+    }
+    html.push_str(r#"<script>
+const slider = document.getElementById("slider");
+const frame = document.getElementById("frame");
+const tick = document.getElementById("tick");
+const play = document.getElementById("play");
+let timer = null;
+function show(idx) {
+    const template = document.getElementById("f" + idx);
+    if (!template) return;
+    slider.value = idx;
+    frame.replaceChildren(template.content.cloneNode(true));
+    tick.textContent = template.dataset.tick;
+}
+slider.addEventListener("input", () => show(+slider.value));
+document.addEventListener("keydown", e => {
+    if (e.key === "ArrowRight") show(Math.min(+slider.value + 1, +slider.max));
+    if (e.key === "ArrowLeft") show(Math.max(+slider.value - 1, 0));
+});
+play.addEventListener("click", () => {
+    if (timer) { clearInterval(timer); timer = null; play.textContent = "play"; return; }
+    play.textContent = "pause";
+    timer = setInterval(() => show(+slider.value < +slider.max ? +slider.value + 1 : 0), 1000 / 30);
+});
+show(0);
+</script>
+</body>
+</html>
+"#);
+    html
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use crate::model::*;
+use crate::{DebugInterface, MyStrategy, Vec2Order};
+
+// allies further than this from the team center are called back
+pub(crate) const FORMATION_RADIUS: f64 = 12.0;
+// and allies closer than this to each other spread out, so one spread shot can't hit both
+const FORMATION_MIN_SPACING: f64 = 3.0;
+
+#[derive(Clone, Debug, Default)]
+pub struct TeamPlan {
+    /// Enemy each of our units should shoot at, by our unit id
+    pub targets: HashMap<i32, i32>,
+    /// Expected time for the assigned shooters to kill each targeted enemy, in seconds
+    pub time_to_kill: HashMap<i32, f64>,
+    pub formation_center: Option<Vec2>,
+}
+
+impl MyStrategy {
+    pub(crate) fn plan_team(&mut self) {
+        let mut plan = TeamPlan {
+            formation_center: self.formation_center(),
+            ..Default::default()
+        };
+
+        let shooters = self.my_units.iter()
+            .filter(|u| u.remaining_spawn_time.is_none())
+            .filter(|u| u.weapon.is_some_and(|w| u.ammo[w as usize] > 0))
+            .map(|unit| {
+                let options = self.enemy_units.iter()
+                    .filter(|enemy| enemy.is_within_fire_range_of(unit, &self.constants))
+                    .filter(|enemy| self.position_is_hittable_by(&HittableEntity::from(*enemy), unit, &mut None))
+                    .map(|enemy| (enemy.id, self.expected_damage_per_second(unit, enemy)))
+                    .collect_vec();
+                (unit.id, options)
+            })
+            .filter(|(_, options)| !options.is_empty())
+            // units with fewer options choose first, so they don't end up with nothing useful to do
+            .sorted_by_key(|(_, options)| options.len())
+            .collect_vec();
+
+        let mut assigned_dps: HashMap<i32, f64> = HashMap::new();
+        for (unit_id, options) in shooters {
+            // joining the fight that ends soonest is what turns into focus fire
+            let best = options.iter()
+                .map(|(enemy_id, dps)| {
+                    let dps = assigned_dps.get(enemy_id).unwrap_or(&0.0) + dps;
+                    (*enemy_id, dps, self.effective_health(*enemy_id) / dps)
+                })
+                .min_by(|(_, _, t1), (_, _, t2)| t1.total_cmp(t2));
+
+            if let Some((enemy_id, dps, time_to_kill)) = best {
+                assigned_dps.insert(enemy_id, dps);
+                plan.targets.insert(unit_id, enemy_id);
+                plan.time_to_kill.insert(enemy_id, time_to_kill);
+            }
+        }
+
+        self.targets = plan.targets.clone();
+        self.team_plan = plan;
+    }
+
+    pub fn effective_health(&self, unit_id: i32) -> f64 {
+        self.units_by_id.get(&unit_id).map_or(f64::INFINITY, |u| u.health + u.shield)
+    }
+
+    /// Weapon damage per second, scaled by the chance to hit the enemy.
+    pub fn expected_damage_per_second(&self, unit: &Unit, enemy: &Unit) -> f64 {
+        let Some(weapon) = unit.get_weapon(&self.constants) else { return 0.0 };
+        // the unit turns to its target anyway, so it's the chance once aimed that matters
+        let aimed = Unit { direction: self.fire_target(enemy, unit) - unit.position, aim: 1.0, ..unit.clone() };
+        let hit_chance = self.aim_at(enemy, &aimed).map_or(0.0, |shot| shot.hit_probability);
+        weapon.projectile_damage * weapon.rounds_per_second * hit_chance.max(0.01)
+    }
+
+    fn formation_center(&self) -> Option<Vec2> {
+        let spawned = self.my_units.iter().filter(|u| u.remaining_spawn_time.is_none()).collect_vec();
+        if spawned.len() < 2 {
+            return None;
+        }
+        let mut center = Vec2::zero();
+        spawned.iter().for_each(|u| center += u.position);
+        Some(center / spawned.len() as f64)
+    }
+
+    pub(crate) fn velocity_keep_formation(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let center = self.team_plan.formation_center.or_else(|| self.reject("no formation"))?;
+
+        let too_close = self.my_units.iter()
+            .filter(|ally| ally.id != unit.id)
+            .find(|ally| ally.position.distance_to(&unit.position) < FORMATION_MIN_SPACING);
+        if let Some(ally) = too_close {
+            let away = unit.position - ally.position;
+            let away = if away.length() > 0.0 { away } else { unit.direction };
+            return Some(Vec2Order {
+                vec: away.clamp_min(self.constants.max_unit_forward_speed),
+                description: Some(format!("spreading out from {}", ally.id)),
+                target: None,
+                score: 1.0 - ally.position.distance_to(&unit.position) / FORMATION_MIN_SPACING,
+            });
+        }
+
+        let distance_to_center = unit.position.distance_to(&center);
+        if distance_to_center > FORMATION_RADIUS {
+            return Some(Vec2Order {
+                vec: center - unit.position,
+                description: Some("keeping formation".to_string()),
+                target: Some(center),
+                score: (distance_to_center / FORMATION_RADIUS - 1.0).min(1.0),
+            });
+        }
+        self.reject("in formation")
+    }
+
+    /// Whether one of our units stands in the way of a shot and would take the hit.
+    pub fn shot_blocked_by_ally(&self, unit: &Unit, target: Vec2) -> bool {
+        self.constants.friendly_fire && self.my_units.iter()
+            .filter(|ally| ally.id != unit.id)
+            .any(|ally| ally.intersects_with(&unit.position, &target))
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use itertools::Itertools;
+
+// simulated ticks a search gets in reproducible games, enough for the default movement search to finish
+const REPRODUCIBLE_SEARCH_TICKS: usize = 2000;
+
+#[derive(Clone, Debug)]
+pub struct TimeBudgetConfig {
+    /// Time limit for the whole game
+    pub game_limit: Duration,
+    /// Ticks the game limit is spread over, the game may well end earlier
+    pub expected_ticks: i32,
+    /// No single tick gets more than this, whatever has been saved up
+    pub max_tick_time: Duration,
+    /// Part of the allowance actually handed out, the rest covers IO and whatever isn't measured
+    pub safety_factor: f64,
+    /// Print timings to stderr when the game finishes
+    pub report_on_finish: bool,
+    /// Searches stop after simulating this many ticks instead of by the clock, so a game plays out the same on every run
+    pub search_ticks: Option<usize>,
+}
+
+impl TimeBudgetConfig {
+    /// What a tick may take after `ticks` ticks that used `game_used`: an even share of what's left of the game
+    /// over the ticks still expected, capped at `max_tick_time`, times `safety_factor`.
+    pub fn tick_allowance(&self, ticks: i32, game_used: Duration) -> Duration {
+        let remaining_ticks = (self.expected_ticks - ticks).max(1) as u32;
+        let fair_share = self.game_limit.saturating_sub(game_used) / remaining_ticks;
+        fair_share.min(self.max_tick_time).mul_f64(self.safety_factor)
+    }
+
+    /// Budget that doesn't depend on how fast the machine is, for recorded, replayed and tuning games.
+    pub fn reproducible() -> Self {
+        Self {
+            search_ticks: Some(REPRODUCIBLE_SEARCH_TICKS),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for TimeBudgetConfig {
+    fn default() -> Self {
+        Self {
+            game_limit: Duration::from_secs(200),
+            expected_ticks: 10_000,
+            max_tick_time: Duration::from_millis(50),
+            safety_factor: 0.8,
+            report_on_finish: false,
+            search_ticks: None,
+        }
+    }
+}
+
+/// When a search has to stop.
+#[derive(Clone, Copy, Debug)]
+pub enum Deadline {
+    At(Instant),
+    SimulatedTicks(usize),
+}
+
+impl Deadline {
+    pub fn reached(&self, simulated_ticks: usize) -> bool {
+        match self {
+            Deadline::At(instant) => Instant::now() >= *instant,
+            Deadline::SimulatedTicks(limit) => simulated_ticks >= *limit,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Timing {
+    pub calls: usize,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// Tracks how much of the time limit is spent, hands out deadlines to searches and collects named timings.
+#[derive(Clone, Debug)]
+pub struct TimeBudget {
+    pub config: TimeBudgetConfig,
+    tick_started_at: Option<Instant>,
+    tick_allowance: Duration,
+    ticks: i32,
+    game_used: Duration,
+    max_tick_used: Duration,
+    overruns: usize,
+    /// Started with every unit, what's left of the tick is split between the units still to go
+    unit_started_at: Option<Instant>,
+    unit_allowance: Duration,
+    timings: HashMap<&'static str, Timing>,
+}
+
+impl TimeBudget {
+    pub fn new(config: TimeBudgetConfig) -> Self {
+        Self {
+            config,
+            tick_started_at: None,
+            tick_allowance: Duration::ZERO,
+            ticks: 0,
+            game_used: Duration::ZERO,
+            max_tick_used: Duration::ZERO,
+            overruns: 0,
+            unit_started_at: None,
+            unit_allowance: Duration::ZERO,
+            timings: HashMap::new(),
+        }
+    }
+
+    pub fn start_tick(&mut self) {
+        self.tick_allowance = self.config.tick_allowance(self.ticks, self.game_used);
+        self.tick_started_at = Some(Instant::now());
+        self.unit_started_at = None;
+    }
+
+    /// Hands the next unit its share of what's left of the tick, `units_left` counts the unit itself.
+    /// Whatever a unit doesn't use goes to the ones after it.
+    pub fn start_unit(&mut self, units_left: usize) {
+        self.unit_allowance = self.remaining_in_tick() / units_left.max(1) as u32;
+        self.unit_started_at = Some(Instant::now());
+    }
+
+    pub fn end_tick(&mut self) {
+        if let Some(started_at) = self.tick_started_at.take() {
+            let used = started_at.elapsed();
+            self.ticks += 1;
+            self.game_used += used;
+            self.max_tick_used = self.max_tick_used.max(used);
+            if used > self.tick_allowance {
+                self.overruns += 1;
+            }
+            self.record("tick", used);
+        }
+    }
+
+    /// Time left of this tick's allowance.
+    pub fn remaining_in_tick(&self) -> Duration {
+        self.tick_started_at.map_or(self.tick_allowance, |started_at| self.tick_allowance.saturating_sub(started_at.elapsed()))
+    }
+
+    /// Time left of the current unit's share, the whole tick's if no unit was started.
+    pub fn remaining_for_unit(&self) -> Duration {
+        match self.unit_started_at {
+            Some(started_at) => self.unit_allowance.saturating_sub(started_at.elapsed()).min(self.remaining_in_tick()),
+            None => self.remaining_in_tick(),
+        }
+    }
+
+    /// When a search wanting `wanted` has to stop, earlier if the unit's share can't afford it.
+    pub fn deadline(&self, wanted: Duration) -> Deadline {
+        match self.config.search_ticks {
+            Some(ticks) => Deadline::SimulatedTicks(ticks),
+            None => Deadline::At(Instant::now() + wanted.min(self.remaining_for_unit())),
+        }
+    }
+
+    pub fn record(&mut self, name: &'static str, elapsed: Duration) {
+        let timing = self.timings.entry(name).or_default();
+        timing.calls += 1;
+        timing.total += elapsed;
+        timing.max = timing.max.max(elapsed);
+    }
+
+    /// Timings table, the most expensive first.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "{} ticks, {:.3}s used of {:.3}s, slowest tick {:.2}ms, {} over allowance\n",
+            self.ticks,
+            self.game_used.as_secs_f64(),
+            self.config.game_limit.as_secs_f64(),
+            self.max_tick_used.as_secs_f64() * 1000.0,
+            self.overruns,
+        );
+        writeln!(report, "{:<32} {:>8} {:>10} {:>10} {:>10}", "name", "calls", "total ms", "avg us", "max us").unwrap();
+        for (name, timing) in self.timings.iter().sorted_by(|(_, t1), (_, t2)| t2.total.cmp(&t1.total)) {
+            writeln!(
+                report,
+                "{:<32} {:>8} {:>10.1} {:>10.1} {:>10.1}",
+                name,
+                timing.calls,
+                timing.total.as_secs_f64() * 1000.0,
+                timing.total.as_secs_f64() * 1e6 / timing.calls as f64,
+                timing.max.as_secs_f64() * 1e6,
+            ).unwrap();
+        }
+        report
+    }
+}
+
+impl Default for TimeBudget {
+    fn default() -> Self {
+        Self::new(TimeBudgetConfig::default())
+    }
+}
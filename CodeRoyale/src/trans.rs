@@ -1,20 +1,85 @@
+use std::fmt;
+use std::io::Read;
 use byteorder::{ReadBytesExt, WriteBytesExt};
 
+// most elements preallocated for a collection before any of them is read, so a corrupted length can't run out of memory
+const MAX_PREALLOCATED: usize = 1024;
+
+/// Why a value couldn't be decoded.
+#[derive(Debug)]
+pub enum TransError {
+    /// Enum tag that isn't one of the type's variants
+    UnexpectedTag { type_name: &'static str, tag: i32 },
+    /// Input ended in the middle of a value
+    Truncated,
+    InvalidUtf8(std::string::FromUtf8Error),
+    /// Bool byte other than 0 or 1
+    InvalidBool(u8),
+    /// Collection or string length below zero
+    InvalidLength(i32),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for TransError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedTag { type_name, tag } => write!(f, "unexpected tag {} for {}", tag, type_name),
+            Self::Truncated => write!(f, "input ended in the middle of a value"),
+            Self::InvalidUtf8(e) => write!(f, "invalid UTF-8 in a string: {}", e),
+            Self::InvalidBool(value) => write!(f, "bool value should be 0 or 1, got {}", value),
+            Self::InvalidLength(len) => write!(f, "negative length {}", len),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TransError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUtf8(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TransError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+/// Keeps the decoding error inside, truncation stays `UnexpectedEof`.
+impl From<TransError> for std::io::Error {
+    fn from(e: TransError) -> Self {
+        match e {
+            TransError::Io(e) => e,
+            TransError::Truncated => std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e),
+            _ => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
 pub trait Trans: Sized + 'static {
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()>;
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self>;
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError>;
+}
+
+fn read_len(reader: &mut dyn std::io::Read) -> Result<usize, TransError> {
+    let len = i32::read_from(reader)?;
+    usize::try_from(len).map_err(|_| TransError::InvalidLength(len))
 }
 
 impl Trans for bool {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
         let value = reader.read_u8()?;
         match value {
             0 => Ok(false),
             1 => Ok(true),
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Bool value should be 0 or 1",
-            )),
+            _ => Err(TransError::InvalidBool(value)),
         }
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
@@ -23,8 +88,8 @@ impl Trans for bool {
 }
 
 impl Trans for i32 {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        reader.read_i32::<byteorder::LittleEndian>()
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        Ok(reader.read_i32::<byteorder::LittleEndian>()?)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         writer.write_i32::<byteorder::LittleEndian>(*self)
@@ -32,8 +97,8 @@ impl Trans for i32 {
 }
 
 impl Trans for i64 {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        reader.read_i64::<byteorder::LittleEndian>()
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        Ok(reader.read_i64::<byteorder::LittleEndian>()?)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         writer.write_i64::<byteorder::LittleEndian>(*self)
@@ -41,8 +106,8 @@ impl Trans for i64 {
 }
 
 impl Trans for usize {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        Ok(i32::read_from(reader)? as usize)
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        read_len(reader)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         let i32_value = *self as i32;
@@ -51,8 +116,8 @@ impl Trans for usize {
 }
 
 impl Trans for f32 {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        reader.read_f32::<byteorder::LittleEndian>()
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        Ok(reader.read_f32::<byteorder::LittleEndian>()?)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         writer.write_f32::<byteorder::LittleEndian>(*self)
@@ -60,8 +125,8 @@ impl Trans for f32 {
 }
 
 impl Trans for f64 {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        reader.read_f64::<byteorder::LittleEndian>()
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        Ok(reader.read_f64::<byteorder::LittleEndian>()?)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         writer.write_f64::<byteorder::LittleEndian>(*self)
@@ -69,11 +134,14 @@ impl Trans for f64 {
 }
 
 impl Trans for String {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        let len = usize::read_from(reader)?;
-        let mut buf = vec![0; len];
-        reader.read_exact(&mut buf)?;
-        String::from_utf8(buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        let len = read_len(reader)?;
+        let mut buf = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+        reader.take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() < len {
+            return Err(TransError::Truncated);
+        }
+        String::from_utf8(buf).map_err(TransError::InvalidUtf8)
     }
     fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
         self.len().write_to(writer)?;
@@ -82,7 +150,7 @@ impl Trans for String {
 }
 
 impl<T: Trans> Trans for Option<T> {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
         let is_some = bool::read_from(reader)?;
         Ok(if is_some {
             Some(T::read_from(reader)?)
@@ -100,9 +168,9 @@ impl<T: Trans> Trans for Option<T> {
 }
 
 impl<T: Trans> Trans for Vec<T> {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        let len = usize::read_from(reader)?;
-        let mut result = Vec::with_capacity(len);
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        let len = read_len(reader)?;
+        let mut result = Vec::with_capacity(len.min(MAX_PREALLOCATED));
         for _ in 0..len {
             result.push(T::read_from(reader)?);
         }
@@ -118,9 +186,9 @@ impl<T: Trans> Trans for Vec<T> {
 }
 
 impl<K: Trans + Eq + std::hash::Hash, V: Trans> Trans for std::collections::HashMap<K, V> {
-    fn read_from(reader: &mut dyn std::io::Read) -> std::io::Result<Self> {
-        let len = usize::read_from(reader)?;
-        let mut result = Self::with_capacity(len);
+    fn read_from(reader: &mut dyn std::io::Read) -> Result<Self, TransError> {
+        let len = read_len(reader)?;
+        let mut result = Self::with_capacity(len.min(MAX_PREALLOCATED));
         for _ in 0..len {
             result.insert(K::read_from(reader)?, V::read_from(reader)?);
         }
@@ -0,0 +1,205 @@
+use std::fmt;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::{Arena, ArenaConfig, StrategyParams, PARAM_RANGES};
+
+// how fast SPSA steps and perturbations shrink over iterations, the usual exponents from Spall
+const STEP_DECAY: f64 = 0.602;
+const PERTURBATION_DECAY: f64 = 0.101;
+// normal quantile of 95% confidence intervals
+const Z_95: f64 = 1.96;
+
+#[derive(Clone, Debug)]
+pub struct TunerConfig {
+    /// Matches to play, player 0 gets the parameters being tried and everybody else the defaults
+    pub arena: ArenaConfig,
+    /// Parameters to tune by `section.name`, the rest stay as they are in `start`
+    pub params: Vec<String>,
+    pub start: StrategyParams,
+    pub iterations: usize,
+    /// Matches played for each side of a perturbation, with the same seeds for both
+    pub games_per_evaluation: usize,
+    /// Matches the tuned and the starting parameters are compared over at the end
+    pub final_games: usize,
+    /// First step size, as a part of each parameter's range
+    pub step: f64,
+    /// First perturbation size, as a part of each parameter's range
+    pub perturbation: f64,
+    /// Matches played at the same time
+    pub threads: usize,
+}
+
+impl Default for TunerConfig {
+    fn default() -> Self {
+        Self {
+            arena: ArenaConfig::default(),
+            params: PARAM_RANGES.iter().map(|(name, _, _)| name.to_string()).collect(),
+            start: StrategyParams::default(),
+            iterations: 20,
+            games_per_evaluation: 4,
+            final_games: 16,
+            step: 0.05,
+            perturbation: 0.1,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// Mean of a sample with its 95% confidence interval, by the normal approximation.
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    pub mean: f64,
+    pub half_width: f64,
+    pub samples: usize,
+}
+
+impl Estimate {
+    pub fn new(samples: &[f64]) -> Self {
+        let n = samples.len().max(1) as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+        Self { mean, half_width: Z_95 * (variance / n).sqrt(), samples: samples.len() }
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>8.1} ± {:<7.1} ({} games)", self.mean, self.half_width, self.samples)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IterationResult {
+    pub plus_score: f64,
+    pub minus_score: f64,
+    /// Parameters after the step, normalized to their ranges
+    pub position: Vec<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TuningReport {
+    pub best: StrategyParams,
+    pub baseline: Estimate,
+    pub tuned: Estimate,
+    /// Tuned minus starting score on the same seeds
+    pub improvement: Estimate,
+    pub iterations: Vec<IterationResult>,
+}
+
+impl fmt::Display for TuningReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "player 0 score after {} iterations, 95% intervals", self.iterations.len())?;
+        writeln!(f, "{:>12} {}", "starting", self.baseline)?;
+        writeln!(f, "{:>12} {}", "tuned", self.tuned)?;
+        writeln!(f, "{:>12} {}", "improvement", self.improvement)?;
+        writeln!(f)?;
+        write!(f, "{}", self.best)
+    }
+}
+
+/// Where SPSA goes from `position` after scoring `plus` at `position + perturbation * delta` and `minus`
+/// at `position - perturbation * delta`, with every coordinate kept in 0..=1.
+pub fn spsa_step(position: &[f64], delta: &[f64], plus: f64, minus: f64, step: f64, perturbation: f64) -> Vec<f64> {
+    // score differences are taken relative to the scores, so steps don't depend on how many points a game gives
+    let scale = ((plus.abs() + minus.abs()) / 2.0).max(1.0);
+    let gradient = (plus - minus) / scale / (2.0 * perturbation);
+    // a few noisy games can make for a huge gradient, no step goes further than the points just played
+    let shift = (step * gradient).clamp(-perturbation, perturbation);
+    position.iter().zip(delta.iter())
+        .map(|(x, d)| (x + shift * d).clamp(0.0, 1.0))
+        .collect()
+}
+
+/// SPSA over the parameters: every iteration plays a random ± perturbation of all of them at once
+/// and steps along the score difference. The perturbations come from the seed and the matches play by
+/// simulated tick budgets, so the same config tunes to the same parameters.
+pub struct Tuner {
+    config: TunerConfig,
+    ranges: Vec<(String, f64, f64)>,
+    rng: StdRng,
+}
+
+impl Tuner {
+    pub fn new(config: TunerConfig) -> Result<Self, String> {
+        let ranges = config.params.iter()
+            .map(|name| {
+                PARAM_RANGES.iter()
+                    .find(|(n, _, _)| n == name)
+                    .map(|(n, min, max)| (n.to_string(), *min, *max))
+                    .ok_or_else(|| format!("unknown parameter {:?}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let rng = StdRng::seed_from_u64(config.arena.seed);
+        Ok(Self { config, ranges, rng })
+    }
+
+    pub fn run(&mut self, mut on_iteration: impl FnMut(usize, &IterationResult)) -> TuningReport {
+        let mut position = self.ranges.iter()
+            .map(|(name, min, max)| ((self.config.start.get(name).unwrap() - min) / (max - min)).clamp(0.0, 1.0))
+            .collect::<Vec<_>>();
+        let games = self.config.games_per_evaluation as u64;
+        let mut iterations = vec![];
+
+        for k in 0..self.config.iterations {
+            let step = self.config.step / ((k + 1) as f64).powf(STEP_DECAY);
+            let perturbation = self.config.perturbation / ((k + 1) as f64).powf(PERTURBATION_DECAY);
+            let delta = (0..position.len()).map(|_| if self.rng.gen_bool(0.5) { 1.0 } else { -1.0 }).collect::<Vec<f64>>();
+            let shifted = |sign: f64| position.iter().zip(delta.iter())
+                .map(|(x, d)| (x + sign * perturbation * d).clamp(0.0, 1.0))
+                .collect::<Vec<_>>();
+
+            let seed = self.config.arena.seed + k as u64 * games;
+            let seeds = seed..seed + games;
+            let plus = Estimate::new(&self.play(&self.params_at(&shifted(1.0)), seeds.clone())).mean;
+            let minus = Estimate::new(&self.play(&self.params_at(&shifted(-1.0)), seeds)).mean;
+
+            position = spsa_step(&position, &delta, plus, minus, step, perturbation);
+
+            let result = IterationResult { plus_score: plus, minus_score: minus, position: position.clone() };
+            on_iteration(k, &result);
+            iterations.push(result);
+        }
+
+        let best = self.params_at(&position);
+        let seed = self.config.arena.seed + self.config.iterations as u64 * games;
+        let seeds = seed..seed + self.config.final_games as u64;
+        let baseline = self.play(&self.config.start, seeds.clone());
+        let tuned = self.play(&best, seeds);
+        let improvement = tuned.iter().zip(baseline.iter()).map(|(t, b)| t - b).collect::<Vec<_>>();
+        TuningReport {
+            best,
+            baseline: Estimate::new(&baseline),
+            tuned: Estimate::new(&tuned),
+            improvement: Estimate::new(&improvement),
+            iterations,
+        }
+    }
+
+    fn params_at(&self, position: &[f64]) -> StrategyParams {
+        let mut params = self.config.start.clone();
+        for ((name, min, max), x) in self.ranges.iter().zip(position.iter()) {
+            params.set(name, min + x * (max - min)).unwrap();
+        }
+        params
+    }
+
+    /// Player 0's score in a match for every seed, in the seeds' order.
+    pub fn play(&self, params: &StrategyParams, seeds: std::ops::Range<u64>) -> Vec<f64> {
+        let seeds = seeds.collect::<Vec<_>>();
+        let chunk = seeds.len().div_ceil(self.config.threads.max(1)).max(1);
+        std::thread::scope(|scope| {
+            let handles = seeds.chunks(chunk).map(|seeds| scope.spawn(move || {
+                seeds.iter().map(|seed| {
+                    let config = ArenaConfig {
+                        seed: *seed,
+                        challenger_params: Some(params.clone()),
+                        ..self.config.arena.clone()
+                    };
+                    let result = Arena::new(&config).run();
+                    result.teams.iter().find(|t| t.player_id == 0).map_or(0.0, |t| t.score)
+                }).collect::<Vec<_>>()
+            })).collect::<Vec<_>>();
+            handles.into_iter().flat_map(|h| h.join().expect("match panicked")).collect()
+        })
+    }
+}
@@ -1,50 +1,90 @@
-use ai_cup_22::debugging::Color;
-use crate::{DebugInterface, MyStrategy};
-use ai_cup_22::model::*;
+use crate::MyStrategy;
+use crate::model::*;
+use crate::segment_circle_contact;
 
+/// What a single unit can see: its view sector minus whatever is hidden behind opaque obstacles.
 pub struct VisibilitySector {
     pub position: Vec2,
     pub radius: f64,
-    pub start_angle: f64,
-    pub end_angle: f64,
+    pub direction: Vec2,
+    pub half_fov: f64,
+    pub unit_radius: f64,
+    /// Obstacles within view distance that block the sight (empty if `view_blocking` is off)
+    pub blocking_obstacles: Vec<Obstacle>,
 }
 
 impl VisibilitySector {
+    pub fn new(position: Vec2, direction: Vec2, aim: f64, weapon: Option<i32>, constants: &Constants) -> Self {
+        let field_of_view = constants.field_of_view;
+        let aim_field_of_view = weapon.map_or(field_of_view, |w| constants.weapons[w as usize].aim_field_of_view);
+        let fov = (field_of_view - (field_of_view - aim_field_of_view) * aim).to_radians();
+
+        let blocking_obstacles = if constants.view_blocking {
+            constants.obstacles.iter()
+                .filter(|o| !o.can_see_through)
+                .filter(|o| o.position.distance_to(&position) <= constants.view_distance + o.radius)
+                .cloned()
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Self {
+            position,
+            radius: constants.view_distance,
+            direction,
+            half_fov: fov / 2.0,
+            unit_radius: constants.unit_radius,
+            blocking_obstacles,
+        }
+    }
+
     pub fn cover_point(&self, point: Vec2) -> bool {
-        let unit_radius = 1.0;
         let distance_to_point = point - self.position;
-        let angle = distance_to_point.angle();
-
         let dist = distance_to_point.length();
-        let under_the_unit = dist <= unit_radius;
-        let in_the_sector = dist < self.radius &&
-            angle > self.start_angle &&
-            angle < self.start_angle;
+        if dist <= self.unit_radius {
+            return true;
+        }
+        if dist > self.radius || self.direction.angle_with(&distance_to_point) > self.half_fov {
+            return false;
+        }
 
-        under_the_unit || in_the_sector
+        !self.blocking_obstacles.iter()
+            .any(|o| segment_circle_contact(self.position, point, o.position, o.radius).is_some())
     }
-}
 
-impl MyStrategy {
-    pub fn unit_visibility_sector(&self, unit: &Unit) -> VisibilitySector {
-        let view_distance = self.constants.view_distance;
-        let field_of_view = self.constants.field_of_view;
-        let aim_field_of_view = if let Some(weapon_idx) = unit.weapon {
-            self.constants.weapons.get(weapon_idx as usize).unwrap().aim_field_of_view
-        } else {
-            0.0
-        };
+    /// Whether every point of the circle is in sight, approximated by its center and four edge points.
+    pub fn cover_circle(&self, center: Vec2, radius: f64) -> bool {
+        self.cover_point(center) && [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)]
+            .iter()
+            .all(|(x, y)| self.cover_point(center + Vec2::from_xy(*x, *y) * radius))
+    }
+}
 
-        let fov = (field_of_view - (field_of_view - aim_field_of_view) * unit.aim).to_radians();
-        let look_direction = unit.direction;
-        let start_angle = look_direction.rotate(-fov / 2.0).angle();
-        let end_angle = look_direction.rotate(fov / 2.0).angle();
+/// Everything the team observes at the moment, the union of sectors of all our units.
+#[derive(Default)]
+pub struct VisibilityMask {
+    pub sectors: Vec<VisibilitySector>,
+}
 
-        VisibilitySector {
-            position: unit.position,
-            radius: view_distance,
-            start_angle,
-            end_angle,
+impl VisibilityMask {
+    pub fn new(units: &[Unit], constants: &Constants) -> Self {
+        Self {
+            sectors: units.iter().map(|u| VisibilitySector::new(u.position, u.direction, u.aim, u.weapon, constants)).collect(),
         }
     }
-}
\ No newline at end of file
+
+    pub fn is_observed(&self, point: Vec2) -> bool {
+        self.sectors.iter().any(|sec| sec.cover_point(point))
+    }
+
+    pub fn is_circle_observed(&self, center: Vec2, radius: f64) -> bool {
+        self.sectors.iter().any(|sec| sec.cover_circle(center, radius))
+    }
+}
+
+impl MyStrategy {
+    pub fn unit_visibility_sector(&self, unit: &Unit) -> VisibilitySector {
+        VisibilitySector::new(unit.position, unit.direction, unit.aim, unit.weapon, &self.constants)
+    }
+}
@@ -1,6 +1,6 @@
 use itertools::Itertools;
-use ai_cup_22::debugging::*;
-use ai_cup_22::model::*;
+use crate::debugging::*;
+use crate::model::*;
 use crate::*;
 
 #[allow(unused_variables)]
@@ -13,6 +13,35 @@ impl MyStrategy {
         debug_interface.clear();
         debug_interface.set_auto_flush(false);
         let state = debug_interface.get_state();
+        self.handle_debug_keys(&state);
+        self.show_debug_legend(&state, debug_interface);
+
+        let layers = self.debug_console.layers.clone();
+        if layers.vision {
+            self.show_vision_ranges(debug_interface);
+        }
+        if layers.loot {
+            self.visualize_loot_plan(&mut Some(debug_interface));
+            self.visualize_spawn_plans(&mut Some(debug_interface));
+        }
+        if layers.rollouts {
+            self.visualize_rollouts(&mut Some(debug_interface));
+        }
+        if layers.beliefs {
+            self.visualize_enemy_beliefs(&mut Some(debug_interface));
+        }
+        if layers.cover {
+            self.visualize_cover(&mut Some(debug_interface));
+        }
+        if layers.trace {
+            // the locked unit, or else whichever of ours is under the cursor
+            let traced_unit = state.locked_unit
+                .and_then(|id| self.my_units.iter().find(|u| u.id == id))
+                .or_else(|| self.my_units.iter().find(|u| state.cursor_world_position.distance_to(&u.position) < self.constants.unit_radius));
+            if let Some(unit) = traced_unit {
+                self.show_decision_trace(unit, debug_interface);
+            }
+        }
 
         let unit_under_cursor = self
             .enemy_units
@@ -20,11 +49,10 @@ impl MyStrategy {
             .find(|u| state.cursor_world_position.distance_to(&u.position) < self.constants.unit_radius);
 
         if let Some(unit) = unit_under_cursor {
-            let my_units_that_see_this = self.my_units.iter().filter(|mu| self.position_is_hittable_by(&HittableEntity::from(unit), mu, &self.constants, &mut Some(debug_interface))).collect_vec();
+            let my_units_that_see_this = self.my_units.iter().filter(|mu| self.position_is_hittable_by(&HittableEntity::from(unit), mu, &mut Some(debug_interface))).collect_vec();
             // println!("enemy: {}, my units: {:?} / {}", unit.id, my_units_that_see_this.iter().map(|u| u.id).collect::<Vec<_>>(), self.my_units.len());
             for mu in my_units_that_see_this.iter() {
-                // debug_interface.add_segment(mu.position, (unit.position - mu.position).rotate(self.angle_for_leading_shot(unit, mu, &mut None)), 0.2, Color::green().a(0.5));
-                let fire_target = self.simple_projected_position(unit, mu);
+                let fire_target = self.fire_target(unit, mu);
                 debug_interface.add_ring(fire_target, self.constants.unit_radius, 0.1, Color::blue().a(0.4));
                 debug_interface.add_segment(mu.position, fire_target, 0.1, Color::red());
             }
@@ -59,6 +87,15 @@ impl MyStrategy {
         }
     }
 
+    pub(crate) fn visualize_enemy_beliefs(&self, debug_interface: &mut Option<&mut DebugInterface>) {
+        if let Some(debug) = debug_interface.as_mut() {
+            for belief in self.unseen_enemies(0.0) {
+                debug.add_ring(belief.position, belief.radius.max(self.constants.unit_radius), 0.1, Color::red().a(belief.confidence));
+                debug.add_placed_text(belief.position, format!("{} ({:.2})", belief.id, belief.confidence), Vec2 { x: 0.5, y: 0.5 }, 0.6, Color::red().a(0.8));
+            }
+        }
+    }
+
     pub(crate) fn visualize_projectiles(&self, game: &Game, debug_interface: &mut Option<&mut DebugInterface>) {
         if debug_interface.is_none() {
             return;
@@ -109,7 +146,8 @@ impl MyStrategy {
     pub fn show_vision_ranges(&self, debug_interface: &mut DebugInterface) {
         for my_unit in self.my_units.iter() {
             let sector = self.unit_visibility_sector(my_unit);
-            debug_interface.add_pie(sector.position, sector.radius, sector.start_angle, sector.end_angle, Color::green().a(0.3));
+            let angle = sector.direction.angle();
+            debug_interface.add_pie(sector.position, sector.radius, angle - sector.half_fov, angle + sector.half_fov, Color::green().a(0.3));
         }
     }
 
@@ -121,7 +159,7 @@ impl MyStrategy {
         }
     }
 
-    pub fn show_status_labels_for_units(&mut self, mut debug_interface: &mut Option<&mut DebugInterface>) {
+    pub fn show_status_labels_for_units(&mut self, debug_interface: &mut Option<&mut DebugInterface>) {
         if let Some(debug) = debug_interface.as_mut() {
             for unit in self.my_units.iter() {
                 let text = match unit.action {
@@ -130,7 +168,7 @@ impl MyStrategy {
                     _ => None,
                 };
                 if let Some(t) = text {
-                    self.place_label(unit.position, t.to_string(), 3, &mut debug_interface);
+                    self.place_label(unit.position, t.to_string(), 3, debug_interface);
                 }
             }
         }
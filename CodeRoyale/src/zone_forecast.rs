@@ -0,0 +1,147 @@
+use std::f64::consts::PI;
+use crate::model::*;
+use crate::{DebugInterface, MyStrategy, Vec2Order};
+
+// arrive at the next circle with this many seconds to spare
+const ROTATION_SAFETY_MARGIN: f64 = 3.0;
+// straight line distances underestimate paths around obstacles
+const DETOUR_FACTOR: f64 = 1.2;
+const EDGE_CANDIDATES: usize = 24;
+
+/// How the zone moves until it reaches the next circle; what happens after that is random.
+#[derive(Clone, Debug)]
+pub struct ZoneForecast {
+    pub zone: Zone,
+    /// Radius shrink per second
+    pub speed: f64,
+}
+
+impl ZoneForecast {
+    pub fn new(zone: &Zone, constants: &Constants) -> Self {
+        Self {
+            zone: zone.clone(),
+            speed: constants.zone_speed,
+        }
+    }
+
+    /// Seconds until the zone becomes the next circle.
+    pub fn shrink_time(&self) -> f64 {
+        (self.zone.current_radius - self.zone.next_radius).max(0.0) / self.speed
+    }
+
+    /// Center and radius of the zone in `seconds` from now, the next circle stays put once reached.
+    pub fn at(&self, seconds: f64) -> (Vec2, f64) {
+        let shrink_time = self.shrink_time();
+        if seconds >= shrink_time || shrink_time == 0.0 {
+            return (self.zone.next_center, self.zone.next_radius);
+        }
+        let s = seconds / shrink_time;
+        let center = self.zone.current_center + (self.zone.next_center - self.zone.current_center) * s;
+        let radius = self.zone.current_radius - (self.zone.current_radius - self.zone.next_radius) * s;
+        (center, radius)
+    }
+
+    /// Seconds until the point is outside the zone, 0 if it already is, infinity if it's inside the next circle.
+    pub fn time_until_outside(&self, point: Vec2) -> f64 {
+        let zone = &self.zone;
+        if point.distance_to(&zone.current_center) > zone.current_radius {
+            return 0.0;
+        }
+        if point.distance_to(&zone.next_center) <= zone.next_radius {
+            return f64::INFINITY;
+        }
+
+        // |q - d s| = R - (R - r) s, for s in [0, 1] being the fraction of the shrink done
+        let q = point - zone.current_center;
+        let d = zone.next_center - zone.current_center;
+        let shrink = zone.current_radius - zone.next_radius;
+        let a = d.dot_product(&d) - shrink * shrink;
+        let b = -2.0 * q.dot_product(&d) + 2.0 * zone.current_radius * shrink;
+        let c = q.dot_product(&q) - zone.current_radius * zone.current_radius;
+
+        let s = if a.abs() < 1e-9 {
+            -c / b
+        } else {
+            let discriminant = (b * b - 4.0 * a * c).max(0.0);
+            let roots = [(-b - discriminant.sqrt()) / (2.0 * a), (-b + discriminant.sqrt()) / (2.0 * a)];
+            roots.into_iter().filter(|s| *s >= 0.0).fold(f64::INFINITY, f64::min)
+        };
+        s.clamp(0.0, 1.0) * self.shrink_time()
+    }
+}
+
+impl MyStrategy {
+    pub(crate) fn update_zone_forecast(&mut self, game: &Game) {
+        self.zone_forecast = ZoneForecast::new(&game.zone, &self.constants);
+
+        let forecast = &self.zone_forecast;
+        self.rotation_targets.retain(|_, target| forecast.time_until_outside(*target).is_infinite());
+    }
+
+    /// Seconds it takes the unit to get to the point, roughly.
+    pub fn travel_time(&self, unit: &Unit, point: Vec2) -> f64 {
+        unit.position.distance_to(&point) * DETOUR_FACTOR / self.constants.max_unit_forward_speed
+    }
+
+    /// Seconds to spare if the unit heads for the next circle right now.
+    pub fn zone_slack(&self, unit: &Unit) -> f64 {
+        let forecast = &self.zone_forecast;
+        let time_left = forecast.time_until_outside(unit.position);
+        if time_left.is_infinite() {
+            return f64::INFINITY;
+        }
+        let zone = &forecast.zone;
+        let distance_to_next = (unit.position.distance_to(&zone.next_center) - zone.next_radius).max(0.0);
+        time_left - distance_to_next * DETOUR_FACTOR / self.constants.max_unit_forward_speed
+    }
+
+    pub(crate) fn velocity_rotate_to_next_zone(&mut self, unit: &Unit, _game: &Game, _debug_interface: &mut Option<&mut DebugInterface>) -> Option<Vec2Order> {
+        let slack = self.zone_slack(unit);
+        if slack > ROTATION_SAFETY_MARGIN && !self.rotation_targets.contains_key(&unit.id) {
+            return self.reject_with(|| format!("{:.1}s to spare before the zone", slack));
+        }
+
+        let target = match self.rotation_targets.get(&unit.id) {
+            Some(target) => *target,
+            None => {
+                let target = self.pick_rotation_target(unit).or_else(|| self.reject("no free spot in the next zone"))?;
+                self.rotation_targets.insert(unit.id, target);
+                target
+            }
+        };
+        if unit.position.distance_to(&target) < self.constants.unit_radius {
+            self.rotation_targets.remove(&unit.id);
+            return self.reject("reached the rotation target");
+        }
+
+        Some(Vec2Order {
+            vec: target - unit.position,
+            description: Some(format!("rotating to {}, {:.1}s to spare", target.to_short_string(), slack)),
+            target: Some(target),
+            score: (1.0 - slack / ROTATION_SAFETY_MARGIN).clamp(0.0, 1.0) * 2.0 + 0.5,
+        })
+    }
+
+    /// Point just inside the edge of the next circle, close to the unit and with cover nearby.
+    fn pick_rotation_target(&self, unit: &Unit) -> Option<Vec2> {
+        let zone = &self.zone_forecast.zone;
+        let radius = zone.next_radius * 0.8;
+        let unit_radius = self.constants.unit_radius;
+
+        (0..EDGE_CANDIDATES)
+            .map(|i| zone.next_center + Vec2::from_length_and_angle(radius, 2.0 * PI * i as f64 / EDGE_CANDIDATES as f64))
+            .chain(std::iter::once(zone.next_center))
+            .filter(|p| self.obstacle_index.near(*p, unit_radius).is_empty())
+            .min_by(|p1, p2| {
+                let cost = |p: &Vec2| self.travel_time(unit, *p) - self.cover_around(*p) as f64;
+                cost(p1).total_cmp(&cost(p2))
+            })
+    }
+
+    /// Number of obstacles that can't be shot through right next to the point.
+    pub fn cover_around(&self, point: Vec2) -> usize {
+        self.obstacle_index.near(point, self.constants.unit_radius + 1.0).iter()
+            .filter(|o| !o.can_shoot_through)
+            .count()
+    }
+}
@@ -0,0 +1,96 @@
+//! What the strategy tests build their games from.
+#![allow(dead_code)]
+
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+
+pub const ME: i32 = 0;
+pub const ENEMY: i32 = 1;
+
+/// A generated arena's constants with the obstacles replaced, so tests control what's in the way.
+pub fn constants(obstacles: Vec<Obstacle>) -> Constants {
+    let mut constants = Arena::new(&ArenaConfig::default()).constants;
+    constants.obstacles = obstacles;
+    constants
+}
+
+pub fn obstacle(id: i32, position: Vec2, radius: f64) -> Obstacle {
+    Obstacle { id, position, radius, can_see_through: false, can_shoot_through: false }
+}
+
+/// Spawned unit at full health looking along x, holding `weapon` with some ammo for it.
+pub fn unit(id: i32, player_id: i32, position: Vec2, weapon: Option<i32>, constants: &Constants) -> Unit {
+    let mut ammo = vec![0; constants.weapons.len()];
+    if let Some(w) = weapon {
+        ammo[w as usize] = 10;
+    }
+    Unit {
+        id,
+        player_id,
+        health: constants.unit_health,
+        shield: 0.0,
+        extra_lives: 0,
+        position,
+        remaining_spawn_time: None,
+        velocity: Vec2::zero(),
+        direction: Vec2::from_xy(1.0, 0.0),
+        aim: 0.0,
+        action: None,
+        health_regeneration_start_tick: 0,
+        weapon,
+        next_shot_tick: 0,
+        ammo,
+        shield_potions: 0,
+        seen_on_tick: 0,
+    }
+}
+
+pub fn projectile(id: i32, shooter: &Unit, position: Vec2, velocity: Vec2, life_time: f64) -> Projectile {
+    Projectile {
+        id,
+        weapon_type_index: shooter.weapon.unwrap_or(0),
+        shooter_id: shooter.id,
+        shooter_player_id: shooter.player_id,
+        position,
+        velocity,
+        life_time,
+        seen_on_tick: 0,
+    }
+}
+
+/// What player `ME` sees, in a zone big enough to stay out of the way.
+pub fn game(units: Vec<Unit>, projectiles: Vec<Projectile>) -> Game {
+    Game {
+        my_id: ME,
+        players: [ME, ENEMY].into_iter()
+            .map(|id| Player { id, kills: 0, damage: 0.0, place: 1, score: 0.0 })
+            .collect(),
+        current_tick: 0,
+        units,
+        loot: vec![],
+        projectiles,
+        zone: Zone {
+            current_center: Vec2::zero(),
+            current_radius: 100.0,
+            next_center: Vec2::zero(),
+            next_radius: 50.0,
+        },
+        sounds: vec![],
+    }
+}
+
+/// Strategy that has seen the game, its plans are made for it.
+pub fn strategy_after(constants: &Constants, game: &Game) -> MyStrategy {
+    let mut strategy = MyStrategy::new(constants.clone());
+    strategy.get_order(game, None);
+    strategy
+}
+
+/// Plays an arena game into a replay file in the temp dir, named after the test.
+pub fn record(name: &str, config: &ArenaConfig) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("royale-{}-{}.replay", name, std::process::id()));
+    Arena::new(config)
+        .with_recorder(ReplayWriter::create(&path).unwrap())
+        .run();
+    path
+}
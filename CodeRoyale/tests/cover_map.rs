@@ -0,0 +1,58 @@
+mod common;
+
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+fn cover_map(obstacles: Vec<Obstacle>) -> CoverMap {
+    let constants = constants(obstacles);
+    CoverMap::new(&constants, &ObstacleGrid::new(&constants))
+}
+
+/// The cover point straight across the obstacle from `threat`.
+fn cover_opposite(map: &CoverMap, obstacle: &Obstacle, threat: Vec2) -> CoverPoint {
+    let away = (obstacle.position - threat).normalize();
+    map.points().iter()
+        .max_by(|c1, c2| (c1.position - obstacle.position).dot_product(&away).total_cmp(&(c2.position - obstacle.position).dot_product(&away)))
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn cover_points_face_their_obstacle() {
+    let wall = obstacle(0, Vec2::from_xy(10.0, 0.0), 2.0);
+    let see_through = Obstacle { can_shoot_through: true, ..obstacle(1, Vec2::from_xy(-10.0, 0.0), 2.0) };
+    let map = cover_map(vec![wall.clone(), see_through]);
+    assert!(map.points().len() >= 4);
+    for cover in map.points() {
+        assert_eq!(cover.obstacle, 0);
+        let to_obstacle = (wall.position - cover.position).normalize();
+        assert!((cover.facing.x - to_obstacle.x).abs() < 1e-9 && (cover.facing.y - to_obstacle.y).abs() < 1e-9, "{:?}", cover);
+    }
+}
+
+#[test]
+fn shelters_only_from_threats_the_obstacle_is_in_front_of() {
+    let wall = obstacle(0, Vec2::zero(), 2.0);
+    let map = cover_map(vec![wall.clone()]);
+    let cover = cover_opposite(&map, &wall, Vec2::from_xy(-30.0, 0.0));
+
+    assert!(map.shelters(&cover, Vec2::from_xy(-30.0, 0.0)));
+    // behind the unit, the obstacle is on the line past the threat but doesn't stand in between
+    assert!(!map.shelters(&cover, cover.position * 3.0));
+    // off to the side the shot passes by the obstacle
+    assert!(!map.shelters(&cover, cover.position + Vec2::from_xy(0.0, 30.0)));
+}
+
+#[test]
+fn wide_obstacles_shelter_from_a_wider_angle() {
+    let threat_angle = 0.15_f64;
+    let threat = Vec2::from_length_and_angle(30.0, std::f64::consts::PI + threat_angle);
+    let narrow = obstacle(0, Vec2::zero(), 1.0);
+    let wide = obstacle(0, Vec2::zero(), 4.0);
+    let narrow_map = cover_map(vec![narrow.clone()]);
+    let wide_map = cover_map(vec![wide.clone()]);
+    let straight = Vec2::from_xy(-30.0, 0.0);
+    assert!(!narrow_map.shelters(&cover_opposite(&narrow_map, &narrow, straight), threat));
+    assert!(wide_map.shelters(&cover_opposite(&wide_map, &wide, straight), threat));
+}
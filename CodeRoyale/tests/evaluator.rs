@@ -0,0 +1,61 @@
+mod common;
+
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use ndarray::{Array1, Array2};
+use ndarray_npy::write_npy;
+use common::*;
+
+fn inputs(constants: &Constants) -> usize {
+    unit_feature_count(constants) + EXPORTED_ENEMIES * enemy_feature_count(constants) + EXPORTED_PROJECTILES * PROJECTILE_FEATURES
+}
+
+fn error(layers: Vec<(Array2<f32>, Array1<f32>)>, constants: &Constants) -> String {
+    MlpEvaluator::new(layers, constants).unwrap_err().to_string()
+}
+
+#[test]
+fn layers_have_to_chain_into_one_output() {
+    let constants = constants(vec![]);
+    let n = inputs(&constants);
+    assert!(MlpEvaluator::new(vec![(Array2::zeros((n, 1)), Array1::zeros(1))], &constants).is_ok());
+    assert!(MlpEvaluator::new(vec![(Array2::zeros((n, 4)), Array1::zeros(4)), (Array2::zeros((4, 1)), Array1::zeros(1))], &constants).is_ok());
+
+    assert_eq!(error(vec![], &constants), "model has no layers");
+    assert!(error(vec![(Array2::zeros((n - 1, 1)), Array1::zeros(1))], &constants).starts_with("layer 0"));
+    assert!(error(vec![(Array2::zeros((n, 1)), Array1::zeros(2))], &constants).starts_with("layer 0"));
+    assert!(error(vec![(Array2::zeros((n, 4)), Array1::zeros(4)), (Array2::zeros((3, 1)), Array1::zeros(1))], &constants).starts_with("layer 1"));
+    assert_eq!(error(vec![(Array2::zeros((n, 2)), Array1::zeros(2))], &constants), "model has 2 outputs, expected 1");
+}
+
+/// Two layers reading the health feature, ReLU(h) + ReLU(-h) + 0.5 gives back h + 0.5.
+fn write_health_model(dir: &std::path::Path, constants: &Constants) {
+    let mut w0 = Array2::<f32>::zeros((inputs(constants), 2));
+    w0[[0, 0]] = 1.0;
+    w0[[0, 1]] = -1.0;
+    std::fs::create_dir_all(dir).unwrap();
+    write_npy(dir.join("w0.npy"), &w0).unwrap();
+    write_npy(dir.join("b0.npy"), &Array1::<f32>::zeros(2)).unwrap();
+    write_npy(dir.join("w1.npy"), &Array2::<f32>::ones((2, 1))).unwrap();
+    write_npy(dir.join("b1.npy"), &Array1::from(vec![0.5f32])).unwrap();
+}
+
+#[test]
+fn loaded_model_values_the_features() {
+    let constants = constants(vec![]);
+    let dir = std::env::temp_dir().join(format!("royale-model-{}", std::process::id()));
+    write_health_model(&dir, &constants);
+    let evaluator = MlpEvaluator::load(&dir, &constants).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let me = Unit { health: 40.0, ..unit(1, ME, Vec2::zero(), Some(0), &constants) };
+    let game = game(vec![me.clone()], vec![]);
+    let value = evaluator.value(&UnitFeatures::new(&me, &game, &constants));
+    assert!((value - 0.9).abs() < 1e-6, "{}", value);
+
+    // a rollout is scored on the simulated unit, the higher the value the lower the cost
+    let obstacles = ObstacleGrid::new(&constants);
+    let order = UnitOrder { target_velocity: Vec2::zero(), target_direction: Vec2::from_xy(1.0, 0.0), action: None };
+    let simulator = Simulator::new(&game, &constants, &obstacles, me.id, order);
+    assert!((evaluator.cost(&simulator) + value).abs() < 1e-6);
+}
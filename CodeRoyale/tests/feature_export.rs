@@ -0,0 +1,111 @@
+mod common;
+
+use ai_cup_22::codegame::{ClientMessage, ServerMessage};
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use ndarray::{Array2, Array3};
+use ndarray_npy::read_npy;
+use common::*;
+
+/// Constants, then every player view with the order sent for it.
+fn recorded_orders(path: &std::path::Path) -> (Constants, Vec<(Game, Order)>) {
+    let mut constants = None;
+    let mut view = None;
+    let mut orders = vec![];
+    for record in ReplayReader::open(path).unwrap() {
+        match record.unwrap() {
+            ReplayRecord::Server(message) => match *message {
+                ServerMessage::UpdateConstants { constants: c } => constants = Some(c),
+                ServerMessage::GetOrder { player_view, .. } => view = Some(player_view),
+                _ => {}
+            },
+            ReplayRecord::Client(ClientMessage::OrderMessage { order }) => orders.push((view.take().unwrap(), order)),
+            _ => {}
+        }
+    }
+    (constants.unwrap(), orders)
+}
+
+#[test]
+fn features_have_the_exported_shapes() {
+    let constants = constants(vec![]);
+    let unit_count = unit_feature_count(&constants);
+    let enemy_count = enemy_feature_count(&constants);
+    let me = unit(1, ME, Vec2::zero(), Some(0), &constants);
+    let enemy = unit(2, ENEMY, Vec2::from_xy(10.0, 0.0), Some(2), &constants);
+    let shot = projectile(10, &enemy, Vec2::from_xy(8.0, 0.0), Vec2::from_xy(-40.0, 0.0), 1.0);
+    let game = game(vec![me.clone(), enemy], vec![shot]);
+
+    let features = UnitFeatures::new(&me, &game, &constants);
+    assert_eq!(features.unit.len(), unit_count);
+    assert_eq!(features.enemies.len(), EXPORTED_ENEMIES * enemy_count);
+    assert_eq!(features.projectiles.len(), EXPORTED_PROJECTILES * PROJECTILE_FEATURES);
+    assert_eq!(features.flat().len(), unit_count + features.enemies.len() + features.projectiles.len());
+    // the one enemy and projectile come first, the rest are absent
+    assert_eq!(features.enemies[0], 1.0);
+    assert!(features.enemies[enemy_count..].iter().all(|f| *f == 0.0));
+    assert_eq!(&features.projectiles[..3], &[1.0, 8.0, 0.0]);
+    assert!(features.projectiles[PROJECTILE_FEATURES..].iter().all(|f| *f == 0.0));
+
+    let order = UnitOrder {
+        target_velocity: Vec2::from_xy(1.0, 2.0),
+        target_direction: Vec2::from_xy(3.0, 4.0),
+        action: Some(ActionOrder::Aim { shoot: true }),
+    };
+    let order = order_features(&order);
+    assert_eq!(order.len(), ORDER_FEATURES);
+    assert_eq!(&order[..4], &[1.0, 2.0, 3.0, 4.0]);
+    // the aim kind and the shoot flag, nothing else
+    assert_eq!(order[4..].iter().sum::<f32>(), 2.0);
+    assert_eq!(order[ORDER_FEATURES - 1], 1.0);
+}
+
+#[test]
+fn exported_tensors_read_back_as_recorded() {
+    let config = ArenaConfig {
+        seed: 7,
+        max_ticks: 60,
+        // units are out right away, nothing to export while they're spawning
+        preset: Preset::Round1,
+        ..ArenaConfig::default()
+    };
+    let path = record("features", &config);
+    let exporter = FeatureExporter::from_replay(&path).unwrap();
+    let (constants, orders) = recorded_orders(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("royale-features-{}", std::process::id()));
+    exporter.write(&dir).unwrap();
+    let index: Array2<i32> = read_npy(dir.join("index.npy")).unwrap();
+    let units: Array2<f32> = read_npy(dir.join("units.npy")).unwrap();
+    let enemies: Array3<f32> = read_npy(dir.join("enemies.npy")).unwrap();
+    let projectiles: Array3<f32> = read_npy(dir.join("projectiles.npy")).unwrap();
+    let exported_orders: Array2<f32> = read_npy(dir.join("orders.npy")).unwrap();
+    let outcomes: Array2<f32> = read_npy(dir.join("outcomes.npy")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let rows = exporter.rows();
+    assert!(rows > 0);
+    assert_eq!(index.dim(), (rows, 2));
+    assert_eq!(units.dim(), (rows, unit_feature_count(&constants)));
+    assert_eq!(enemies.dim(), (rows, EXPORTED_ENEMIES, enemy_feature_count(&constants)));
+    assert_eq!(projectiles.dim(), (rows, EXPORTED_PROJECTILES, PROJECTILE_FEATURES));
+    assert_eq!(exported_orders.dim(), (rows, ORDER_FEATURES));
+    assert_eq!(outcomes.dim(), (rows, OUTCOME_FEATURES));
+
+    // every row is the recorded view and order of the unit and tick in the index
+    for (row, index) in index.rows().into_iter().enumerate() {
+        let (game, order) = orders.iter().find(|(game, _)| game.current_tick == index[0]).unwrap();
+        let unit = game.units.iter().find(|u| u.id == index[1]).unwrap();
+        let features = UnitFeatures::new(unit, game, &constants);
+        assert_eq!(units.row(row).to_vec(), features.unit);
+        assert_eq!(enemies.index_axis(ndarray::Axis(0), row).iter().copied().collect::<Vec<_>>(), features.enemies);
+        assert_eq!(projectiles.index_axis(ndarray::Axis(0), row).iter().copied().collect::<Vec<_>>(), features.projectiles);
+        assert_eq!(exported_orders.row(row).to_vec(), order_features(&order.unit_orders[&unit.id]).to_vec());
+    }
+
+    let (last_view, _) = orders.last().unwrap();
+    let outcome = outcome_features(last_view);
+    assert!((0.0..=1.0).contains(&outcome[0]));
+    assert!(outcomes.rows().into_iter().all(|row| row.to_vec() == outcome));
+}
@@ -0,0 +1,187 @@
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use proptest::prelude::*;
+
+// samples along a segment or a time interval when checking against brute force
+const SAMPLES: usize = 400;
+const EPS: f64 = 1e-6;
+
+fn point() -> impl Strategy<Value = Vec2> {
+    (-50.0..50.0, -50.0..50.0).prop_map(|(x, y)| Vec2::from_xy(x, y))
+}
+
+fn velocity() -> impl Strategy<Value = Vec2> {
+    (-20.0..20.0, -20.0..20.0).prop_map(|(x, y)| Vec2::from_xy(x, y))
+}
+
+fn radius() -> impl Strategy<Value = f64> {
+    0.1..10.0
+}
+
+fn obstacles() -> impl Strategy<Value = Vec<Obstacle>> {
+    prop::collection::vec((point(), radius()), 0..8).prop_map(|circles| {
+        circles.into_iter().enumerate().map(|(id, (position, radius))| Obstacle {
+            id: id as i32,
+            position,
+            radius,
+            can_see_through: false,
+            can_shoot_through: false,
+        }).collect()
+    })
+}
+
+fn lerp(start: Vec2, end: Vec2, t: f64) -> Vec2 {
+    start + (end - start) * t
+}
+
+/// Smallest distance from the center to evenly spread points of the segment.
+fn sampled_distance(start: Vec2, end: Vec2, center: Vec2) -> f64 {
+    (0..=SAMPLES)
+        .map(|i| lerp(start, end, i as f64 / SAMPLES as f64).distance_to(&center))
+        .fold(f64::INFINITY, f64::min)
+}
+
+proptest! {
+    #[test]
+    fn closest_point_is_closer_than_any_sample(start in point(), end in point(), p in point()) {
+        let closest = closest_point_on_segment(start, end, p);
+        prop_assert!(closest.distance_to(&p) <= sampled_distance(start, end, p) + EPS);
+        // and it lies on the segment
+        let length = start.distance_to(&end);
+        prop_assert!((start.distance_to(&closest) + closest.distance_to(&end) - length).abs() < 1e-6 * (1.0 + length));
+    }
+
+    #[test]
+    fn segment_intersection_matches_sampling(start in point(), end in point(), center in point(), r in radius()) {
+        let step = start.distance_to(&end) / SAMPLES as f64;
+        let sampled = sampled_distance(start, end, center);
+        let intersects = segment_intersects_circle(start, end, center, r);
+        if sampled <= r - EPS {
+            prop_assert!(intersects);
+        }
+        if intersects {
+            prop_assert!(sampled <= r + step + EPS);
+        }
+    }
+
+    #[test]
+    fn segment_intersection_ignores_direction_and_translation(start in point(), end in point(), center in point(), r in radius(), shift in point()) {
+        let forward = segment_intersects_circle(start, end, center, r);
+        prop_assert_eq!(forward, segment_intersects_circle(end, start, center, r));
+        prop_assert_eq!(forward, segment_intersects_circle(start + shift, end + shift, center + shift, r));
+    }
+
+    #[test]
+    fn hittable_entity_agrees_with_segment_intersection(start in point(), end in point(), center in point(), r in radius()) {
+        let entity = HittableEntity::from_position_and_radius(center, r);
+        prop_assert_eq!(entity.intersects_with(&start, &end), segment_intersects_circle(start, end, center, r));
+    }
+
+    #[test]
+    fn first_contact_is_on_the_circle_and_nothing_earlier_is_inside(start in point(), end in point(), center in point(), r in radius()) {
+        match segment_circle_contact(start, end, center, r) {
+            Some(t) => {
+                prop_assert!((0.0..=1.0).contains(&t));
+                let contact = lerp(start, end, t);
+                if t > 0.0 {
+                    prop_assert!((contact.distance_to(&center) - r).abs() < 1e-6 * (1.0 + r));
+                } else {
+                    prop_assert!(start.distance_to(&center) <= r + EPS);
+                }
+                let before = (0..SAMPLES)
+                    .map(|i| t * i as f64 / SAMPLES as f64)
+                    .map(|s| lerp(start, end, s).distance_to(&center))
+                    .fold(f64::INFINITY, f64::min);
+                prop_assert!(t == 0.0 || before >= r - EPS);
+            }
+            None => prop_assert!(sampled_distance(start, end, center) >= r - EPS),
+        }
+    }
+
+    #[test]
+    fn contact_agrees_with_intersection(start in point(), end in point(), center in point(), r in radius()) {
+        let contact = segment_circle_contact(start, end, center, r);
+        let intersects = segment_intersects_circle(start, end, center, r);
+        prop_assert_eq!(contact.is_some(), intersects);
+        if let Some(p) = segment_circle_intersection(start, end, center, r) {
+            prop_assert!(p.distance_to(&center) <= r + EPS);
+        }
+    }
+
+    #[test]
+    fn moving_circles_touch_at_contact_time(p1 in point(), v1 in velocity(), r1 in radius(), p2 in point(), v2 in velocity(), r2 in radius()) {
+        let horizon = 10.0;
+        let distance_at = |t: f64| (p1 + v1 * t).distance_to(&(p2 + v2 * t));
+        let sampled = (0..=SAMPLES)
+            .map(|i| horizon * i as f64 / SAMPLES as f64)
+            .map(distance_at)
+            .fold(f64::INFINITY, f64::min);
+
+        match moving_circles_contact_time(p1, v1, r1, p2, v2, r2) {
+            Some(t) => {
+                prop_assert!(t >= 0.0);
+                if t > 0.0 {
+                    prop_assert!((distance_at(t) - (r1 + r2)).abs() < 1e-6 * (1.0 + r1 + r2));
+                    let earliest = (0..SAMPLES)
+                        .map(|i| t * i as f64 / SAMPLES as f64)
+                        .map(distance_at)
+                        .fold(f64::INFINITY, f64::min);
+                    prop_assert!(earliest >= r1 + r2 - EPS);
+                }
+            }
+            None => prop_assert!(sampled >= r1 + r2 - EPS),
+        }
+    }
+
+    #[test]
+    fn tangent_points_touch_the_circle(p in point(), center in point(), r in radius()) {
+        match circle_tangent_points(p, center, r) {
+            Some((t1, t2)) => {
+                for t in [t1, t2] {
+                    prop_assert!((t.distance_to(&center) - r).abs() < 1e-6 * (1.0 + r));
+                    // the radius is perpendicular to the tangent line
+                    let cos = (t - center).normalize().dot_product(&(p - t).normalize());
+                    prop_assert!(cos.abs() < 1e-6);
+                }
+            }
+            None => prop_assert!(p.distance_to(&center) <= r),
+        }
+    }
+
+    #[test]
+    fn ray_cast_finds_the_first_obstacle(
+        origin in point(),
+        direction in velocity(),
+        max_distance in 1.0..100.0,
+        obstacles in obstacles(),
+    ) {
+        prop_assume!(direction.length() > EPS);
+        let end = origin + direction * (max_distance / direction.length());
+        match ray_cast(origin, direction, max_distance, obstacles.iter()) {
+            Some(hit) => {
+                prop_assert!(hit.distance >= 0.0 && hit.distance <= max_distance + EPS);
+                prop_assert!(hit.point.distance_to(&hit.obstacle.position) <= hit.obstacle.radius + 1e-6);
+                let closer = obstacles.iter()
+                    .filter_map(|o| segment_circle_contact(origin, end, o.position, o.radius))
+                    .any(|t| t * max_distance < hit.distance - EPS);
+                prop_assert!(!closer);
+            }
+            None => {
+                for o in obstacles.iter() {
+                    prop_assert!(!segment_intersects_circle(origin, end, o.position, o.radius));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn segments_pointing_in_negative_directions_are_handled() {
+    let center = Vec2::from_xy(0.0, 0.0);
+    let start = Vec2::from_xy(10.0, 10.0);
+    let end = Vec2::from_xy(-10.0, -10.0);
+    assert!(segment_intersects_circle(start, end, center, 1.0));
+    assert!(HittableEntity::from_position_and_radius(center, 1.0).intersects_with(&start, &end));
+    let contact = segment_circle_intersection(start, end, center, 1.0).unwrap();
+    assert!(contact.x > 0.0 && contact.y > 0.0);
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use ai_cup_22::model::*;
+use common::*;
+
+/// A unit never picks up ammo it would drop for an ally, and never drops ammo it would pick up.
+#[test]
+fn shared_ammo_is_never_picked_up() {
+    let constants = constants(vec![]);
+    let weapons = constants.weapons.len() as i32;
+    let strategy = ai_cup_22::MyStrategy::new(constants.clone());
+
+    for held in std::iter::once(None).chain((0..weapons).map(Some)) {
+        for w in 0..weapons {
+            if held == Some(w) {
+                continue;
+            }
+            let mut me = unit(1, ME, Vec2::zero(), held, &constants);
+            me.ammo[w as usize] = 1;
+            let mut ally = unit(2, ME, Vec2::from_xy(2.0, 0.0), Some(w), &constants);
+            ally.ammo[w as usize] = 0;
+
+            let dropped = strategy.shared_ammo(&me, &[&ally])
+                .is_some_and(|order| matches!(order.action_order, ActionOrder::DropAmmo { weapon_type_index, .. } if weapon_type_index == w));
+            let picked = strategy.loot_value(&me, &Item::Ammo { weapon_type_index: w, amount: 10 }) > 0.0;
+            assert_ne!(dropped, picked, "holding {:?}, ammo for {}", held, w);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use std::rc::Rc;
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+fn planner(obstacles: Vec<Obstacle>) -> (PathPlanner, Constants) {
+    let constants = constants(obstacles);
+    (PathPlanner::new(&constants, Rc::new(ObstacleGrid::new(&constants))), constants)
+}
+
+#[test]
+fn segment_through_an_obstacle_is_blocked() {
+    let (planner, _) = planner(vec![obstacle(1, Vec2::zero(), 2.0)]);
+
+    assert!(!planner.is_segment_clear(Vec2::from_xy(-10.0, 0.0), Vec2::from_xy(10.0, 0.0)));
+    assert!(planner.is_segment_clear(Vec2::from_xy(-10.0, 5.0), Vec2::from_xy(10.0, 5.0)));
+}
+
+#[test]
+fn unit_touching_an_obstacle_can_only_move_away() {
+    let (planner, constants) = planner(vec![obstacle(1, Vec2::zero(), 2.0)]);
+    let touching = Vec2::from_xy(2.0 + constants.unit_radius / 2.0, 0.0);
+
+    assert!(planner.is_segment_clear(touching, Vec2::from_xy(10.0, 0.0)));
+    assert!(!planner.is_segment_clear(touching, Vec2::from_xy(-10.0, 0.0)));
+}
+
+#[test]
+fn path_goes_around_an_obstacle() {
+    let (planner, _) = planner(vec![obstacle(1, Vec2::zero(), 2.0)]);
+    let (from, to) = (Vec2::from_xy(-10.0, 0.0), Vec2::from_xy(10.0, 0.0));
+    let path = planner.find_path(from, to).unwrap();
+
+    assert!(path.last().is_some_and(|last| last.distance_to(&to) < 1e-9));
+    let mut start = from;
+    for waypoint in path {
+        assert!(planner.is_segment_clear(start, waypoint), "{:?} to {:?}", start, waypoint);
+        start = waypoint;
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use ai_cup_22::codegame::ClientMessage;
+use ai_cup_22::*;
+use common::*;
+
+#[test]
+fn replayed_game_gives_the_recorded_orders() {
+    let config = ArenaConfig {
+        seed: 5,
+        max_ticks: 200,
+        ..ArenaConfig::default()
+    };
+    let path = record("orders", &config);
+
+    let mut playback = Playback::open(&path).unwrap();
+    playback.run(None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(playback.stats.ticks > 150, "{:?}", playback.stats);
+    assert_eq!(playback.stats.mismatched_ticks, Vec::<i32>::new());
+}
+
+#[test]
+fn game_played_with_other_behaviours_and_params_replays_with_them() {
+    let mut behaviours = BehaviourConfig::default();
+    behaviours.velocity.selector = Selector::Utility;
+    let mut params = StrategyParams::default();
+    params.set("logic.approach_speed", 0.5).unwrap();
+    let config = ArenaConfig {
+        seed: 8,
+        max_ticks: 200,
+        challenger_behaviours: Some(behaviours.clone()),
+        challenger_params: Some(params.clone()),
+        ..ArenaConfig::default()
+    };
+    let path = record("configured", &config);
+
+    let mut playback = Playback::open(&path).unwrap().with_behaviours(behaviours).with_params(params);
+    playback.run(None).unwrap();
+    let mut default_playback = Playback::open(&path).unwrap();
+    default_playback.run(None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(playback.stats.mismatched_ticks, Vec::<i32>::new());
+    assert!(!default_playback.stats.mismatched_ticks.is_empty());
+}
+
+#[test]
+fn replay_cut_inside_a_record_is_an_error() {
+    let config = ArenaConfig {
+        seed: 7,
+        max_ticks: 20,
+        ..ArenaConfig::default()
+    };
+    let path = record("truncated", &config);
+    let records = ReplayReader::open(&path).unwrap().map(Result::unwrap).count();
+
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 3).unwrap();
+    let read = ReplayReader::open(&path).unwrap().collect::<Vec<_>>();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.len(), records);
+    let error = read.last().unwrap().as_ref().unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn debug_updates_are_recorded_and_replayed() {
+    let config = ArenaConfig {
+        seed: 6,
+        max_ticks: 100,
+        debug_layers: Some(DebugLayers::only("RBT")),
+        ..ArenaConfig::default()
+    };
+    let path = record("debug", &config);
+
+    let records = ReplayReader::open(&path).unwrap().map(Result::unwrap).collect::<Vec<_>>();
+    let states = records.iter().filter(|r| matches!(r, ReplayRecord::DebugState(_))).count();
+    let drawn = records.iter().filter(|r| matches!(r, ReplayRecord::Client(ClientMessage::DebugMessage { .. }))).count();
+    assert!(states >= 100, "{} debug states", states);
+    assert!(drawn > 0);
+
+    let mut playback = Playback::open(&path).unwrap();
+    playback.run(None).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(playback.stats.ticks, 100);
+    assert_eq!(playback.stats.mismatched_ticks, Vec::<i32>::new());
+}
+
+#[test]
+fn rendered_frames_show_what_was_drawn() {
+    let config = ArenaConfig {
+        seed: 8,
+        max_ticks: 40,
+        preset: Preset::Round1,
+        debug_layers: Some(DebugLayers::only("RLT")),
+        ..ArenaConfig::default()
+    };
+    let path = record("render", &config);
+    let undrawn_path = record("render-undrawn", &ArenaConfig { debug_layers: None, ..config.clone() });
+    let render = RenderConfig { from_tick: 10, to_tick: 19, ..RenderConfig::default() };
+    let frames = render_replay(&path, &render).unwrap();
+    let undrawn = render_replay(&undrawn_path, &render).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&undrawn_path).unwrap();
+
+    assert_eq!(frames.iter().map(|(tick, _)| *tick).collect::<Vec<_>>(), (10..20).collect::<Vec<_>>());
+    // the same game, only with the debug drawings over it
+    for ((_, drawn), (_, plain)) in frames.iter().zip(undrawn.iter()) {
+        assert!(drawn.len() > plain.len());
+    }
+}
+
+#[test]
+fn replayed_debug_interface_without_states_answers_idle() {
+    let mut frame = vec![];
+    let mut debug = DebugInterface::replayed(&mut frame, vec![]);
+    let state = debug.get_state();
+
+    assert!(state.pressed_keys.is_empty());
+    assert_eq!(state.locked_unit, None);
+}
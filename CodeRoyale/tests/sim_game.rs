@@ -0,0 +1,34 @@
+mod common;
+
+use std::collections::HashMap;
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+fn dropping(weapon_type_index: i32, amount: i32) -> (SimGame, Constants) {
+    let constants = constants(vec![]);
+    let mut sim = SimGame::new(&game(vec![unit(1, ME, Vec2::zero(), Some(0), &constants)], vec![]));
+    sim.units[0].action = Some(Action { finish_tick: 0, action_type: ActionType::Looting });
+    sim.units[0].action_order = Some(ActionOrder::DropAmmo { weapon_type_index, amount });
+    (sim, constants)
+}
+
+#[test]
+fn dropped_ammo_becomes_loot() {
+    let (mut sim, constants) = dropping(0, 4);
+    sim.tick(&constants, &ObstacleGrid::new(&constants), &HashMap::new());
+
+    assert_eq!(sim.units[0].ammo[0], 6);
+    assert!(matches!(sim.loot.as_slice(), [Loot { item: Item::Ammo { weapon_type_index: 0, amount: 4 }, .. }]));
+}
+
+#[test]
+fn dropping_ammo_of_an_unknown_weapon_is_ignored() {
+    for weapon_type_index in [-1, 99] {
+        let (mut sim, constants) = dropping(weapon_type_index, 4);
+        sim.tick(&constants, &ObstacleGrid::new(&constants), &HashMap::new());
+
+        assert_eq!(sim.units[0].ammo[0], 10);
+        assert!(sim.loot.is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+const HORIZONS: [usize; 3] = [1, 10, 30];
+// mean position error allowed per tick of horizon, whatever the situation
+const POSITION_TOLERANCE_PER_TICK: f64 = 0.02;
+const DIRECTION_TOLERANCE_DEGREES: f64 = 1.0;
+
+/// Games played against the official local runner, see `tests/replays/README.md`.
+/// Arena games can't be used, `SimGame` shares its physics with `Simulator`.
+fn server_replays() -> Vec<PathBuf> {
+    let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/replays"));
+    let mut paths = std::fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "replay"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+#[ignore = "needs a game recorded from the official local runner in tests/replays"]
+fn simulator_does_not_drift_from_server_games() {
+    let replays = server_replays();
+    assert!(!replays.is_empty(), "no replays in tests/replays");
+
+    for path in replays {
+        let mut playback = Playback::open(&path).unwrap().with_validation(HORIZONS.to_vec());
+        playback.run(None).unwrap();
+
+        let validator = playback.validator().unwrap();
+        assert!(!validator.stats().is_empty(), "nothing was validated in {}", path.display());
+        let drifts = drifts(validator);
+        assert!(drifts.is_empty(), "{}: {}\n{}", path.display(), drifts.join("\n"), validator.report());
+    }
+}
+
+/// Every horizon and situation whose mean error is over the tolerance.
+fn drifts(validator: &SimValidator) -> Vec<String> {
+    let mut drifts = vec![];
+    for ((horizon, situation), stats) in validator.stats() {
+        if stats.position_mean() > POSITION_TOLERANCE_PER_TICK * *horizon as f64 {
+            drifts.push(format!("{:?} position drifts by {:.4} over {} ticks", situation, stats.position_mean(), horizon));
+        }
+        if stats.direction_mean().to_degrees() > DIRECTION_TOLERANCE_DEGREES {
+            drifts.push(format!("{:?} direction drifts by {:.4} degrees over {} ticks", situation, stats.direction_mean().to_degrees(), horizon));
+        }
+    }
+    drifts
+}
+
+/// Feeds the validator a unit told to stand still that is seen `step` further along x every tick.
+fn validate_standing_unit(step: f64, aim: f64, ticks: i32) -> SimValidator {
+    let constants = constants(vec![]);
+    let mut validator = SimValidator::new(constants.clone(), HORIZONS.to_vec());
+    let order = Order { unit_orders: HashMap::new() };
+    for tick in 0..ticks {
+        let mut me = unit(1, ME, Vec2::from_xy(step * tick as f64, 0.0), Some(0), &constants);
+        me.aim = aim;
+        let mut game = game(vec![me], vec![]);
+        game.current_tick = tick;
+        validator.update(&game, &order);
+    }
+    validator
+}
+
+#[test]
+fn predictions_are_aggregated_per_horizon_and_situation() {
+    let validator = validate_standing_unit(0.0, 0.0, 40);
+
+    let keys = validator.stats().keys().copied().collect::<Vec<_>>();
+    assert_eq!(keys, HORIZONS.iter().map(|h| (*h, Situation::Free)).collect::<Vec<_>>());
+    for ((horizon, _), stats) in validator.stats() {
+        // a prediction for every tick that has a whole window behind it
+        assert_eq!(stats.samples, 40 - horizon);
+        assert!(stats.position_max < 1e-9, "{}", validator.report());
+    }
+    assert!(drifts(&validator).is_empty());
+}
+
+#[test]
+fn aiming_units_are_validated_separately() {
+    let validator = validate_standing_unit(0.0, 0.5, 15);
+
+    let keys = validator.stats().keys().copied().collect::<Vec<_>>();
+    assert_eq!(keys, vec![(1, Situation::Aiming), (10, Situation::Aiming)]);
+}
+
+#[test]
+fn drift_over_the_tolerance_is_reported() {
+    // 0.05 a tick is over the 0.02 allowed, yet close enough not to look like a respawn
+    let validator = validate_standing_unit(0.05, 0.0, 40);
+
+    let stats = &validator.stats()[&(10, Situation::Free)];
+    assert!((stats.position_mean() - 0.5).abs() < 1e-9, "{}", validator.report());
+    assert_eq!(drifts(&validator).len(), HORIZONS.len());
+}
@@ -0,0 +1,49 @@
+use std::sync::OnceLock;
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use proptest::prelude::*;
+
+/// A generated arena's constants, obstacles all over the zone.
+fn constants() -> &'static Constants {
+    static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+    CONSTANTS.get_or_init(|| Arena::new(&ArenaConfig::default()).constants)
+}
+
+fn point() -> impl Strategy<Value = Vec2> {
+    // a bit past the zone too, the grid clamps those to its edge cells
+    (-120.0..120.0, -120.0..120.0).prop_map(|(x, y)| Vec2::from_xy(x, y))
+}
+
+fn ids<'a>(obstacles: impl IntoIterator<Item = &'a Obstacle>) -> Vec<i32> {
+    let mut ids = obstacles.into_iter().map(|o| o.id).collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids
+}
+
+proptest! {
+    #[test]
+    fn obstacles_near_match_brute_force(point in point(), distance in 0.0..30.0) {
+        let grid = ObstacleGrid::new(constants());
+        let expected = ids(constants().obstacles.iter().filter(|o| o.position.distance_to(&point) <= o.radius + distance));
+        prop_assert_eq!(ids(grid.near(point, distance)), expected);
+    }
+
+    #[test]
+    fn obstacles_along_segment_match_brute_force(start in point(), end in point(), radius in 0.0..3.0) {
+        let grid = ObstacleGrid::new(constants());
+        let expected = ids(constants().obstacles.iter().filter(|o| segment_intersects_circle(start, end, o.position, o.radius + radius)));
+        prop_assert_eq!(ids(grid.along_segment(start, end, radius)), expected);
+    }
+
+    #[test]
+    fn points_within_match_brute_force(points in prop::collection::vec(point(), 0..60), center in point(), radius in 0.0..40.0) {
+        let index = PointIndex::new(points.iter().enumerate().map(|(id, p)| (id as i32, *p)));
+        let mut found = index.within(center, radius);
+        found.sort_unstable();
+        let expected = points.iter().enumerate()
+            .filter(|(_, p)| p.distance_to(&center) <= radius)
+            .map(|(id, _)| id as i32)
+            .collect::<Vec<_>>();
+        prop_assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+const BOW: i32 = 2;
+
+fn spawning(position: Vec2, constants: &Constants) -> Unit {
+    Unit { remaining_spawn_time: Some(3.0), ..unit(1, ME, position, None, constants) }
+}
+
+fn bow_loot(id: i32, position: Vec2) -> Loot {
+    Loot { id, position, item: Item::Weapon { type_index: BOW }, seen_on_tick: 0 }
+}
+
+/// Flies the unit toward its landing spot for `ticks`, returning the spot picked on every tick.
+fn fly(strategy: &mut MyStrategy, game: &mut Game, constants: &Constants, ticks: i32) -> Vec<Vec2> {
+    let mut spots = vec![];
+    for _ in 0..ticks {
+        strategy.get_order(game, None);
+        let spot = strategy.landing_spot_of(1).expect("nowhere to land");
+        spots.push(spot);
+        let me = &mut game.units[0];
+        let step = constants.spawn_movement_speed / constants.ticks_per_second;
+        me.position = me.position + (spot - me.position).clamp(step);
+        me.remaining_spawn_time = me.remaining_spawn_time.map(|t| t - 1.0 / constants.ticks_per_second);
+        game.current_tick += 1;
+    }
+    spots
+}
+
+#[test]
+fn landing_spot_holds_while_flying_to_it() {
+    let constants = constants(vec![]);
+    let mut game = game(vec![spawning(Vec2::zero(), &constants)], vec![]);
+    game.loot = vec![bow_loot(100, Vec2::from_xy(12.0, 7.0))];
+    let mut strategy = MyStrategy::new(constants.clone());
+
+    let spots = fly(&mut strategy, &mut game, &constants, 30);
+    assert!(spots.iter().all(|spot| spot.distance_to(&spots[0]) < 1e-9), "{:?}", spots);
+}
+
+#[test]
+fn landing_spot_moves_away_from_an_enemy_showing_up() {
+    let constants = constants(vec![]);
+    let mut game = game(vec![spawning(Vec2::zero(), &constants)], vec![]);
+    game.loot = vec![bow_loot(100, Vec2::from_xy(12.0, 7.0))];
+    let mut strategy = MyStrategy::new(constants.clone());
+    let before = fly(&mut strategy, &mut game, &constants, 5)[4];
+
+    let enemy = unit(2, ENEMY, before + Vec2::from_xy(5.0, 0.0), Some(BOW), &constants);
+    game.units.push(enemy.clone());
+    let after = fly(&mut strategy, &mut game, &constants, 1)[0];
+    assert!(after.distance_to(&enemy.position) > before.distance_to(&enemy.position), "{:?} then {:?}", before, after);
+}
@@ -0,0 +1,43 @@
+use ai_cup_22::*;
+
+#[test]
+fn written_params_read_back() {
+    let mut params = StrategyParams::default();
+    for (idx, (name, min, max)) in PARAM_RANGES.iter().enumerate() {
+        // somewhere inside the range, different for every parameter
+        params.set(name, min + (max - min) * (idx + 1) as f64 / (PARAM_RANGES.len() + 1) as f64).unwrap();
+    }
+    let read: StrategyParams = params.to_string().parse().unwrap();
+    for (name, _, _) in PARAM_RANGES {
+        assert_eq!(read.get(name), params.get(name), "{}", name);
+    }
+    assert_eq!(read.to_string(), params.to_string());
+}
+
+#[test]
+fn every_ranged_param_can_be_set_and_read() {
+    let mut params = StrategyParams::default();
+    for (name, min, max) in PARAM_RANGES {
+        let default = params.get(name).unwrap_or_else(|| panic!("{} can't be read", name));
+        assert!((*min..=*max).contains(&default), "{} defaults to {} outside {}..={}", name, default, min, max);
+        params.set(name, *max).unwrap();
+        assert_eq!(params.get(name), Some(*max), "{}", name);
+    }
+}
+
+#[test]
+fn missing_params_keep_their_defaults_and_counts_are_rounded() {
+    let params: StrategyParams = "# tuned\n[sustain]\nretreat_below = 0.25\n\n[spawn]\nloadout_length = 2.6\n".parse().unwrap();
+    assert_eq!(params.sustain.retreat_below, 0.25);
+    assert_eq!(params.spawn.loadout_length, 3);
+    assert_eq!(params.loot_zone_fraction, StrategyParams::default().loot_zone_fraction);
+}
+
+#[test]
+fn malformed_params_name_the_line() {
+    let error = |text: &str| text.parse::<StrategyParams>().unwrap_err();
+    assert_eq!(error("retreat_below = 0.3"), "line 1: expected a section header");
+    assert_eq!(error("[sustain]\nretreat_below 0.3"), "line 2: expected `name = value`, got \"retreat_below 0.3\"");
+    assert_eq!(error("[sustain]\nretreat_below = low"), "line 2: can't parse value \"low\"");
+    assert_eq!(error("[sustain]\nbravery = 1"), "line 2: unknown parameter \"sustain.bravery\"");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+const BOW: i32 = 2;
+
+fn assert_seconds(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} instead of {}", actual, expected);
+}
+
+/// Me at the origin, an enemy archer far off to the left who shot at me from the right.
+fn projectile_game(me: Unit, velocity: Vec2, constants: &Constants) -> Game {
+    let enemy = unit(2, ENEMY, Vec2::from_xy(-90.0, 0.0), Some(BOW), constants);
+    let projectile = projectile(10, &enemy, Vec2::from_xy(10.0, 0.0), velocity, 1.0);
+    game(vec![me, enemy], vec![projectile])
+}
+
+#[test]
+fn projectile_hits_when_it_reaches_the_unit() {
+    let constants = constants(vec![]);
+    let me = unit(1, ME, Vec2::zero(), None, &constants);
+    let game = projectile_game(me.clone(), Vec2::from_xy(-40.0, 0.0), &constants);
+    // flies 9 of the 10 units to the unit's edge
+    assert_seconds(strategy_after(&constants, &game).seconds_until_projectile_hit(&me), 9.0 / 40.0);
+}
+
+#[test]
+fn unit_moving_into_a_projectile_is_hit_sooner() {
+    let constants = constants(vec![]);
+    let me = Unit { velocity: Vec2::from_xy(10.0, 0.0), ..unit(1, ME, Vec2::zero(), None, &constants) };
+    let game = projectile_game(me.clone(), Vec2::from_xy(-40.0, 0.0), &constants);
+    assert_seconds(strategy_after(&constants, &game).seconds_until_projectile_hit(&me), 9.0 / 50.0);
+}
+
+#[test]
+fn projectiles_that_miss_or_are_blocked_never_hit() {
+    let constants_without = constants(vec![]);
+    let me = unit(1, ME, Vec2::zero(), None, &constants_without);
+    let passing_by = projectile_game(me.clone(), Vec2::from_xy(0.0, 40.0), &constants_without);
+    assert!(strategy_after(&constants_without, &passing_by).seconds_until_projectile_hit(&me).is_infinite());
+
+    let constants_with_wall = constants(vec![obstacle(0, Vec2::from_xy(5.0, 0.0), 1.0)]);
+    let blocked = projectile_game(me.clone(), Vec2::from_xy(-40.0, 0.0), &constants_with_wall);
+    assert!(strategy_after(&constants_with_wall, &blocked).seconds_until_projectile_hit(&me).is_infinite());
+
+    let falling_short = projectile_game(me.clone(), Vec2::from_xy(-4.0, 0.0), &constants_without);
+    assert!(strategy_after(&constants_without, &falling_short).seconds_until_projectile_hit(&me).is_infinite());
+}
+
+#[test]
+fn own_projectiles_never_hit() {
+    let constants = constants(vec![]);
+    let me = unit(1, ME, Vec2::zero(), Some(BOW), &constants);
+    let shot = projectile(10, &me, Vec2::from_xy(10.0, 0.0), Vec2::from_xy(-40.0, 0.0), 1.0);
+    let game = game(vec![me.clone()], vec![shot]);
+    assert!(strategy_after(&constants, &game).seconds_until_projectile_hit(&me).is_infinite());
+}
+
+fn hurt(me: Unit) -> Unit {
+    Unit { health: 30.0, ..me }
+}
+
+fn plan(constants: &Constants, me: &Unit, others: Vec<Unit>) -> SustainPlan {
+    let game = game(std::iter::once(me.clone()).chain(others).collect(), vec![]);
+    strategy_after(constants, &game).sustain_plan_for(me, &game)
+}
+
+#[test]
+fn fights_when_nobody_is_around() {
+    let constants = constants(vec![]);
+    let me = hurt(unit(1, ME, Vec2::zero(), Some(BOW), &constants));
+    let plan = plan(&constants, &me, vec![]);
+    assert!(plan.safe_for.is_infinite());
+    assert!(matches!(plan.decision, SustainDecision::Fight), "{:?}", plan);
+}
+
+#[test]
+fn drinks_when_nobody_can_shoot_in_time() {
+    let constants = constants(vec![]);
+    let me = Unit { shield_potions: 2, ..hurt(unit(1, ME, Vec2::zero(), Some(BOW), &constants)) };
+    let plan = plan(&constants, &me, vec![]);
+    assert!(matches!(plan.decision, SustainDecision::Drink), "{:?}", plan);
+}
+
+#[test]
+fn regenerates_when_the_enemy_is_a_walk_away() {
+    let constants = constants(vec![]);
+    let me = hurt(unit(1, ME, Vec2::zero(), Some(BOW), &constants));
+    // 3s of walking into range, 1s of aiming and 1s of flight, regenerating takes 3.5s
+    let enemy = unit(2, ENEMY, Vec2::from_xy(-70.0, 0.0), Some(BOW), &constants);
+    let plan = plan(&constants, &me, vec![enemy]);
+    assert_seconds(plan.safe_for, 5.0);
+    assert!(matches!(plan.decision, SustainDecision::Regenerate), "{:?}", plan);
+}
+
+#[test]
+fn retreats_behind_cover_when_about_to_be_shot() {
+    let enemy_position = Vec2::from_xy(-30.0, 0.0);
+    let wall = obstacle(0, Vec2::from_xy(4.0, 0.0), 2.0);
+
+    let open_field = constants(vec![]);
+    let me = hurt(unit(1, ME, Vec2::zero(), Some(BOW), &open_field));
+    let plan_in_open_field = plan(&open_field, &me, vec![unit(2, ENEMY, enemy_position, Some(BOW), &open_field)]);
+    assert!(matches!(plan_in_open_field.decision, SustainDecision::Fight), "{:?}", plan_in_open_field);
+
+    let with_wall = constants(vec![wall.clone()]);
+    let plan_with_wall = plan(&with_wall, &me, vec![unit(2, ENEMY, enemy_position, Some(BOW), &with_wall)]);
+    match plan_with_wall.decision {
+        SustainDecision::Retreat { cover } => assert!(cover.x > wall.position.x, "{:?} isn't behind the wall", cover),
+        decision => panic!("{:?}", decision),
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use ai_cup_22::debugging::{Color, DebugData};
+use ai_cup_22::model::*;
+use ai_cup_22::*;
+use common::*;
+
+fn view() -> SvgView {
+    SvgView { center: Vec2::from_xy(10.0, 20.0), radius: 5.0 }
+}
+
+fn render(data: Vec<DebugData>) -> String {
+    render_svg(&[&data], &view())
+}
+
+/// Numbers in the `d` of the only path drawn.
+fn path_numbers(svg: &str) -> Vec<f64> {
+    let d = svg.split("d=\"").nth(1).unwrap().split('"').next().unwrap();
+    d.split_whitespace().filter_map(|token| token.parse().ok()).collect()
+}
+
+fn end_of_path(svg: &str) -> Vec2 {
+    let numbers = path_numbers(svg);
+    Vec2::from_xy(numbers[numbers.len() - 2], numbers[numbers.len() - 1])
+}
+
+fn assert_near(actual: Vec2, expected: Vec2) {
+    assert!(actual.distance_to(&expected) < 1e-3, "{:?} instead of {:?}", actual, expected);
+}
+
+#[test]
+fn frame_shows_the_view_flipped_up() {
+    let svg = render(vec![]);
+    assert!(svg.contains("viewBox=\"5.000 -25.000 10.000 10.000\""), "{}", svg);
+    assert!(svg.contains("<g transform=\"scale(1,-1)\">"));
+}
+
+#[test]
+fn pies_and_arcs_sweep_counterclockwise() {
+    let center = Vec2::from_xy(10.0, 20.0);
+    let quarter = render(vec![DebugData::Arc { position: center, radius: 2.0, width: 0.1, start_angle: 0.0, end_angle: std::f64::consts::FRAC_PI_2, color: Color::red() }]);
+    assert_eq!(quarter.matches("A ").count(), 2);
+    assert_near(end_of_path(&quarter), center + Vec2::from_xy(0.0, 2.0));
+
+    // from 3/4 of a turn to 1/4 wraps around through 0
+    let wrapped = render(vec![DebugData::Pie { position: center, radius: 2.0, start_angle: 1.5 * std::f64::consts::PI, end_angle: std::f64::consts::FRAC_PI_2, color: Color::red() }]);
+    let numbers = path_numbers(&wrapped);
+    // center, start, then the middle of the first half arc is at angle 0
+    assert_near(Vec2::from_xy(numbers[numbers.len() - 9], numbers[numbers.len() - 8]), center + Vec2::from_xy(2.0, 0.0));
+    assert_near(end_of_path(&wrapped), center + Vec2::from_xy(0.0, 2.0));
+}
+
+#[test]
+fn full_turn_pie_is_a_whole_circle() {
+    let center = Vec2::from_xy(10.0, 20.0);
+    for (start_angle, end_angle) in [(0.0, 2.0 * std::f64::consts::PI), (-std::f64::consts::PI, std::f64::consts::PI)] {
+        let svg = render(vec![DebugData::Pie { position: center, radius: 3.0, start_angle, end_angle, color: Color::green() }]);
+        let numbers = path_numbers(&svg);
+        let start = center + Vec2::from_length_and_angle(3.0, start_angle);
+        // out to the edge, half way around to the opposite side, and back
+        assert_near(Vec2::from_xy(numbers[numbers.len() - 9], numbers[numbers.len() - 8]), center - (start - center));
+        assert_near(end_of_path(&svg), start);
+    }
+}
+
+#[test]
+fn things_out_of_view_are_left_out() {
+    let svg = render(vec![
+        DebugData::Circle { position: Vec2::from_xy(10.0, 20.0), radius: 1.0, color: Color::red() },
+        DebugData::Circle { position: Vec2::from_xy(100.0, 20.0), radius: 1.0, color: Color::red() },
+        // big enough to reach into the view
+        DebugData::Ring { position: Vec2::from_xy(30.0, 20.0), radius: 16.0, width: 0.1, color: Color::red() },
+        DebugData::Segment { first_end: Vec2::from_xy(100.0, 0.0), second_end: Vec2::from_xy(200.0, 0.0), width: 0.1, color: Color::red() },
+    ]);
+    assert_eq!(svg.matches("<circle").count(), 2, "{}", svg);
+    assert_eq!(svg.matches("<polyline").count(), 1);
+}
+
+#[test]
+fn texts_are_escaped_and_upright() {
+    let svg = render(vec![DebugData::PlacedText {
+        position: Vec2::from_xy(10.0, 20.0),
+        text: "<a & b>".to_string(),
+        alignment: Vec2::from_xy(0.0, 0.5),
+        size: 1.0,
+        color: Color::red(),
+    }]);
+    assert!(svg.contains(">&lt;a &amp; b&gt;</text>"), "{}", svg);
+    assert!(svg.contains("translate(10.000,20.000) scale(1,-1)"));
+    assert!(svg.contains("text-anchor=\"start\""));
+}
+
+#[test]
+fn gradients_get_their_own_ids() {
+    let gradient = DebugData::GradientCircle { position: Vec2::from_xy(10.0, 20.0), radius: 1.0, inner_color: Color::red(), outer_color: Color::green() };
+    let layer = [gradient];
+    let svg = render_svg(&[&layer, &layer], &view());
+    assert!(svg.contains("id=\"g1\"") && svg.contains("url(#g1)"), "{}", svg);
+    assert!(svg.contains("id=\"g2\"") && svg.contains("url(#g2)"));
+}
+
+#[test]
+fn scene_draws_the_zone_and_every_unit() {
+    let constants = constants(vec![obstacle(0, Vec2::from_xy(5.0, 5.0), 2.0)]);
+    let me = unit(1, ME, Vec2::zero(), None, &constants);
+    let enemy = unit(2, ENEMY, Vec2::from_xy(10.0, 0.0), None, &constants);
+    let game = game(vec![me, enemy], vec![]);
+    let svg = render_svg(&[&scene(&game, &constants)], &SvgView::zone(&game));
+    // the zone and the next one, the obstacle and the two units
+    assert_eq!(svg.matches("<circle").count(), 5, "{}", svg);
+    assert!(svg.contains(">1</text>") && svg.contains(">2</text>"));
+}
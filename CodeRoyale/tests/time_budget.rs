@@ -0,0 +1,68 @@
+use std::time::Duration;
+use ai_cup_22::*;
+
+fn config() -> TimeBudgetConfig {
+    TimeBudgetConfig {
+        game_limit: Duration::from_secs(10),
+        expected_ticks: 1000,
+        max_tick_time: Duration::from_millis(50),
+        safety_factor: 0.8,
+        ..TimeBudgetConfig::default()
+    }
+}
+
+fn assert_millis(duration: Duration, millis: f64) {
+    assert!((duration.as_secs_f64() * 1000.0 - millis).abs() < 1e-6, "{:?} instead of {}ms", duration, millis);
+}
+
+#[test]
+fn even_share_of_the_game() {
+    assert_millis(config().tick_allowance(0, Duration::ZERO), 8.0);
+}
+
+#[test]
+fn share_of_what_is_left() {
+    // 2s left over 500 ticks
+    assert_millis(config().tick_allowance(500, Duration::from_secs(8)), 3.2);
+    // saved up time goes to the remaining ticks
+    assert_millis(config().tick_allowance(500, Duration::from_secs(3)), 11.2);
+}
+
+#[test]
+fn capped_at_max_tick_time() {
+    assert_millis(config().tick_allowance(999, Duration::ZERO), 40.0);
+}
+
+#[test]
+fn longer_game_than_expected() {
+    assert_millis(config().tick_allowance(5000, Duration::from_millis(9990)), 8.0);
+    assert_millis(config().tick_allowance(5000, Duration::from_secs(11)), 0.0);
+}
+
+#[test]
+fn tick_is_split_between_units() {
+    let mut budget = TimeBudget::new(TimeBudgetConfig { max_tick_time: Duration::from_millis(4), ..config() });
+    budget.start_tick();
+    let tick = budget.remaining_in_tick();
+    assert!(tick <= Duration::from_micros(3200));
+
+    budget.start_unit(4);
+    let unit = budget.remaining_for_unit();
+    assert!(unit <= tick / 4, "{:?} of {:?}", unit, tick);
+    assert!(unit >= tick / 8, "{:?} of {:?}", unit, tick);
+
+    // the last unit gets whatever is left
+    budget.start_unit(1);
+    assert!(budget.remaining_for_unit() > tick / 2);
+}
+
+#[test]
+fn reproducible_deadline_counts_ticks() {
+    let mut budget = TimeBudget::new(TimeBudgetConfig::reproducible());
+    budget.start_tick();
+    budget.start_unit(3);
+    let deadline = budget.deadline(Duration::from_millis(5));
+    assert!(!deadline.reached(0));
+    assert!(deadline.reached(usize::MAX));
+    assert!(matches!(deadline, Deadline::SimulatedTicks(_)));
+}
@@ -0,0 +1,137 @@
+use std::sync::OnceLock;
+use ai_cup_22::codegame::ServerMessage;
+use ai_cup_22::trans::{Trans, TransError};
+use ai_cup_22::*;
+use proptest::prelude::*;
+
+fn encode(value: &impl Trans) -> Vec<u8> {
+    let mut bytes = vec![];
+    value.write_to(&mut bytes).unwrap();
+    bytes
+}
+
+fn decode<T: Trans>(mut bytes: &[u8]) -> Result<T, TransError> {
+    T::read_from(&mut bytes)
+}
+
+/// Encoded server messages of a short local game, constants and views with units, loot and everything.
+fn recorded_messages() -> &'static [Vec<u8>] {
+    static MESSAGES: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    MESSAGES.get_or_init(|| {
+        let path = std::env::temp_dir().join(format!("royale-trans-{}.replay", std::process::id()));
+        let config = ArenaConfig {
+            seed: 3,
+            max_ticks: 20,
+            ..ArenaConfig::default()
+        };
+        Arena::new(&config)
+            .with_recorder(ReplayWriter::create(&path).unwrap())
+            .run();
+        let messages = ReplayReader::open(&path).unwrap()
+            .filter_map(|record| match record.unwrap() {
+                ReplayRecord::Server(message) => Some(encode(&*message)),
+                ReplayRecord::Client(_) | ReplayRecord::DebugState(_) => None,
+            })
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        messages
+    })
+}
+
+#[test]
+fn recorded_messages_round_trip() {
+    assert!(recorded_messages().len() > 2);
+    for bytes in recorded_messages() {
+        let message: ServerMessage = decode(bytes).unwrap();
+        assert_eq!(&encode(&message), bytes);
+    }
+}
+
+#[test]
+fn unknown_message_tag() {
+    let result = decode::<ServerMessage>(&encode(&7));
+    assert!(matches!(result, Err(TransError::UnexpectedTag { type_name: "ServerMessage", tag: 7 })), "{:?}", result);
+}
+
+#[test]
+fn bool_other_than_zero_or_one() {
+    let mut bytes = encode(&ServerMessage::GetOrder { player_view: empty_game(), debug_available: true });
+    *bytes.last_mut().unwrap() = 2;
+    let result = decode::<ServerMessage>(&bytes);
+    assert!(matches!(result, Err(TransError::InvalidBool(2))), "{:?}", result);
+}
+
+#[test]
+fn string_with_invalid_utf8() {
+    let mut bytes = encode(&2);
+    bytes.extend([0xc3, 0x28]);
+    let result = decode::<String>(&bytes);
+    assert!(matches!(result, Err(TransError::InvalidUtf8(_))), "{:?}", result);
+}
+
+#[test]
+fn negative_length() {
+    let result = decode::<Vec<i32>>(&encode(&-1));
+    assert!(matches!(result, Err(TransError::InvalidLength(-1))), "{:?}", result);
+}
+
+#[test]
+fn huge_length_is_truncated_input() {
+    let mut bytes = encode(&i32::MAX);
+    bytes.extend(b"short");
+    let result = decode::<String>(&bytes);
+    assert!(matches!(result, Err(TransError::Truncated)), "{:?}", result);
+    let result = decode::<Vec<f64>>(&bytes);
+    assert!(matches!(result, Err(TransError::Truncated)), "{:?}", result);
+}
+
+#[test]
+fn decoding_errors_survive_io_errors() {
+    let error = std::io::Error::from(TransError::InvalidBool(5));
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(matches!(error.get_ref().and_then(|e| e.downcast_ref()), Some(TransError::InvalidBool(5))));
+    assert_eq!(std::io::Error::from(TransError::Truncated).kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+fn empty_game() -> model::Game {
+    model::Game {
+        my_id: 0,
+        players: vec![],
+        current_tick: 0,
+        units: vec![],
+        loot: vec![],
+        projectiles: vec![],
+        zone: model::Zone {
+            current_center: model::Vec2::from_xy(0.0, 0.0),
+            current_radius: 100.0,
+            next_center: model::Vec2::from_xy(0.0, 0.0),
+            next_radius: 90.0,
+        },
+        sounds: vec![],
+    }
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = decode::<ServerMessage>(&bytes);
+    }
+
+    #[test]
+    fn cut_messages_are_truncated(message in any::<prop::sample::Index>(), cut in any::<prop::sample::Index>()) {
+        let bytes = message.get(recorded_messages());
+        let cut = cut.index(bytes.len());
+        let result = decode::<ServerMessage>(&bytes[..cut]);
+        prop_assert!(matches!(result, Err(TransError::Truncated)), "{:?}", result);
+    }
+
+    #[test]
+    fn flipped_bytes_never_panic(message in any::<prop::sample::Index>(), flips in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8)) {
+        let mut bytes = message.get(recorded_messages()).clone();
+        for (position, value) in flips {
+            let len = bytes.len();
+            bytes[position.index(len)] = value;
+        }
+        let _ = decode::<ServerMessage>(&bytes);
+    }
+}
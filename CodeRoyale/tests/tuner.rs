@@ -0,0 +1,46 @@
+use ai_cup_22::*;
+
+#[test]
+fn spsa_steps_toward_the_better_side() {
+    let position = [0.5, 0.5, 0.5];
+    let delta = [1.0, -1.0, 1.0];
+    // plus scored better, every coordinate moves along its perturbation
+    let step = spsa_step(&position, &delta, 12.0, 8.0, 0.1, 0.2);
+    let shift = 0.1 * (12.0 - 8.0) / 10.0 / (2.0 * 0.2);
+    for ((x, start), d) in step.iter().zip(position).zip(delta) {
+        assert!((x - (start + shift * d)).abs() < 1e-12, "{:?}", step);
+    }
+    // minus scored better, the other way
+    let back = spsa_step(&position, &delta, 8.0, 12.0, 0.1, 0.2);
+    assert!(back[0] < 0.5 && back[1] > 0.5 && back[2] < 0.5, "{:?}", back);
+    // no difference, no step
+    assert_eq!(spsa_step(&position, &delta, 5.0, 5.0, 0.1, 0.2), position.to_vec());
+}
+
+#[test]
+fn spsa_steps_stay_within_the_perturbation_and_the_range() {
+    let huge = spsa_step(&[0.5], &[1.0], 1000.0, 0.0, 10.0, 0.05);
+    assert!((huge[0] - 0.55).abs() < 1e-12, "{:?}", huge);
+    let edge = spsa_step(&[0.98, 0.01], &[1.0, -1.0], 1000.0, 0.0, 10.0, 0.05);
+    assert_eq!(edge, vec![1.0, 0.0]);
+}
+
+/// What the tuner scores on plays out the same every time, whichever thread the match runs on.
+#[test]
+fn matches_replay_the_same() {
+    let mut params = StrategyParams::default();
+    params.set("sustain.retreat_below", 0.3).unwrap();
+    let config = ArenaConfig {
+        seed: 11,
+        max_ticks: 120,
+        preset: Preset::Round1,
+        // close enough for a fight to break out
+        initial_zone_radius: 25.0,
+        challenger_params: Some(params),
+        ..ArenaConfig::default()
+    };
+    let result = Arena::new(&config).run();
+    assert!(result.teams.iter().any(|t| t.damage > 0.0), "nothing happened in {:?}", result);
+    let replayed = std::thread::spawn(move || Arena::new(&config).run()).join().unwrap();
+    assert_eq!(format!("{:?}", replayed), format!("{:?}", result));
+}